@@ -157,6 +157,20 @@ fn bench_different_configurations(c: &mut Criterion) {
     });
 }
 
+fn bench_fast_preset(c: &mut Criterion) {
+    let graph_1000 = create_large_dag(1000, 2);
+    let layout_default = DagreLayout::new();
+    let layout_fast = DagreLayout::with_options(LayoutOptions::fast());
+
+    c.bench_function("layout_1000_nodes_default", |b| {
+        b.iter(|| layout_default.compute(black_box(&graph_1000)))
+    });
+
+    c.bench_function("layout_1000_nodes_fast", |b| {
+        b.iter(|| layout_fast.compute(black_box(&graph_1000)))
+    });
+}
+
 fn bench_crossing_reduction(c: &mut Criterion) {
     let crossing_heavy = create_wide_dag(15, 6);
     
@@ -184,6 +198,7 @@ criterion_group!(
     bench_medium_graphs,
     bench_large_graphs,
     bench_different_configurations,
+    bench_fast_preset,
     bench_crossing_reduction
 );
 criterion_main!(benches);
\ No newline at end of file
@@ -67,17 +67,18 @@ fn time_layout(name: &str, graph: Graph<String, ()>) {
     let layout = DagreLayout::new();
     
     // Warm up
-    let _ = layout.compute(&graph);
+    let _ = layout.compute(&graph).expect("Layout failed");
     
     // Time multiple runs
     let runs = 3;
     let mut times = Vec::new();
     
+    let mut phases = None;
+    
     for _ in 0..runs {
-        let start = Instant::now();
-        let result = layout.compute(&graph);
-        let duration = start.elapsed();
-        times.push(duration);
+        let (result, metrics) = layout.compute_with_metrics(&graph).expect("Layout failed");
+        times.push(metrics.total);
+        phases = Some(metrics);
         
         // Quick validation
         assert_eq!(result.node_positions.len(), graph.node_count());
@@ -89,6 +90,15 @@ fn time_layout(name: &str, graph: Graph<String, ()>) {
     
     println!("  Average: {:.2}ms", avg.as_secs_f64() * 1000.0);
     println!("  Range: {:.2}ms - {:.2}ms", min.as_secs_f64() * 1000.0, max.as_secs_f64() * 1000.0);
+    if let Some(metrics) = phases {
+        println!(
+            "  Last run: ranking {:.2}ms, crossings {:.2}ms ({} sweeps), coordinates {:.2}ms",
+            metrics.ranking.as_secs_f64() * 1000.0,
+            metrics.crossing_reduction.as_secs_f64() * 1000.0,
+            metrics.iterations,
+            metrics.coordinate_assignment.as_secs_f64() * 1000.0
+        );
+    }
     println!();
 }
 
@@ -116,7 +126,7 @@ fn main() {
     });
     
     let start = Instant::now();
-    let result = layout_ltr.compute(&graph);
+    let result = layout_ltr.compute(&graph).expect("Layout failed");
     let duration = start.elapsed();
     
     println!("  {} nodes in {:.2}ms", result.node_positions.len(), duration.as_secs_f64() * 1000.0);
@@ -1,6 +1,5 @@
 use dagrers::{DagreLayout, LayoutOptions, RankDir};
-use petgraph::{Graph, graph::NodeIndex};
-use std::collections::{HashMap, HashSet};
+use petgraph::Graph;
 
 /// Comprehensive test suite to verify layout correctness
 struct LayoutTester {
@@ -16,53 +15,12 @@ impl LayoutTester {
 
     /// Test that all layout invariants are satisfied
     fn test_layout_invariants<N, E>(&self, graph: &Graph<N, E>) -> Result<(), String> {
-        let result = self.layout_engine.compute(graph);
+        let result = self.layout_engine.compute(graph).map_err(|e| e.to_string())?;
 
-        // 1. All nodes must have positions
-        if result.node_positions.len() != graph.node_count() {
-            return Err(format!(
-                "Position count mismatch: got {}, expected {}",
-                result.node_positions.len(),
-                graph.node_count()
-            ));
-        }
-
-        // 2. All nodes must appear in exactly one layer
-        let mut nodes_in_layers = HashSet::new();
-        for layer in &result.layers {
-            for &node in layer {
-                if !nodes_in_layers.insert(node) {
-                    return Err(format!("Node {:?} appears in multiple layers", node));
-                }
-            }
-        }
-
-        if nodes_in_layers.len() != graph.node_count() {
-            return Err(format!(
-                "Layer node count mismatch: got {}, expected {}",
-                nodes_in_layers.len(),
-                graph.node_count()
-            ));
-        }
-
-        // 3. Verify hierarchical ordering (edges only go forward in layers)
-        let node_to_layer = self.create_node_to_layer_map(&result.layers);
-        for edge in graph.edge_indices() {
-            if let Some((source, target)) = graph.edge_endpoints(edge) {
-                let source_layer = node_to_layer[&source];
-                let target_layer = node_to_layer[&target];
-                
-                if source_layer >= target_layer {
-                    return Err(format!(
-                        "Edge violates hierarchy: {:?} (layer {}) -> {:?} (layer {})",
-                        source, source_layer, target, target_layer
-                    ));
-                }
-            }
-        }
-
-        // 4. Verify coordinate consistency with layout direction
-        self.verify_coordinate_consistency(&result, &node_to_layer)?;
+        // 1-4. Positions, layer membership, edge direction and rank coordinates
+        self.layout_engine
+            .validate_layout(graph, &result, 0.01)
+            .map_err(|e| e.to_string())?;
 
         // 5. Verify dimensions are reasonable
         if result.width <= 0.0 || result.height <= 0.0 {
@@ -74,91 +32,6 @@ impl LayoutTester {
 
         Ok(())
     }
-
-    fn create_node_to_layer_map(&self, layers: &[Vec<NodeIndex>]) -> HashMap<NodeIndex, usize> {
-        let mut map = HashMap::new();
-        for (layer_idx, layer) in layers.iter().enumerate() {
-            for &node in layer {
-                map.insert(node, layer_idx);
-            }
-        }
-        map
-    }
-
-    fn verify_coordinate_consistency(
-        &self,
-        result: &dagrers::LayoutResult,
-        node_to_layer: &HashMap<NodeIndex, usize>,
-    ) -> Result<(), String> {
-        match self.layout_engine.options.rank_dir {
-            RankDir::TopToBottom => {
-                // Y coordinates should increase with layer depth
-                for (&node, &layer) in node_to_layer {
-                    let pos = result.node_positions[&node];
-                    let expected_y = layer as f32 * self.layout_engine.options.rank_sep;
-                    
-                    if (pos.1 - expected_y).abs() > 0.01 {
-                        return Err(format!(
-                            "Y coordinate inconsistent: node {:?} at ({}, {}), expected y={}",
-                            node, pos.0, pos.1, expected_y
-                        ));
-                    }
-                }
-            }
-            RankDir::LeftToRight => {
-                // X coordinates should increase with layer depth
-                for (&node, &layer) in node_to_layer {
-                    let pos = result.node_positions[&node];
-                    let expected_x = layer as f32 * self.layout_engine.options.rank_sep;
-                    
-                    if (pos.0 - expected_x).abs() > 0.01 {
-                        return Err(format!(
-                            "X coordinate inconsistent: node {:?} at ({}, {}), expected x={}",
-                            node, pos.0, pos.1, expected_x
-                        ));
-                    }
-                }
-            }
-        }
-        Ok(())
-    }
-
-    /// Count edge crossings between two adjacent layers
-    fn count_crossings(&self, graph: &Graph<impl std::fmt::Debug, impl std::fmt::Debug>, upper_layer: &[NodeIndex], lower_layer: &[NodeIndex]) -> usize {
-        let lower_positions: HashMap<NodeIndex, usize> = lower_layer
-            .iter()
-            .enumerate()
-            .map(|(pos, &node)| (node, pos))
-            .collect();
-        
-        let mut crossings = 0;
-        
-        for (i, &node1) in upper_layer.iter().enumerate() {
-            for &node2 in upper_layer.iter().skip(i + 1) {
-                let node1_connections: Vec<usize> = graph
-                    .neighbors(node1)
-                    .filter_map(|n| lower_positions.get(&n))
-                    .copied()
-                    .collect();
-                
-                let node2_connections: Vec<usize> = graph
-                    .neighbors(node2)
-                    .filter_map(|n| lower_positions.get(&n))
-                    .copied()
-                    .collect();
-                
-                for &pos1 in &node1_connections {
-                    for &pos2 in &node2_connections {
-                        if pos1 > pos2 {
-                            crossings += 1;
-                        }
-                    }
-                }
-            }
-        }
-        
-        crossings
-    }
 }
 
 // Test case generators
@@ -215,7 +88,7 @@ fn create_pathological_cases() -> Vec<(&'static str, Graph<&'static str, ()>)> {
     // Case 3: Star pattern (one node connects to many)
     let mut star = Graph::new();
     let center = star.add_node("center");
-    for i in 1..=10 {
+    for _ in 1..=10 {
         let leaf = star.add_node("leaf");
         star.add_edge(center, leaf, ());
     }
@@ -224,7 +97,7 @@ fn create_pathological_cases() -> Vec<(&'static str, Graph<&'static str, ()>)> {
     // Case 4: Long chain
     let mut chain = Graph::new();
     let mut prev = chain.add_node("start");
-    for i in 1..=20 {
+    for _ in 1..=20 {
         let current = chain.add_node("node");
         chain.add_edge(prev, current, ());
         prev = current;
@@ -283,12 +156,8 @@ fn run_test_suite() {
         let crossing_graph = create_problematic_crossing_graph();
         match tester.test_layout_invariants(&crossing_graph) {
             Ok(_) => {
-                let result = tester.layout_engine.compute(&crossing_graph);
-                let total_crossings: usize = (0..result.layers.len().saturating_sub(1))
-                    .map(|i| tester.count_crossings(&crossing_graph, &result.layers[i], &result.layers[i + 1]))
-                    .sum();
-                
-                println!("  ✓ Crossing Optimization (crossings: {})", total_crossings);
+                let result = tester.layout_engine.compute(&crossing_graph).expect("Layout failed");
+                println!("  ✓ Crossing Optimization (crossings: {})", result.crossing_count);
                 passed_tests += 1;
             },
             Err(e) => {
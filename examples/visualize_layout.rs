@@ -1,134 +1,8 @@
-use dagrers::{DagreLayout, LayoutOptions, LayoutResult, RankDir};
-use petgraph::{Graph, graph::NodeIndex};
-use std::collections::HashMap;
+use dagrers::render::{SvgStyle, render_svg};
+use dagrers::{DagreLayout, LayoutOptions, RankDir};
+use petgraph::Graph;
 use std::fs;
 
-/// Simple SVG generator for visualizing graph layouts
-struct SvgRenderer {
-    width: f32,
-    height: f32,
-    padding: f32,
-}
-
-impl SvgRenderer {
-    fn new(width: f32, height: f32) -> Self {
-        Self {
-            width: width + 100.0, // Add padding
-            height: height + 100.0,
-            padding: 50.0,
-        }
-    }
-
-    fn render_layout<N: std::fmt::Display, E>(
-        &self,
-        graph: &Graph<N, E>,
-        layout: &LayoutResult,
-        title: &str,
-    ) -> String {
-        let mut svg = format!(
-            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
-<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">
-<style>
-.node {{ fill: #e1f5fe; stroke: #0277bd; stroke-width: 2; }}
-.node-text {{ font-family: Arial, sans-serif; font-size: 14px; text-anchor: middle; dominant-baseline: middle; }}
-.edge {{ stroke: #424242; stroke-width: 1.5; marker-end: url(#arrowhead); }}
-.title {{ font-family: Arial, sans-serif; font-size: 18px; font-weight: bold; text-anchor: middle; }}
-.layer-line {{ stroke: #e0e0e0; stroke-width: 1; stroke-dasharray: 5,5; }}
-</style>
-<defs>
-<marker id=\"arrowhead\" markerWidth=\"10\" markerHeight=\"7\" refX=\"9\" refY=\"3.5\" orient=\"auto\">
-<polygon points=\"0 0, 10 3.5, 0 7\" fill=\"#424242\"/>
-</marker>
-</defs>
-",
-            self.width, self.height
-        );
-
-        // Title
-        svg.push_str(&format!(
-            "<text x=\"{}\" y=\"25\" class=\"title\">{}</text>",
-            self.width / 2.0,
-            title
-        ));
-
-        // Draw layer separator lines
-        for (i, layer) in layout.layers.iter().enumerate() {
-            if layer.is_empty() {
-                continue;
-            }
-            
-            let first_pos = layout.node_positions[&layer[0]];
-            let y = first_pos.1 + self.padding;
-            
-            svg.push_str(&format!(
-                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" class=\"layer-line\" />",
-                self.padding,
-                y,
-                self.width - self.padding,
-                y
-            ));
-            
-            svg.push_str(&format!(
-                "<text x=\"{}\" y=\"{}\" class=\"node-text\" style=\"fill: #757575; font-size: 12px;\">Layer {}</text>",
-                15.0,
-                y - 5.0,
-                i
-            ));
-        }
-
-        // Draw edges first (so they appear behind nodes)
-        for edge in graph.edge_indices() {
-            if let Some((source, target)) = graph.edge_endpoints(edge) {
-                if let (Some(&(x1, y1)), Some(&(x2, y2))) = (
-                    layout.node_positions.get(&source),
-                    layout.node_positions.get(&target),
-                ) {
-                    svg.push_str(&format!(
-                        "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" class=\"edge\" />",
-                        x1 + self.padding,
-                        y1 + self.padding,
-                        x2 + self.padding,
-                        y2 + self.padding
-                    ));
-                }
-            }
-        }
-
-        // Draw nodes
-        for (node_idx, &(x, y)) in &layout.node_positions {
-            let node_data = &graph[*node_idx];
-            
-            // Node circle
-            svg.push_str(&format!(
-                "<circle cx=\"{}\" cy=\"{}\" r=\"20\" class=\"node\" />",
-                x + self.padding,
-                y + self.padding
-            ));
-            
-            // Node label
-            svg.push_str(&format!(
-                "<text x=\"{}\" y=\"{}\" class=\"node-text\">{}</text>",
-                x + self.padding,
-                y + self.padding,
-                node_data
-            ));
-        }
-
-        // Layout info
-        svg.push_str(&format!(
-            "<text x=\"10\" y=\"{}\" class=\"node-text\" style=\"fill: #757575; font-size: 12px;\">Nodes: {} | Layers: {} | Size: {:.0}×{:.0}</text>",
-            self.height - 10.0,
-            graph.node_count(),
-            layout.layers.len(),
-            layout.width,
-            layout.height
-        ));
-
-        svg.push_str("</svg>");
-        svg
-    }
-}
-
 fn create_simple_chain() -> Graph<&'static str, ()> {
     let mut graph = Graph::new();
     let a = graph.add_node("A");
@@ -235,7 +109,7 @@ fn test_layout(name: &str, graph: Graph<impl std::fmt::Display, ()>, options: La
     println!("Testing layout: {}", name);
     
     let layout_engine = DagreLayout::with_options(options.clone());
-    let result = layout_engine.compute(&graph);
+    let result = layout_engine.compute(&graph).expect("Layout failed");
     
     // Print layout statistics
     println!("  Nodes: {}", graph.node_count());
@@ -245,16 +119,10 @@ fn test_layout(name: &str, graph: Graph<impl std::fmt::Display, ()>, options: La
     println!("  Direction: {:?}", options.rank_dir);
     
     // Check layout properties
-    let mut total_crossings = 0;
-    for i in 0..result.layers.len().saturating_sub(1) {
-        let crossings = count_crossings(&graph, &result.layers[i], &result.layers[i + 1]);
-        total_crossings += crossings;
-    }
-    println!("  Edge crossings: {}", total_crossings);
+    println!("  Edge crossings: {}", result.crossing_count);
     
     // Generate SVG
-    let renderer = SvgRenderer::new(result.width, result.height);
-    let svg_content = renderer.render_layout(&graph, &result, name);
+    let svg_content = render_svg(&graph, &result, &SvgStyle::default());
     
     // Create output directory if it doesn't exist
     let output_dir = "examples/output";
@@ -268,47 +136,6 @@ fn test_layout(name: &str, graph: Graph<impl std::fmt::Display, ()>, options: La
     println!();
 }
 
-fn count_crossings<N, E>(
-    graph: &Graph<N, E>, 
-    upper_layer: &[NodeIndex], 
-    lower_layer: &[NodeIndex]
-) -> usize {
-    let lower_positions: HashMap<NodeIndex, usize> = lower_layer
-        .iter()
-        .enumerate()
-        .map(|(pos, &node)| (node, pos))
-        .collect();
-    
-    let mut crossings = 0;
-    
-    for (i, &node1) in upper_layer.iter().enumerate() {
-        for &node2 in upper_layer.iter().skip(i + 1) {
-            let node1_connections: Vec<usize> = graph
-                .neighbors(node1)
-                .filter_map(|n| lower_positions.get(&n))
-                .copied()
-                .collect();
-            
-            let node2_connections: Vec<usize> = graph
-                .neighbors(node2)
-                .filter_map(|n| lower_positions.get(&n))
-                .copied()
-                .collect();
-            
-            // Count inversions between the two sets of connections
-            for &pos1 in &node1_connections {
-                for &pos2 in &node2_connections {
-                    if pos1 > pos2 {
-                        crossings += 1;
-                    }
-                }
-            }
-        }
-    }
-    
-    crossings
-}
-
 fn main() {
     println!("Dagrers Layout Visualization Tests");
     println!("==================================");
@@ -0,0 +1,527 @@
+use crate::derived::DerivedGraph;
+use crate::layout::{DagreLayout, LayoutResult};
+use petgraph::prelude::*;
+use std::collections::{HashSet, VecDeque};
+
+/// How `compute` picks the edges to reverse when the graph has cycles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CycleRemoval {
+    /// Reverse the back edges of a depth-first search starting at the sources
+    #[default]
+    DepthFirst,
+    /// Reverse the feedback arc set found by [`greedy_fas`], usually fewer edges
+    Greedy,
+}
+
+impl DagreLayout {
+    /// Layout engine and acyclic copy of the graph, if the graph has cycles
+    ///
+    /// The edges picked by `cycle_removal` are reversed in the copy. The
+    /// depth-first search starts from the sources in index order, then from
+    /// the `cycle_roots` and, without sources, the node picked by
+    /// `cycle_start`, and continues with the remaining unvisited nodes in
+    /// index order. Self-loops are kept,
+    /// they do not affect ranking. Edges into `min_rank_nodes` and out of
+    /// `max_rank_nodes` are reversed before looking for cycles, so pinned
+    /// nodes only have edges pointing away from their rank. Returns the
+    /// reversed edges in input indices.
+    pub(crate) fn removing_cycles<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Option<(DagreLayout, DerivedGraph, HashSet<EdgeIndex>)> {
+        let pinned = self.edges_against_pins(graph);
+        let reversed = match pinned.is_empty() {
+            true => self.cycle_edges(graph),
+            false => {
+                let flipped = DerivedGraph::new(graph, |edge| match pinned.contains(&edge.id()) {
+                    true => Some((edge.target(), edge.source())),
+                    false => Some((edge.source(), edge.target())),
+                });
+                let mut reversed = pinned;
+                for edge in self.cycle_edges(&flipped.graph) {
+                    reversed.insert(flipped.original(edge));
+                }
+                reversed
+            }
+        };
+        if reversed.is_empty() {
+            return None;
+        }
+        let derived = DerivedGraph::new(graph, |edge| match reversed.contains(&edge.id()) {
+            true => Some((edge.target(), edge.source())),
+            false => Some((edge.source(), edge.target())),
+        });
+        Some((self.for_derived(&derived), derived, reversed))
+    }
+
+    /// Edges picked by `cycle_removal` to break every cycle of the graph
+    fn cycle_edges<N, E>(&self, graph: &DiGraph<N, E>) -> HashSet<EdgeIndex> {
+        match self.options.cycle_removal {
+            CycleRemoval::DepthFirst => back_edges(graph, &self.search_roots(graph)),
+            CycleRemoval::Greedy => greedy_fas(graph),
+        }
+    }
+
+    /// Edges pointing into a node of `min_rank_nodes` or out of a node of
+    /// `max_rank_nodes` from a node outside the set, none with `fixed_ranks`
+    fn edges_against_pins<N, E>(&self, graph: &DiGraph<N, E>) -> HashSet<EdgeIndex> {
+        if !self.options.fixed_ranks.is_empty() {
+            return HashSet::new();
+        }
+        let (min, max) = (&self.options.min_rank_nodes, &self.options.max_rank_nodes);
+        graph
+            .edge_references()
+            .filter(|edge| {
+                let (source, target) = (edge.source(), edge.target());
+                (min.contains(&target) && !min.contains(&source))
+                    || (max.contains(&source) && !max.contains(&target) && !min.contains(&source))
+            })
+            .map(|edge| edge.id())
+            .collect()
+    }
+}
+
+/// Edges that point back to a node on the current path of a depth-first search
+pub(crate) fn back_edges<N, E>(graph: &DiGraph<N, E>, roots: &[NodeIndex]) -> HashSet<EdgeIndex> {
+    let mut on_path = vec![false; graph.node_count()];
+    let mut visited = vec![false; graph.node_count()];
+    let mut back = HashSet::new();
+
+    for &root in roots {
+        if visited[root.index()] {
+            continue;
+        }
+        visited[root.index()] = true;
+        on_path[root.index()] = true;
+        let mut stack = vec![(root, graph.edges_directed(root, Outgoing))];
+
+        while let Some((node, edges)) = stack.last_mut() {
+            let node = *node;
+            let Some(edge) = edges.next() else {
+                on_path[node.index()] = false;
+                stack.pop();
+                continue;
+            };
+            let target = edge.target();
+            if target == node {
+                continue;
+            }
+            if on_path[target.index()] {
+                back.insert(edge.id());
+            } else if !visited[target.index()] {
+                visited[target.index()] = true;
+                on_path[target.index()] = true;
+                stack.push((target, graph.edges_directed(target, Outgoing)));
+            }
+        }
+    }
+    back
+}
+
+/// Nodes that no path from a node without incoming edges reaches
+///
+/// Self-loops count as incoming edges, so a node whose only predecessor is
+/// itself is unreachable as well.
+pub(crate) fn unreachable_nodes<N, E>(graph: &DiGraph<N, E>) -> HashSet<NodeIndex> {
+    let mut reached = vec![false; graph.node_count()];
+    let mut stack: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|&node| graph.neighbors_directed(node, Incoming).next().is_none())
+        .collect();
+    for node in &stack {
+        reached[node.index()] = true;
+    }
+    while let Some(node) = stack.pop() {
+        for next in graph.neighbors_directed(node, Outgoing) {
+            if !reached[next.index()] {
+                reached[next.index()] = true;
+                stack.push(next);
+            }
+        }
+    }
+    graph
+        .node_indices()
+        .filter(|node| !reached[node.index()])
+        .collect()
+}
+
+/// Edges whose reversal makes the graph acyclic, found with the Eades–Lin–Smyth heuristic
+///
+/// Nodes are removed one at a time to build a sequence: sinks go to the
+/// back, sources to the front, and otherwise the node with the largest
+/// surplus of outgoing over incoming edges goes to the front. Sinks and
+/// sources are taken in the order they turned up, lowest index first at the
+/// start; among nodes with the same surplus the one whose surplus changed
+/// last is taken, the lowest index while none has. Edges pointing backward
+/// in the sequence are returned; self-loops never are. Runs in
+/// O(V + E) time.
+pub fn greedy_fas<N, E>(graph: &DiGraph<N, E>) -> HashSet<EdgeIndex> {
+    let count = graph.node_count();
+    let mut incoming = vec![0usize; count];
+    let mut outgoing = vec![0usize; count];
+    for edge in graph.edge_references() {
+        if edge.source() != edge.target() {
+            outgoing[edge.source().index()] += 1;
+            incoming[edge.target().index()] += 1;
+        }
+    }
+
+    let mut removed = vec![false; count];
+    let mut sinks = VecDeque::new();
+    let mut sources = VecDeque::new();
+    let mut buckets = SurplusBuckets::new(count, graph.edge_count());
+    for node in graph.node_indices() {
+        let index = node.index();
+        if outgoing[index] == 0 {
+            sinks.push_back(node);
+        } else if incoming[index] == 0 {
+            sources.push_back(node);
+        }
+    }
+    for node in graph.node_indices().rev() {
+        let index = node.index();
+        if outgoing[index] > 0 && incoming[index] > 0 {
+            buckets.insert(index, outgoing[index] as isize - incoming[index] as isize);
+        }
+    }
+
+    let mut front = Vec::with_capacity(count);
+    let mut back = Vec::new();
+    loop {
+        let node = if let Some(sink) = sinks.pop_front() {
+            if removed[sink.index()] {
+                continue;
+            }
+            back.push(sink);
+            sink
+        } else if let Some(source) = sources.pop_front() {
+            if removed[source.index()] {
+                continue;
+            }
+            front.push(source);
+            source
+        } else if let Some(index) = buckets.pop_max() {
+            let node = NodeIndex::new(index);
+            front.push(node);
+            node
+        } else {
+            break;
+        };
+
+        removed[node.index()] = true;
+        for edge in graph.edges_directed(node, Outgoing) {
+            let target = edge.target().index();
+            if edge.target() == node || removed[target] {
+                continue;
+            }
+            incoming[target] -= 1;
+            if buckets.remove(target) {
+                if incoming[target] == 0 {
+                    sources.push_back(edge.target());
+                } else {
+                    let surplus = outgoing[target] as isize - incoming[target] as isize;
+                    buckets.insert(target, surplus);
+                }
+            }
+        }
+        for edge in graph.edges_directed(node, Incoming) {
+            let source = edge.source().index();
+            if edge.source() == node || removed[source] {
+                continue;
+            }
+            outgoing[source] -= 1;
+            if outgoing[source] == 0 {
+                // A source losing its last outgoing edge is a sink first
+                buckets.remove(source);
+                sinks.push_back(edge.source());
+            } else if buckets.remove(source) {
+                let surplus = outgoing[source] as isize - incoming[source] as isize;
+                buckets.insert(source, surplus);
+            }
+        }
+    }
+
+    let mut position = vec![0; count];
+    for (order, node) in front.into_iter().chain(back.into_iter().rev()).enumerate() {
+        position[node.index()] = order;
+    }
+    graph
+        .edge_references()
+        .filter(|edge| position[edge.source().index()] > position[edge.target().index()])
+        .map(|edge| edge.id())
+        .collect()
+}
+
+/// Nodes with both incoming and outgoing edges, in doubly linked lists by surplus
+///
+/// The surplus of outgoing over incoming edges lies within the edge count
+/// either way, so the lists are indexed by surplus plus the edge count.
+struct SurplusBuckets {
+    heads: Vec<Option<usize>>,
+    next: Vec<Option<usize>>,
+    previous: Vec<Option<usize>>,
+    bucket: Vec<Option<usize>>,
+    /// No list above this one holds a node
+    max: usize,
+}
+
+impl SurplusBuckets {
+    fn new(nodes: usize, edges: usize) -> Self {
+        Self {
+            heads: vec![None; 2 * edges + 1],
+            next: vec![None; nodes],
+            previous: vec![None; nodes],
+            bucket: vec![None; nodes],
+            max: 0,
+        }
+    }
+
+    /// Put a node at the head of the list of its surplus
+    fn insert(&mut self, node: usize, surplus: isize) {
+        let bucket = (surplus + (self.heads.len() / 2) as isize) as usize;
+        self.next[node] = self.heads[bucket];
+        self.previous[node] = None;
+        if let Some(head) = self.heads[bucket] {
+            self.previous[head] = Some(node);
+        }
+        self.heads[bucket] = Some(node);
+        self.bucket[node] = Some(bucket);
+        self.max = self.max.max(bucket);
+    }
+
+    /// Take a node out of its list, returning whether it was in one
+    fn remove(&mut self, node: usize) -> bool {
+        let Some(bucket) = self.bucket[node].take() else {
+            return false;
+        };
+        match self.previous[node] {
+            Some(previous) => self.next[previous] = self.next[node],
+            None => self.heads[bucket] = self.next[node],
+        }
+        if let Some(next) = self.next[node] {
+            self.previous[next] = self.previous[node];
+        }
+        true
+    }
+
+    /// Take the head of the list with the largest surplus
+    fn pop_max(&mut self) -> Option<usize> {
+        loop {
+            if let Some(node) = self.heads[self.max] {
+                self.remove(node);
+                return Some(node);
+            }
+            if self.max == 0 {
+                return None;
+            }
+            self.max -= 1;
+        }
+    }
+}
+
+/// Point the routes of edges reversed for ranking back from source to target
+///
+/// Edges that a later stage reversed again end up laid out in their own
+/// direction and are no longer reported as reversed.
+pub(crate) fn restore_reversed(result: &mut LayoutResult, reversed: HashSet<EdgeIndex>) {
+    for edge in reversed {
+        if let Some(points) = result.edge_points.get_mut(&edge) {
+            points.reverse();
+        }
+        if !result.reversed_edges.remove(&edge) {
+            result.reversed_edges.insert(edge);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::ComponentPacking;
+    use crate::layout::{CycleStart, LayoutOptions};
+    use petgraph::Graph;
+    use petgraph::algo::is_cyclic_directed;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_cycles_are_broken_and_restored() {
+        // A single edge closes a long cycle
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..5).map(|i| graph.add_node(i)).collect();
+        for pair in nodes.windows(2) {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+        let closing = graph.add_edge(nodes[4], nodes[0], ());
+
+        let layout = DagreLayout::new();
+        let result = layout.compute(&graph).unwrap();
+        let layers: Vec<_> = nodes.iter().map(|&node| vec![node]).collect();
+        assert_eq!(result.layers, layers);
+        assert_eq!(result.reversed_edges, HashSet::from([closing]));
+        let points = &result.edge_points[&closing];
+        assert_eq!(points[0], result.node_positions[&nodes[4]]);
+        assert_eq!(*points.last().unwrap(), result.node_positions[&nodes[0]]);
+        assert_eq!(points.len(), 5);
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+        assert_eq!(result.unreachable_nodes, nodes.iter().copied().collect());
+
+        // Independent cycles, one of them without any source to start from
+        let mut graph = Graph::new();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let c = graph.add_node("c");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        let ca = graph.add_edge(c, a, ());
+        let entry = graph.add_node("entry");
+        let p = graph.add_node("p");
+        let q = graph.add_node("q");
+        let r = graph.add_node("r");
+        graph.add_edge(entry, p, ());
+        graph.add_edge(p, q, ());
+        graph.add_edge(q, r, ());
+        let rp = graph.add_edge(r, p, ());
+        graph.add_edge(b, b, ());
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            cycle_start: CycleStart::HighestOutDegree,
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(result.reversed_edges, HashSet::from([ca, rp]));
+        assert_eq!(result.edge_points.len(), graph.edge_count());
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+        let mut first = result.layers[0].clone();
+        first.sort();
+        assert_eq!(first, vec![a, entry]);
+        assert_eq!(result.unreachable_nodes, HashSet::from([a, b, c]));
+    }
+
+    #[test]
+    fn test_unreachable_nodes_are_reported_for_the_input_graph() {
+        // A pair of nodes pointing at each other feeds a tail, next to a
+        // chain from a source and a node whose only edge is a self-loop
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..7).map(|i| graph.add_node(i)).collect();
+        for (source, target) in [(0, 1), (1, 0), (1, 2), (3, 4), (4, 5), (6, 6)] {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+        let unreachable = HashSet::from([nodes[0], nodes[1], nodes[2], nodes[6]]);
+
+        let result = DagreLayout::new().compute(&graph).unwrap();
+        assert_eq!(result.unreachable_nodes, unreachable);
+        assert_eq!(result.node_positions.len(), graph.node_count());
+
+        // Stages laying out a rewritten graph report it for the input
+        let layout = DagreLayout::with_options(LayoutOptions {
+            merge_bidirectional: true,
+            pack_components: Some(ComponentPacking::Row { gap: 20.0 }),
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(result.unreachable_nodes, unreachable);
+
+        // Without cycles every node is reached
+        graph.remove_edge(EdgeIndex::new(5));
+        graph.remove_edge(EdgeIndex::new(1));
+        let result = DagreLayout::new().compute(&graph).unwrap();
+        assert!(result.unreachable_nodes.is_empty());
+    }
+
+    #[test]
+    fn test_greedy_fas_reverses_fewer_edges() {
+        // Dense graph mostly running from lower to higher indices, with a
+        // few edges against that direction
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..10).map(|i| graph.add_node(i)).collect();
+        for i in 0..10 {
+            for j in i + 1..10 {
+                if (i * 7 + j * 3) % 5 == 0 {
+                    graph.add_edge(nodes[j], nodes[i], ());
+                } else {
+                    graph.add_edge(nodes[i], nodes[j], ());
+                }
+            }
+        }
+
+        let reversed = |cycle_removal| {
+            let layout = DagreLayout::with_options(LayoutOptions {
+                cycle_removal,
+                ..Default::default()
+            });
+            let (_, derived, reversed) = layout.removing_cycles(&graph).unwrap();
+            assert!(!is_cyclic_directed(&derived.graph));
+            reversed
+        };
+        let greedy = reversed(CycleRemoval::Greedy);
+        let depth_first = reversed(CycleRemoval::DepthFirst);
+        assert_eq!(greedy, greedy_fas(&graph));
+        assert!(greedy.len() * 2 < depth_first.len());
+    }
+
+    #[test]
+    fn test_greedy_fas_breaks_every_cycle_of_a_large_graph() {
+        // Interleaved cycles of every length with self-loops, multi-edges
+        // and a tail, enough nodes that a quadratic pass would show
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..5000).map(|i| graph.add_node(i)).collect();
+        for i in 0..5000 {
+            graph.add_edge(nodes[i], nodes[(i * 37 + 11) % 5000], ());
+            graph.add_edge(nodes[i], nodes[(i + 1) % 5000], ());
+            if i % 7 == 0 {
+                graph.add_edge(nodes[i], nodes[i], ());
+                graph.add_edge(nodes[(i + 3) % 5000], nodes[i], ());
+            }
+        }
+        let tail = graph.add_node(5000);
+        graph.add_edge(nodes[0], tail, ());
+
+        let reversed = greedy_fas(&graph);
+        let mut acyclic = Graph::new();
+        for node in graph.node_indices() {
+            acyclic.add_node(node);
+        }
+        for edge in graph.edge_references() {
+            let (source, target) = (edge.source(), edge.target());
+            if reversed.contains(&edge.id()) {
+                assert_ne!(source, target);
+                acyclic.add_edge(target, source, ());
+            } else if source != target {
+                acyclic.add_edge(source, target, ());
+            }
+        }
+        assert!(!is_cyclic_directed(&acyclic));
+    }
+
+    #[test]
+    fn test_cycle_roots_pick_the_start_of_each_cycle() {
+        // Two pure cycles, so no component has a source
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..7).map(|i| graph.add_node(i)).collect();
+        let mut edges = Vec::new();
+        for cycle in [&nodes[..4], &nodes[4..]] {
+            for (i, &node) in cycle.iter().enumerate() {
+                let next = cycle[(i + 1) % cycle.len()];
+                edges.push(graph.add_edge(node, next, ()));
+            }
+        }
+
+        // Each cycle starts at its lowest index, closing at its last node
+        let layout = DagreLayout::new();
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(result.reversed_edges, HashSet::from([edges[3], edges[6]]));
+        assert_eq!(result.layers[0], [nodes[0], nodes[4]]);
+
+        // A preferred root of the second cycle leaves the first one alone
+        let layout = DagreLayout::with_options(LayoutOptions {
+            cycle_roots: vec![nodes[5]],
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(result.reversed_edges, HashSet::from([edges[3], edges[4]]));
+        assert_eq!(result.node_ranks[&nodes[5]], 0);
+        assert_eq!(result.node_ranks[&nodes[4]], 2);
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+    }
+}
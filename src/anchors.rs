@@ -0,0 +1,142 @@
+use crate::layout::RankDir;
+use petgraph::prelude::*;
+use std::collections::HashMap;
+
+/// Points on a node's border where its edges attach
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnchorSlots {
+    /// Incoming edges with their anchor on the border facing the previous layer,
+    /// ordered along the cross axis by the position each edge arrives from
+    pub incoming: Vec<(EdgeIndex, (f32, f32))>,
+    /// Outgoing edges with their anchor on the border facing the next layer,
+    /// ordered along the cross axis by the position each edge leaves toward
+    pub outgoing: Vec<(EdgeIndex, (f32, f32))>,
+}
+
+/// Distribute the edges of every node over evenly spaced slots on its border
+///
+/// Incoming edges attach to the side facing the previous layer, outgoing
+/// edges to the side facing the next one. Slots are ordered by the
+/// neighboring point of each edge's route so attached edges do not cross
+/// at the node. The first and last point of every route in `edge_points`
+/// is moved onto its slot.
+pub(crate) fn assign_anchor_slots<N, E>(
+    graph: &DiGraph<N, E>,
+    positions: &HashMap<NodeIndex, (f32, f32)>,
+    sizes: &HashMap<NodeIndex, (f32, f32)>,
+    rank_dir: RankDir,
+    edge_points: &mut HashMap<EdgeIndex, Vec<(f32, f32)>>,
+) -> HashMap<NodeIndex, AnchorSlots> {
+    let mut anchors = HashMap::new();
+
+    for node in graph.node_indices() {
+        let center = positions[&node];
+        let (width, height) = sizes.get(&node).copied().unwrap_or((0.0, 0.0));
+        let (cross_size, rank_size) = (
+            rank_dir.cross((width, height)),
+            rank_dir.rank((width, height)),
+        );
+
+        let slots_for = |direction: Direction| {
+            // Sort by the route point next to this node, the edge's first bend
+            let mut edges: Vec<(EdgeIndex, f32)> = graph
+                .edges_directed(node, direction)
+                .map(|edge| {
+                    let points = &edge_points[&edge.id()];
+                    let neighbor = match direction {
+                        Incoming => points[points.len().saturating_sub(2)],
+                        Outgoing => points[1.min(points.len() - 1)],
+                    };
+                    (edge.id(), rank_dir.cross(neighbor))
+                })
+                .collect();
+            edges.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+            let border = match direction {
+                Incoming => rank_dir.rank(center) - rank_size * 0.5,
+                Outgoing => rank_dir.rank(center) + rank_size * 0.5,
+            };
+            let start = rank_dir.cross(center) - cross_size * 0.5;
+            let step = cross_size / (edges.len() + 1) as f32;
+
+            edges
+                .into_iter()
+                .enumerate()
+                .map(|(slot, (edge, _))| {
+                    let anchor = rank_dir.point(start + (slot + 1) as f32 * step, border);
+                    (edge, anchor)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let slots = AnchorSlots {
+            incoming: slots_for(Incoming),
+            outgoing: slots_for(Outgoing),
+        };
+        anchors.insert(node, slots);
+    }
+
+    for slots in anchors.values() {
+        for &(edge, anchor) in &slots.incoming {
+            let points = edge_points.get_mut(&edge).unwrap();
+            *points.last_mut().unwrap() = anchor;
+        }
+        for &(edge, anchor) in &slots.outgoing {
+            edge_points.get_mut(&edge).unwrap()[0] = anchor;
+        }
+    }
+
+    anchors
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::{DagreLayout, LayoutOptions};
+    use petgraph::Graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_incoming_anchor_slots_follow_source_order() {
+        let mut graph = Graph::new();
+        let sources: Vec<_> = (0..3).map(|i| graph.add_node(i)).collect();
+        let sink = graph.add_node(3);
+        for &source in &sources {
+            graph.add_edge(source, sink, ());
+        }
+
+        let options = LayoutOptions {
+            anchor_slots: true,
+            node_sizes: HashMap::from([(sink, (80.0, 40.0))]),
+            ..Default::default()
+        };
+        let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+
+        let slots = &result.anchor_slots[&sink];
+        assert_eq!(slots.incoming.len(), 3);
+        assert!(slots.outgoing.is_empty());
+
+        let center = result.node_positions[&sink];
+        let xs: Vec<f32> = slots.incoming.iter().map(|(_, p)| p.0).collect();
+        assert!(
+            xs.windows(2).all(|w| w[0] < w[1]),
+            "slots not distinct: {:?}",
+            xs
+        );
+        assert!(xs.iter().all(|&x| (x - center.0).abs() < 40.0));
+        assert!(slots.incoming.iter().all(|(_, p)| p.1 == center.1 - 20.0));
+
+        // Slots follow the order of the source nodes
+        let source_xs: Vec<f32> = slots
+            .incoming
+            .iter()
+            .map(|&(edge, _)| result.node_positions[&graph.edge_endpoints(edge).unwrap().0].0)
+            .collect();
+        assert!(source_xs.windows(2).all(|w| w[0] < w[1]));
+
+        // Routes end on their slot
+        for &(edge, anchor) in &slots.incoming {
+            assert_eq!(*result.edge_points[&edge].last().unwrap(), anchor);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::layout::LayoutResult;
+use petgraph::prelude::*;
+use std::collections::HashMap;
+
+/// Graph element revealed by an [`AnimationEvent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnimationItem {
+    /// A node of the laid out graph
+    Node(NodeIndex),
+    /// An edge of the laid out graph
+    Edge(EdgeIndex),
+}
+
+/// Suggested moment for a graph element to appear
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationEvent {
+    /// Element that appears
+    pub item: AnimationItem,
+    /// Start time of the appearance, in the unit of the per-item delay
+    pub start: f32,
+}
+
+impl LayoutResult {
+    /// Order all nodes and edges into a reveal sequence for animations
+    ///
+    /// Layers are revealed from the first rank to the last. Within a layer
+    /// the nodes appear in their layer order, followed by every edge whose
+    /// later endpoint lies in that layer, so an edge never appears before
+    /// both of its nodes. Consecutive events start `per_item_delay` apart.
+    pub fn animation_timeline<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        per_item_delay: f32,
+    ) -> Vec<AnimationEvent> {
+        let position: HashMap<NodeIndex, (usize, usize)> = self
+            .layers
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, layer)| {
+                layer
+                    .iter()
+                    .enumerate()
+                    .map(move |(order, &node)| (node, (rank, order)))
+            })
+            .collect();
+
+        // Each edge is revealed with the layer of its later endpoint
+        let mut edges_by_layer = vec![Vec::new(); self.layers.len()];
+        for edge in graph.edge_references() {
+            let (Some(&source), Some(&target)) =
+                (position.get(&edge.source()), position.get(&edge.target()))
+            else {
+                continue;
+            };
+            let (later, earlier) = if source >= target {
+                (source, target)
+            } else {
+                (target, source)
+            };
+            edges_by_layer[later.0].push((later.1, earlier, edge.id()));
+        }
+
+        let mut items = Vec::with_capacity(graph.node_count() + graph.edge_count());
+        for (layer, edges) in self.layers.iter().zip(&mut edges_by_layer) {
+            items.extend(layer.iter().map(|&node| AnimationItem::Node(node)));
+            edges.sort();
+            items.extend(edges.iter().map(|&(_, _, edge)| AnimationItem::Edge(edge)));
+        }
+
+        items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| AnimationEvent {
+                item,
+                start: i as f32 * per_item_delay,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::DagreLayout;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_animation_timeline_follows_ranks() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        graph.add_edge(a, b, ());
+        graph.add_edge(a, c, ());
+        graph.add_edge(b, d, ());
+        graph.add_edge(c, d, ());
+
+        let result = DagreLayout::new().compute(&graph).unwrap();
+        let events = result.animation_timeline(&graph, 0.25);
+
+        assert_eq!(events.len(), graph.node_count() + graph.edge_count());
+        let start = |item| events.iter().find(|e| e.item == item).unwrap().start;
+        for node in graph.node_indices() {
+            assert!(events.iter().any(|e| e.item == AnimationItem::Node(node)));
+        }
+        for edge in graph.edge_indices() {
+            let (source, target) = graph.edge_endpoints(edge).unwrap();
+            let edge_start = start(AnimationItem::Edge(edge));
+            assert!(edge_start > start(AnimationItem::Node(source)));
+            assert!(edge_start > start(AnimationItem::Node(target)));
+        }
+
+        assert!(start(AnimationItem::Node(a)) < start(AnimationItem::Node(d)));
+        assert!(
+            events
+                .windows(2)
+                .all(|pair| pair[1].start - pair[0].start == 0.25)
+        );
+    }
+}
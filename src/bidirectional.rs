@@ -0,0 +1,91 @@
+use crate::derived::DerivedGraph;
+use crate::layout::DagreLayout;
+use petgraph::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+impl DagreLayout {
+    /// Layout engine and graph with the antiparallel edge pairs merged, if `merge_bidirectional` finds any
+    ///
+    /// Edges are paired in index order, each with the lowest unpaired edge
+    /// running the opposite way between the same nodes. The lower edge of a
+    /// pair represents both, the other one is left out. Returns the pairs as
+    /// a map from the kept edge to its partner, in input indices.
+    pub(crate) fn merging_bidirectional<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Option<(DagreLayout, DerivedGraph, HashMap<EdgeIndex, EdgeIndex>)> {
+        if !self.options.merge_bidirectional {
+            return None;
+        }
+
+        let mut unpaired: HashMap<(NodeIndex, NodeIndex), Vec<EdgeIndex>> = HashMap::new();
+        let mut partners = HashMap::new();
+        for edge in graph.edge_references() {
+            let (source, target) = (edge.source(), edge.target());
+            if source == target {
+                continue;
+            }
+            match unpaired.get_mut(&(target, source)) {
+                Some(opposite) if !opposite.is_empty() => {
+                    partners.insert(opposite.remove(0), edge.id());
+                }
+                _ => unpaired
+                    .entry((source, target))
+                    .or_default()
+                    .push(edge.id()),
+            }
+        }
+        if partners.is_empty() {
+            return None;
+        }
+
+        let merged: HashSet<EdgeIndex> = partners.values().copied().collect();
+        let derived = DerivedGraph::new(graph, |edge| {
+            (!merged.contains(&edge.id())).then(|| (edge.source(), edge.target()))
+        });
+        let mut layout = self.for_derived(&derived);
+        layout.options.merge_bidirectional = false;
+        Some((layout, derived, partners))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::{DagreLayout, LayoutOptions};
+    use petgraph::Graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_merge_bidirectional_edges() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let forward = graph.add_edge(a, b, ());
+        let backward = graph.add_edge(b, a, ());
+        let other = graph.add_edge(a, c, ());
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            merge_bidirectional: true,
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+
+        assert_eq!(
+            result.bidirectional_edges,
+            HashMap::from([(forward, backward)])
+        );
+        assert_eq!(result.edge_points.len(), 2);
+        assert!(result.edge_points.contains_key(&other));
+        let points = &result.edge_points[&forward];
+        assert_eq!(points[0], result.node_positions[&a]);
+        assert_eq!(*points.last().unwrap(), result.node_positions[&b]);
+        assert_eq!(result.layers[0], vec![a]);
+
+        // Crossing reduction sees a single edge between A and B
+        let (normalized, _) = layout.normalized_graph(&graph).unwrap();
+        assert_eq!(normalized.edge_count(), 2);
+        assert_eq!(normalized.edges_connecting(a, b).count(), 1);
+        assert_eq!(normalized.edges_connecting(b, a).count(), 0);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::layout::LayoutOptions;
+use crate::normalize::NodeKind;
+use petgraph::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Assign cross-axis coordinates with the Brandes–Köpf method
+///
+/// Every node is aligned with a median neighbor on the previous layer
+/// unless that would cross an alignment already made, and aligned nodes are
+/// packed as blocks as closely as `node_sep` and node sizes allow. This is
+/// done four times, aligning with predecessors or successors and packing
+/// to the left or right. The four placements are aligned to the narrowest
+/// one and every node takes the average of its two median coordinates.
+/// Segments between dummy nodes of long edges take precedence, so long
+/// edges run straight wherever possible. Only the nodes of `layers` and
+/// the edges between them are considered.
+pub(crate) fn assign_brandes_kopf(
+    graph: &DiGraph<NodeKind, EdgeIndex>,
+    layers: &[Vec<NodeIndex>],
+    options: &LayoutOptions,
+) -> (HashMap<NodeIndex, (f32, f32)>, f32, f32) {
+    let rank_dir = options.rank_dir;
+
+    // Renumber the nodes of the layers in order, so the work scales with them alone
+    let originals: Vec<NodeIndex> = layers.iter().flatten().copied().collect();
+    let local: HashMap<NodeIndex, NodeIndex> = originals
+        .iter()
+        .enumerate()
+        .map(|(index, &node)| (node, NodeIndex::new(index)))
+        .collect();
+    let mut sub = DiGraph::with_capacity(originals.len(), originals.len());
+    for &node in &originals {
+        sub.add_node(graph[node]);
+    }
+    for &node in &originals {
+        for edge in graph.edges_directed(node, Outgoing) {
+            if let Some(&target) = local.get(&edge.target()) {
+                sub.add_edge(local[&node], target, *edge.weight());
+            }
+        }
+    }
+    let sub_layers: Vec<Vec<NodeIndex>> = layers
+        .iter()
+        .map(|layer| layer.iter().map(|node| local[node]).collect())
+        .collect();
+    let widths: Vec<f32> = originals
+        .iter()
+        .map(|&node| match graph[node] {
+            NodeKind::Dummy(_) => 0.0,
+            NodeKind::Real(_) => options
+                .node_sizes
+                .get(&node)
+                .map_or(0.0, |&size| rank_dir.cross(size)),
+        })
+        .collect();
+    let conflicts = inner_segment_conflicts(&sub, &sub_layers);
+
+    let mut runs = Vec::with_capacity(4);
+    for upward in [false, true] {
+        for rightward in [false, true] {
+            let mut adjusted = sub_layers.clone();
+            if upward {
+                adjusted.reverse();
+            }
+            if rightward {
+                adjusted.iter_mut().for_each(|layer| layer.reverse());
+            }
+            let direction = if upward { Outgoing } else { Incoming };
+            let roots = align_blocks(&sub, &adjusted, &conflicts, direction);
+            let mut xs = compact_blocks(&adjusted, &roots, &widths, options.node_sep);
+            if rightward {
+                xs.iter_mut().for_each(|x| *x = -*x);
+            }
+            runs.push((xs, rightward));
+        }
+    }
+
+    let nodes = 0..originals.len();
+    let bounds = |xs: &[f32]| {
+        nodes
+            .clone()
+            .fold((f32::MAX, f32::MIN), |(min, max), node| {
+                (min.min(xs[node]), max.max(xs[node]))
+            })
+    };
+    let extent = |xs: &[f32]| {
+        let left = nodes.clone().map(|node| xs[node] - widths[node] * 0.5);
+        let right = nodes.clone().map(|node| xs[node] + widths[node] * 0.5);
+        right.fold(f32::MIN, f32::max) - left.fold(f32::MAX, f32::min)
+    };
+
+    // Move every placement onto the side of the narrowest one it packed toward
+    let narrowest = (0..runs.len())
+        .min_by(|&a, &b| extent(&runs[a].0).total_cmp(&extent(&runs[b].0)))
+        .unwrap_or(0);
+    let (target_min, target_max) = bounds(&runs[narrowest].0);
+    for (xs, rightward) in &mut runs {
+        let (min, max) = bounds(xs);
+        let delta = match rightward {
+            true => target_max - max,
+            false => target_min - min,
+        };
+        xs.iter_mut().for_each(|x| *x += delta);
+    }
+
+    let cross: Vec<f32> = nodes
+        .clone()
+        .map(|node| {
+            let mut candidates: Vec<f32> = runs.iter().map(|(xs, _)| xs[node]).collect();
+            candidates.sort_by(f32::total_cmp);
+            (candidates[1] + candidates[2]) * 0.5
+        })
+        .collect();
+
+    let left = nodes
+        .clone()
+        .map(|node| cross[node] - widths[node] * 0.5)
+        .fold(f32::MAX, f32::min);
+    let right = nodes
+        .clone()
+        .map(|node| cross[node] + widths[node] * 0.5)
+        .fold(f32::MIN, f32::max);
+    let content = if nodes.is_empty() { 0.0 } else { right - left };
+    let (offset, cross_extent) = match options.fixed_cross_axis_width {
+        Some(fixed) => (
+            (fixed - content) * 0.5,
+            fixed.max(content + options.node_sep),
+        ),
+        None => (0.0, content + options.node_sep),
+    };
+
+    let rank_coordinates = options.rank_coordinates(layers);
+    let mut positions = HashMap::new();
+    for (layer, &rank) in sub_layers.iter().zip(&rank_coordinates) {
+        for &node in layer {
+            let x = cross[node.index()] - left + offset;
+            positions.insert(originals[node.index()], rank_dir.point(x, rank));
+        }
+    }
+    let (width, height) = rank_dir.point(cross_extent, options.rank_extent(&rank_coordinates));
+    (positions, width, height)
+}
+
+/// Unordered pair of node indices
+fn pair(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// Segments that cross a segment between two dummy nodes
+///
+/// Only segments with at least one real end are reported, so alignment
+/// keeps the inner segments of long edges and gives up the others.
+fn inner_segment_conflicts(
+    graph: &DiGraph<NodeKind, EdgeIndex>,
+    layers: &[Vec<NodeIndex>],
+) -> HashSet<(usize, usize)> {
+    let is_dummy = |node: NodeIndex| matches!(graph[node], NodeKind::Dummy(_));
+    let mut conflicts = HashSet::new();
+
+    for pair_of_layers in layers.windows(2) {
+        let (previous, layer) = (&pair_of_layers[0], &pair_of_layers[1]);
+        let position: HashMap<NodeIndex, usize> = previous
+            .iter()
+            .enumerate()
+            .map(|(pos, &node)| (node, pos))
+            .collect();
+        let mut low = 0;
+        let mut scanned = 0;
+        for (i, &node) in layer.iter().enumerate() {
+            let inner = graph
+                .neighbors_directed(node, Incoming)
+                .filter(|&pred| is_dummy(node) && is_dummy(pred))
+                .find_map(|pred| position.get(&pred).copied());
+            if inner.is_none() && i + 1 < layer.len() {
+                continue;
+            }
+            let high = inner.unwrap_or(previous.len());
+            for &scan in &layer[scanned..=i] {
+                for pred in graph.neighbors_directed(scan, Incoming) {
+                    let Some(&pos) = position.get(&pred) else {
+                        continue;
+                    };
+                    let both_dummies = is_dummy(pred) && is_dummy(scan);
+                    if (pos < low || high < pos) && !both_dummies {
+                        conflicts.insert(pair(pred.index(), scan.index()));
+                    }
+                }
+            }
+            scanned = i + 1;
+            low = high;
+        }
+    }
+    conflicts
+}
+
+/// Root of the block every node joins when aligned with its median neighbors
+///
+/// Layers are visited in order, each node is aligned with the left or right
+/// median of its `direction` neighbors on the previous layer unless the
+/// segment conflicts or crosses an alignment made earlier in the layer.
+fn align_blocks(
+    graph: &DiGraph<NodeKind, EdgeIndex>,
+    layers: &[Vec<NodeIndex>],
+    conflicts: &HashSet<(usize, usize)>,
+    direction: Direction,
+) -> Vec<usize> {
+    let count = graph.node_count();
+    let mut roots: Vec<usize> = (0..count).collect();
+    let mut aligned = vec![false; count];
+    let mut position = vec![0; count];
+    let mut rank = vec![usize::MAX; count];
+    for (layer_idx, layer) in layers.iter().enumerate() {
+        for (pos, &node) in layer.iter().enumerate() {
+            position[node.index()] = pos;
+            rank[node.index()] = layer_idx;
+        }
+    }
+
+    for (layer_idx, layer) in layers.iter().enumerate().skip(1) {
+        let mut previous: Option<usize> = None;
+        for &node in layer {
+            let v = node.index();
+            let mut neighbors: Vec<usize> = graph
+                .neighbors_directed(node, direction)
+                .map(|neighbor| neighbor.index())
+                .filter(|&w| rank[w] == layer_idx - 1)
+                .collect();
+            neighbors.sort_by_key(|&w| position[w]);
+            neighbors.dedup();
+            if neighbors.is_empty() {
+                continue;
+            }
+            let medians = (neighbors.len() - 1) / 2..=neighbors.len() / 2;
+            for &w in &neighbors[medians] {
+                let free = previous.is_none_or(|previous| previous < position[w]);
+                if !aligned[v] && free && !conflicts.contains(&pair(v, w)) {
+                    roots[v] = roots[w];
+                    aligned[v] = true;
+                    previous = Some(position[w]);
+                }
+            }
+        }
+    }
+    roots
+}
+
+/// Pack the blocks given by `roots` to the left, then let them drift right
+///
+/// Neighbors in a layer keep their centers `node_sep` plus half of both
+/// widths apart. Blocks are first placed as far left as their left
+/// neighbors allow, then moved right toward their right neighbors where
+/// that leaves them closer.
+fn compact_blocks(
+    layers: &[Vec<NodeIndex>],
+    roots: &[usize],
+    widths: &[f32],
+    node_sep: f32,
+) -> Vec<f32> {
+    let count = roots.len();
+    let mut separation: HashMap<(usize, usize), f32> = HashMap::new();
+    for layer in layers {
+        for pair in layer.windows(2) {
+            let (u, v) = (pair[0].index(), pair[1].index());
+            let sep = (widths[u] + widths[v]) * 0.5 + node_sep;
+            let entry = separation.entry((roots[u], roots[v])).or_insert(sep);
+            *entry = entry.max(sep);
+        }
+    }
+    let mut incoming: Vec<Vec<(usize, f32)>> = vec![Vec::new(); count];
+    let mut outgoing: Vec<Vec<(usize, f32)>> = vec![Vec::new(); count];
+    for (&(u, v), &sep) in &separation {
+        outgoing[u].push((v, sep));
+        incoming[v].push((u, sep));
+    }
+
+    // Blocks in an order where left neighbors come first
+    let mut pending: Vec<usize> = incoming.iter().map(Vec::len).collect();
+    let mut order: Vec<usize> = (0..count)
+        .filter(|&block| roots[block] == block && pending[block] == 0)
+        .collect();
+    let mut next = 0;
+    while next < order.len() {
+        let block = order[next];
+        next += 1;
+        for &(successor, _) in &outgoing[block] {
+            pending[successor] -= 1;
+            if pending[successor] == 0 {
+                order.push(successor);
+            }
+        }
+    }
+
+    let mut xs = vec![0.0; count];
+    for &block in &order {
+        xs[block] = incoming[block]
+            .iter()
+            .map(|&(predecessor, sep)| xs[predecessor] + sep)
+            .fold(0.0, f32::max);
+    }
+    for &block in order.iter().rev() {
+        let limit = outgoing[block]
+            .iter()
+            .map(|&(successor, sep)| xs[successor] - sep)
+            .fold(f32::INFINITY, f32::min);
+        if limit.is_finite() {
+            xs[block] = xs[block].max(limit);
+        }
+    }
+
+    for node in 0..count {
+        xs[node] = xs[roots[node]];
+    }
+    xs
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::DagreLayout;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_brandes_kopf_straightens_chains() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let left = graph.add_node("left");
+        let right = graph.add_node("right");
+        let below = graph.add_node("below");
+        let end = graph.add_node("end");
+        graph.add_edge(root, left, ());
+        graph.add_edge(root, right, ());
+        graph.add_edge(left, below, ());
+        graph.add_edge(below, end, ());
+        let long = graph.add_edge(right, end, ());
+
+        // Brandes–Köpf is the default placement
+        let layout = DagreLayout::new();
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+
+        // The chain below the left child and the long edge both run straight
+        let x = |node| result.node_positions[&node].0;
+        assert_eq!(x(left), x(below));
+        let points = &result.edge_points[&long];
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1].0, x(right));
+        assert_eq!(x(right) - x(left), layout.options.node_sep);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::error::LayoutError;
+use crate::layout::{DagreLayout, LayoutResult};
+use petgraph::prelude::*;
+use std::collections::HashMap;
+
+/// Graph with every chain of nodes replaced by a single meta-node, see [`collapse_chains`]
+///
+/// Nodes and edges are renumbered in index order, a meta-node taking the
+/// place of the first node of its chain. `nodes` and `edges` hold the input
+/// node and edge of every node and edge of the reduced graph.
+#[derive(Debug, Clone, Default)]
+pub struct CollapsedGraph {
+    /// The reduced graph
+    pub graph: DiGraph<(), ()>,
+    /// Input nodes of every meta-node, in order along the chain
+    pub chains: HashMap<NodeIndex, Vec<NodeIndex>>,
+    /// Input node of every node, the first node of its chain for meta-nodes
+    pub nodes: Vec<NodeIndex>,
+    /// Input edge of every edge; the edges within a chain are left out
+    pub edges: Vec<EdgeIndex>,
+}
+
+/// Replace every chain of nodes with one incoming and one outgoing edge by a meta-node
+///
+/// A chain is a longest run of at least two such nodes, each the successor
+/// of the one before. The meta-node takes over the edge into the first node
+/// and the edge out of the last one. Self-loops do not link nodes, and
+/// cycles made of chain nodes only are left as they are, since their
+/// meta-node would have nothing but an edge to itself.
+pub fn collapse_chains<N, E>(graph: &DiGraph<N, E>) -> CollapsedGraph {
+    let successor = |node: NodeIndex| {
+        let mut incoming = graph.neighbors_directed(node, Incoming);
+        let mut outgoing = graph.neighbors_directed(node, Outgoing);
+        let single = |neighbors: &mut petgraph::graph::Neighbors<'_, E>| {
+            neighbors.next().filter(|_| neighbors.next().is_none())
+        };
+        single(&mut incoming)?;
+        single(&mut outgoing).filter(|&next| next != node)
+    };
+    let linked = |node: NodeIndex| successor(node).is_some();
+
+    // Follow every chain from its first node, one whose predecessor is not linked
+    let mut chain_of = vec![None; graph.node_count()];
+    let mut chains = Vec::new();
+    for node in graph.node_indices() {
+        let first = linked(node)
+            && graph
+                .neighbors_directed(node, Incoming)
+                .all(|predecessor| !linked(predecessor));
+        if !first {
+            continue;
+        }
+        let mut chain = vec![node];
+        let mut next = successor(node);
+        while let Some(member) = next.filter(|&member| linked(member)) {
+            chain.push(member);
+            next = successor(member);
+        }
+        if chain.len() >= 2 {
+            for member in &chain {
+                chain_of[member.index()] = Some(chains.len());
+            }
+            chains.push(chain);
+        }
+    }
+
+    let mut collapsed = CollapsedGraph::default();
+    let mut local = vec![NodeIndex::end(); graph.node_count()];
+    let mut meta_nodes = vec![NodeIndex::end(); chains.len()];
+    for node in graph.node_indices() {
+        match chain_of[node.index()] {
+            Some(chain) if chains[chain][0] == node => {
+                meta_nodes[chain] = collapsed.graph.add_node(());
+                collapsed.nodes.push(node);
+                let meta = meta_nodes[chain];
+                collapsed.chains.insert(meta, chains[chain].clone());
+            }
+            Some(_) => continue,
+            None => {
+                local[node.index()] = collapsed.graph.add_node(());
+                collapsed.nodes.push(node);
+            }
+        }
+    }
+    let reduced = |node: NodeIndex| match chain_of[node.index()] {
+        Some(chain) => meta_nodes[chain],
+        None => local[node.index()],
+    };
+    for edge in graph.edge_references() {
+        let (source, target) = (edge.source(), edge.target());
+        let chain = chain_of[source.index()];
+        if chain.is_some() && chain == chain_of[target.index()] {
+            continue;
+        }
+        let (source, target) = (reduced(source), reduced(target));
+        collapsed.graph.add_edge(source, target, ());
+        collapsed.edges.push(edge.id());
+    }
+    collapsed
+}
+
+impl DagreLayout {
+    /// Like [`Self::compute`], laying out the graph with its chains collapsed
+    ///
+    /// The layout is that of the reduced graph of [`collapse_chains`], in
+    /// its indices; the returned [`CollapsedGraph`] maps them back to the
+    /// input graph. Options keyed by node or edge are translated to the
+    /// reduced graph, a meta-node taking the entries of the first node of
+    /// its chain.
+    ///
+    /// # Example
+    /// ```
+    /// use dagrers::DagreLayout;
+    /// use petgraph::Graph;
+    ///
+    /// let mut graph = Graph::new();
+    /// let a = graph.add_node("A");
+    /// let b = graph.add_node("B");
+    /// let c = graph.add_node("C");
+    /// let d = graph.add_node("D");
+    /// graph.add_edge(a, b, ());
+    /// graph.add_edge(b, c, ());
+    /// graph.add_edge(c, d, ());
+    /// graph.add_edge(a, d, ());
+    ///
+    /// let (result, collapsed) = DagreLayout::new().compute_collapsed(&graph).unwrap();
+    /// assert_eq!(result.node_positions.len(), 3);
+    /// let (meta, chain) = collapsed.chains.iter().next().unwrap();
+    /// assert_eq!(chain, &[b, c]);
+    /// assert!(result.node_positions.contains_key(meta));
+    /// ```
+    pub fn compute_collapsed<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Result<(LayoutResult, CollapsedGraph), LayoutError> {
+        let collapsed = collapse_chains(graph);
+        let layout = self.for_subgraph(&collapsed.nodes, &collapsed.edges);
+        let result = layout.compute(&collapsed.graph)?;
+        Ok((result, collapsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutOptions;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_collapse_chains_keeps_branches_and_cycles() {
+        // A chain of three beside a shortcut, a single linked node, a cycle
+        // of linked nodes and a node with a self-loop between two others
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..11).map(|i| graph.add_node(i)).collect();
+        let edges = [
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (3, 4),
+            (0, 4),
+            (4, 5),
+            (5, 6),
+            (7, 8),
+            (8, 7),
+            (4, 9),
+            (9, 9),
+            (9, 10),
+        ];
+        for (source, target) in edges {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+
+        let collapsed = collapse_chains(&graph);
+        assert_eq!(collapsed.graph.node_count(), 9);
+        let meta = NodeIndex::new(1);
+        assert_eq!(
+            collapsed.chains,
+            HashMap::from([(meta, vec![nodes[1], nodes[2], nodes[3]])])
+        );
+        let kept = [0, 1, 4, 5, 6, 7, 8, 9, 10].map(|i| nodes[i]);
+        assert_eq!(collapsed.nodes, kept);
+
+        // Edges into and out of the chain now attach to its meta-node
+        let reduced: Vec<_> = collapsed
+            .graph
+            .edge_references()
+            .map(|edge| (edge.source().index(), edge.target().index()))
+            .collect();
+        assert_eq!(
+            reduced,
+            [
+                (0, 1),
+                (1, 2),
+                (0, 2),
+                (2, 3),
+                (3, 4),
+                (5, 6),
+                (6, 5),
+                (2, 7),
+                (7, 7),
+                (7, 8)
+            ]
+        );
+        let kept = [0, 3, 4, 5, 6, 7, 8, 9, 10, 11].map(EdgeIndex::new);
+        assert_eq!(collapsed.edges, kept);
+    }
+
+    #[test]
+    fn test_compute_collapsed_lays_out_the_reduced_graph() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..6).map(|i| graph.add_node(i)).collect();
+        for (source, target) in [(0, 1), (1, 2), (2, 3), (3, 4), (0, 5), (5, 4)] {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+
+        // Sizes of later chain nodes are dropped, the first one's is kept
+        let layout = DagreLayout::with_options(LayoutOptions {
+            node_sizes: HashMap::from([(nodes[1], (80.0, 20.0)), (nodes[2], (10.0, 10.0))]),
+            ..Default::default()
+        });
+        let (result, collapsed) = layout.compute_collapsed(&graph).unwrap();
+        let meta = NodeIndex::new(1);
+        assert_eq!(collapsed.chains[&meta], [nodes[1], nodes[2], nodes[3]]);
+        assert_eq!(result.node_sizes, HashMap::from([(meta, (80.0, 20.0))]));
+        let reduced = layout.for_subgraph(&collapsed.nodes, &collapsed.edges);
+        assert!(result.approx_eq(&reduced.compute(&collapsed.graph).unwrap()));
+
+        // The meta-node sits on the rank of the node beside it, as one node
+        assert_eq!(result.layers.len(), 3);
+        assert_eq!(result.node_ranks[&meta], 1);
+        assert_eq!(result.node_ranks[&NodeIndex::new(3)], 1);
+        assert_eq!(result.edge_points.len(), collapsed.graph.edge_count());
+    }
+}
@@ -0,0 +1,421 @@
+use crate::error::LayoutError;
+use crate::layout::{DagreLayout, LayoutResult, RankDir};
+use crate::normalize::{NodeKind, NormalizedView};
+use crate::routing::RoutingConstraint;
+use petgraph::prelude::*;
+use petgraph::unionfind::UnionFind;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// Arrangement of the weakly connected components laid out on their own with `pack_components`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ComponentPacking {
+    /// All components side by side along the cross axis
+    Row {
+        /// Space between the boxes of neighboring components
+        gap: f32,
+    },
+    /// Rows of components side by side, stacked along the rank axis
+    Grid {
+        /// Number of components in every row but the last
+        columns: usize,
+        /// Space between the boxes of neighboring components and rows
+        gap: f32,
+    },
+}
+
+/// Place of a weakly connected component in a layout packed by `pack_components`
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackedComponent {
+    /// Nodes of the component, sorted by index
+    pub nodes: Vec<NodeIndex>,
+    /// Shift from the component's own layout to its place in the packed layout
+    pub offset: (f32, f32),
+}
+
+/// Weakly connected component copied into a graph of its own
+///
+/// Nodes and edges are renumbered in index order, `nodes` and `edges`
+/// hold the input node and edge of every node and edge of the copy.
+#[derive(Default)]
+struct Component {
+    graph: DiGraph<(), ()>,
+    nodes: Vec<NodeIndex>,
+    edges: Vec<EdgeIndex>,
+}
+
+impl DagreLayout {
+    /// Layout of every component packed with `pack_components`, if the graph has several
+    ///
+    /// Each component is laid out on its own without margins, and the boxes
+    /// of its `width` and `height` are packed in order of their lowest node
+    /// index. The margins are left around the packed layout. `layers` and
+    /// `node_ranks` merge the ranks of all components by index, even where
+    /// their rank coordinates differ.
+    pub(crate) fn packing_components<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Option<Result<LayoutResult, LayoutError>> {
+        let packing = self.options.pack_components?;
+        let components = split_components(graph);
+        if components.len() < 2 {
+            return None;
+        }
+        Some(self.pack_components(&components, packing))
+    }
+
+    /// Normalized graph of every component laid out on its own with
+    /// `pack_components`, if the graph has several, see [`Self::normalized_graph`]
+    ///
+    /// Real nodes keep their input index, the dummy nodes of each component
+    /// follow in order of the components, and ranks merge by index.
+    pub(crate) fn normalized_components<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Option<Result<NormalizedView, LayoutError>> {
+        self.options.pack_components?;
+        let components = split_components(graph);
+        if components.len() < 2 {
+            return None;
+        }
+        Some(self.merge_normalized(graph, &components))
+    }
+
+    fn merge_normalized<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        components: &[Component],
+    ) -> Result<NormalizedView, LayoutError> {
+        let mut merged = DiGraph::with_capacity(graph.node_count(), graph.edge_count());
+        for node in graph.node_indices() {
+            merged.add_node(NodeKind::Real(node));
+        }
+        let mut ranks = HashMap::new();
+        for component in components {
+            let layout = component.layout(self);
+            let (normalized, local_ranks) = layout.normalized_graph(&component.graph)?;
+            let nodes: Vec<NodeIndex> = normalized
+                .node_weights()
+                .map(|&kind| match kind {
+                    NodeKind::Real(local) => component.nodes[local.index()],
+                    NodeKind::Dummy(edge) => {
+                        merged.add_node(NodeKind::Dummy(component.edges[edge.index()]))
+                    }
+                })
+                .collect();
+            for edge in normalized.edge_references() {
+                let (source, target) = (edge.source().index(), edge.target().index());
+                merged.add_edge(nodes[source], nodes[target], ());
+            }
+            ranks.extend(
+                local_ranks
+                    .into_iter()
+                    .map(|(local, rank)| (nodes[local.index()], rank)),
+            );
+        }
+        Ok((merged, ranks))
+    }
+
+    fn pack_components(
+        &self,
+        components: &[Component],
+        packing: ComponentPacking,
+    ) -> Result<LayoutResult, LayoutError> {
+        let results = components
+            .iter()
+            .map(|component| component.layout(self).compute(&component.graph))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (offsets, width, height) = place(&results, packing, self.options.rank_dir);
+
+        let (margin_x, margin_y) = (self.options.margin_x, self.options.margin_y);
+        let mut packed = LayoutResult {
+            width: width + 2.0 * margin_x,
+            height: height + 2.0 * margin_y,
+            ..Default::default()
+        };
+        for ((component, mut result), (x, y)) in components.iter().zip(results).zip(offsets) {
+            let offset = (x + margin_x, y + margin_y);
+            result.translate(offset.0, offset.1);
+            component.restore(result, &mut packed);
+            packed.components.push(PackedComponent {
+                nodes: component.nodes.clone(),
+                offset,
+            });
+        }
+        Ok(packed)
+    }
+
+    /// Layout engine for a graph copied from parts of the input graph
+    ///
+    /// `nodes` and `edges` hold the input node and edge of every node and
+    /// edge of the copy. Options keyed by node or edge are translated to the
+    /// copy, entries of nodes and edges left out of it are dropped.
+    pub(crate) fn for_subgraph(&self, nodes: &[NodeIndex], edges: &[EdgeIndex]) -> DagreLayout {
+        let local_nodes: HashMap<NodeIndex, NodeIndex> = nodes
+            .iter()
+            .enumerate()
+            .map(|(local, &node)| (node, NodeIndex::new(local)))
+            .collect();
+        let local_edges: HashMap<EdgeIndex, EdgeIndex> = edges
+            .iter()
+            .enumerate()
+            .map(|(local, &edge)| (edge, EdgeIndex::new(local)))
+            .collect();
+        let node = |node: &NodeIndex| local_nodes.get(node).copied();
+        let edge = |edge: &EdgeIndex| local_edges.get(edge).copied();
+
+        let mut layout = self.clone();
+        let options = &mut layout.options;
+        options.node_sizes = remap(&options.node_sizes, node);
+        options.node_shapes = remap(&options.node_shapes, node);
+        options.fixed_ranks = remap(&options.fixed_ranks, node);
+        options.initial_order = remap(&options.initial_order, node);
+        options.same_rank = options
+            .same_rank
+            .iter()
+            .map(|group| group.iter().filter_map(node).collect::<Vec<_>>())
+            .filter(|group| !group.is_empty())
+            .collect();
+        options.min_rank_nodes = options.min_rank_nodes.iter().filter_map(node).collect();
+        options.max_rank_nodes = options.max_rank_nodes.iter().filter_map(node).collect();
+        options.cycle_roots = options.cycle_roots.iter().filter_map(node).collect();
+        options.edge_weights = remap(&options.edge_weights, edge);
+        options.min_lengths = remap(&options.min_lengths, edge);
+        options.edge_label_sizes = remap(&options.edge_label_sizes, edge);
+        options.source_ports = remap(&options.source_ports, edge);
+        options.target_ports = remap(&options.target_ports, edge);
+        options.routing_constraints = options
+            .routing_constraints
+            .iter()
+            .filter_map(|constraint| {
+                Some(RoutingConstraint {
+                    edge: edge(&constraint.edge)?,
+                    ..*constraint
+                })
+            })
+            .collect();
+        if let Some(compare) = layout.node_order.clone() {
+            let nodes = nodes.to_vec();
+            layout.node_order = Some(Arc::new(move |a, b| {
+                compare(nodes[a.index()], nodes[b.index()])
+            }));
+        }
+        layout
+    }
+}
+
+impl Component {
+    /// Layout engine with the options keyed by node or edge translated to the component
+    fn layout(&self, layout: &DagreLayout) -> DagreLayout {
+        let mut layout = layout.for_subgraph(&self.nodes, &self.edges);
+        layout.options.pack_components = None;
+        (layout.options.margin_x, layout.options.margin_y) = (0.0, 0.0);
+        layout
+    }
+
+    /// Add a layout of the component to `packed`, in input indices
+    fn restore(&self, result: LayoutResult, packed: &mut LayoutResult) {
+        let node = |node: NodeIndex| self.nodes[node.index()];
+        let edge = |edge: EdgeIndex| self.edges[edge.index()];
+
+        let positions = result.node_positions.into_iter();
+        packed
+            .node_positions
+            .extend(positions.map(|(local, position)| (node(local), position)));
+        let points = result.edge_points.into_iter();
+        packed
+            .edge_points
+            .extend(points.map(|(local, points)| (edge(local), points)));
+        let labels = result.edge_label_positions.into_iter();
+        packed
+            .edge_label_positions
+            .extend(labels.map(|(local, position)| (edge(local), position)));
+        let sizes = result.node_sizes.into_iter();
+        packed
+            .node_sizes
+            .extend(sizes.map(|(local, size)| (node(local), size)));
+        for (local, mut slots) in result.anchor_slots {
+            for (local, _) in slots.incoming.iter_mut().chain(&mut slots.outgoing) {
+                *local = edge(*local);
+            }
+            packed.anchor_slots.insert(node(local), slots);
+        }
+        packed
+            .highway_edges
+            .extend(result.highway_edges.into_iter().map(edge));
+        packed
+            .reversed_edges
+            .extend(result.reversed_edges.into_iter().map(edge));
+        packed
+            .dropped_edges
+            .extend(result.dropped_edges.into_iter().map(edge));
+        packed
+            .flat_edges
+            .extend(result.flat_edges.into_iter().map(edge));
+        let partners = result.bidirectional_edges.into_iter();
+        packed
+            .bidirectional_edges
+            .extend(partners.map(|(local, partner)| (edge(local), edge(partner))));
+        for (rank, layer) in result.layers.into_iter().enumerate() {
+            if packed.layers.len() <= rank {
+                packed.layers.push(Vec::new());
+            }
+            packed.layers[rank].extend(layer.into_iter().map(node));
+        }
+        let ranks = result.node_ranks.into_iter();
+        packed
+            .node_ranks
+            .extend(ranks.map(|(local, rank)| (node(local), rank)));
+        packed
+            .isolated_nodes
+            .extend(result.isolated_nodes.into_iter().map(node));
+        packed.crossing_count += result.crossing_count;
+    }
+}
+
+/// Copy of every weakly connected component, in order of its lowest node index
+fn split_components<N, E>(graph: &DiGraph<N, E>) -> Vec<Component> {
+    let mut union = UnionFind::new(graph.node_count());
+    for edge in graph.edge_references() {
+        union.union(edge.source().index(), edge.target().index());
+    }
+
+    let mut index_of: HashMap<usize, usize> = HashMap::new();
+    let mut components: Vec<Component> = Vec::new();
+    let mut local = Vec::with_capacity(graph.node_count());
+    for node in graph.node_indices() {
+        let index = *index_of.entry(union.find(node.index())).or_insert_with(|| {
+            components.push(Component::default());
+            components.len() - 1
+        });
+        let component = &mut components[index];
+        local.push(component.graph.add_node(()));
+        component.nodes.push(node);
+    }
+    for edge in graph.edge_references() {
+        let (source, target) = (edge.source().index(), edge.target().index());
+        let component = &mut components[index_of[&union.find(source)]];
+        component.graph.add_edge(local[source], local[target], ());
+        component.edges.push(edge.id());
+    }
+    components
+}
+
+/// Copy of `map` with its keys translated by `key`, dropping keys it has no translation for
+fn remap<K: Eq + Hash, V: Clone>(
+    map: &HashMap<K, V>,
+    key: impl Fn(&K) -> Option<K>,
+) -> HashMap<K, V> {
+    map.iter()
+        .filter_map(|(k, value)| Some((key(k)?, value.clone())))
+        .collect()
+}
+
+/// Offset of every component's box with `packing`, and the packed width and height
+fn place(
+    results: &[LayoutResult],
+    packing: ComponentPacking,
+    rank_dir: RankDir,
+) -> (Vec<(f32, f32)>, f32, f32) {
+    let (columns, gap) = match packing {
+        ComponentPacking::Row { gap } => (results.len(), gap),
+        ComponentPacking::Grid { columns, gap } => (columns.max(1), gap),
+    };
+
+    let mut offsets = Vec::with_capacity(results.len());
+    let (mut cross_extent, mut rank_extent) = (0.0f32, 0.0);
+    for row in results.chunks(columns) {
+        let (mut cross, mut row_extent) = (0.0, 0.0f32);
+        for result in row {
+            offsets.push(rank_dir.point(cross, rank_extent));
+            let size = (result.width, result.height);
+            cross += rank_dir.cross(size) + gap;
+            row_extent = row_extent.max(rank_dir.rank(size));
+        }
+        cross_extent = cross_extent.max(cross - gap);
+        rank_extent += row_extent + gap;
+    }
+    let (width, height) = rank_dir.point(cross_extent, rank_extent - gap);
+    (offsets, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutOptions;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_pack_components_side_by_side_and_in_a_grid() {
+        // A chain of three, an edge and a lone node, interleaved by index
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..6).map(|i| graph.add_node(i)).collect();
+        graph.add_edge(nodes[0], nodes[2], ());
+        graph.add_edge(nodes[2], nodes[4], ());
+        graph.add_edge(nodes[1], nodes[3], ());
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            pack_components: Some(ComponentPacking::Row { gap: 20.0 }),
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        let groups: Vec<_> = result.components.iter().map(|c| c.nodes.clone()).collect();
+        assert_eq!(
+            groups,
+            [
+                vec![nodes[0], nodes[2], nodes[4]],
+                vec![nodes[1], nodes[3]],
+                vec![nodes[5]],
+            ]
+        );
+        assert_eq!(result.node_positions.len(), 6);
+        assert_eq!(result.edge_points.len(), 3);
+        assert_eq!(result.layers[0].len(), 3);
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+
+        // Every component sits to the right of the one before, with the gap
+        // between the default 50 wide nodes
+        let right = |i: usize| {
+            result.components[i]
+                .nodes
+                .iter()
+                .map(|node| result.node_positions[node].0 + 25.0)
+                .fold(f32::MIN, f32::max)
+        };
+        let left = |i: usize| {
+            result.components[i]
+                .nodes
+                .iter()
+                .map(|node| result.node_positions[node].0 - 25.0)
+                .fold(f32::MAX, f32::min)
+        };
+        for i in 1..3 {
+            assert!(left(i) >= right(i - 1) + 20.0 - 0.01);
+        }
+
+        // Two columns put the lone node under the chain
+        let layout = DagreLayout::with_options(LayoutOptions {
+            pack_components: Some(ComponentPacking::Grid {
+                columns: 2,
+                gap: 20.0,
+            }),
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        let [chain, _, lone] = &result.components[..] else {
+            panic!("expected three components");
+        };
+        assert_eq!(lone.offset.0, chain.offset.0);
+        assert!(result.node_positions[&nodes[5]].1 > result.node_positions[&nodes[4]].1);
+
+        // A connected graph is laid out as before
+        graph.add_edge(nodes[4], nodes[1], ());
+        graph.add_edge(nodes[3], nodes[5], ());
+        let result = layout.compute(&graph).unwrap();
+        assert!(result.components.is_empty());
+        assert!(result.approx_eq(&DagreLayout::new().compute(&graph).unwrap()));
+    }
+}
@@ -0,0 +1,761 @@
+use crate::acyclic::back_edges;
+use crate::derived::DerivedGraph;
+use crate::error::LayoutError;
+use crate::layout::{DagreLayout, LayoutResult};
+use petgraph::algo::toposort;
+use petgraph::graph::EdgeReference;
+use petgraph::prelude::*;
+use petgraph::unionfind::UnionFind;
+use std::collections::{HashMap, HashSet};
+
+/// What to do with an edge whose target is not below its source after `fixed_ranks`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RankConflict {
+    /// Fail with [`LayoutError::RankConflict`]
+    #[default]
+    Error,
+    /// Lay the edge out from target to source and report it in `reversed_edges`
+    ReverseEdge,
+    /// Leave the edge out of the layout and report it in `dropped_edges`
+    DropEdge,
+}
+
+/// What to do with an edge whose source and target share a rank
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlatEdge {
+    /// Handle it like an edge pointing upward, with `on_rank_conflict`,
+    /// or fail with [`LayoutError::InfeasibleRankConstraint`] inside a
+    /// `same_rank` group
+    #[default]
+    Conflict,
+    /// Keep both nodes on their rank and route the edge as an arc within
+    /// it, leaving it out of crossing reduction; reported in `flat_edges`
+    Arc,
+    /// Split the rank so the target lands on a new rank right below the
+    /// source, moving all later ranks down
+    NewRank,
+}
+
+/// Copy of a ranked graph made by [`DagreLayout::resolving_conflicts`]
+pub(crate) struct ResolvedConflicts {
+    /// The graph with flat and conflicting edges turned around or left out
+    pub(crate) derived: DerivedGraph,
+    /// Edges within a rank drawn as arcs
+    pub(crate) flat: HashSet<EdgeIndex>,
+    /// Edges that did not point down, in input indices
+    pub(crate) conflicts: HashSet<EdgeIndex>,
+}
+
+impl DagreLayout {
+    /// Rank nodes with their `fixed_ranks`, all others by longest path below them
+    ///
+    /// Unfixed nodes are placed below their lowest predecessor by the
+    /// `min_lengths` of their edges, in topological order. Graphs with cycles
+    /// are ranked by longest path first, with fixed nodes moved to their
+    /// ranks afterwards.
+    pub(crate) fn assign_fixed_ranks<N, E>(&self, graph: &DiGraph<N, E>) -> Vec<Vec<NodeIndex>> {
+        let fixed = &self.options.fixed_ranks;
+        let mut ranks = vec![0; graph.node_count()];
+
+        match toposort(graph, None) {
+            Ok(order) => {
+                for node in order {
+                    ranks[node.index()] = match fixed.get(&node) {
+                        Some(&rank) => rank,
+                        None => graph
+                            .edges_directed(node, Incoming)
+                            .map(|edge| ranks[edge.source().index()] + self.min_length(edge.id()))
+                            .max()
+                            .unwrap_or(0),
+                    };
+                }
+            }
+            Err(_) => {
+                let layers = self.assign_layers_longest_path(graph);
+                for (rank, layer) in layers.iter().enumerate() {
+                    for node in layer {
+                        ranks[node.index()] = fixed.get(node).copied().unwrap_or(rank);
+                    }
+                }
+            }
+        }
+
+        let rank_count = ranks.iter().max().map_or(0, |max| max + 1);
+        let mut layers = vec![Vec::new(); rank_count];
+        for node in graph.node_indices() {
+            layers[ranks[node.index()]].push(node);
+        }
+        layers
+    }
+
+    /// Rank nodes by longest path honoring `same_rank`, `min_rank_nodes` and `max_rank_nodes`
+    ///
+    /// Pinned nodes are moved to the first or last rank after the groups
+    /// are ranked, leaving their group if they are in one; a node in both
+    /// pinning sets goes to the first rank. Expects the edges against the
+    /// pins to be reversed, as cycle removal does. Fails if an edge joins
+    /// two nodes that must share a rank, unless `on_flat_edge` handles
+    /// edges within a `same_rank` group.
+    pub(crate) fn assign_constrained_ranks<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Result<Vec<Vec<NodeIndex>>, LayoutError> {
+        let mut layers = self.assign_same_ranks(graph)?;
+        let (min, max) = (&self.options.min_rank_nodes, &self.options.max_rank_nodes);
+        if min.is_empty() && max.is_empty() {
+            return Ok(layers);
+        }
+        let clash = graph.edge_references().find(|edge| {
+            let (source, target) = (edge.source(), edge.target());
+            let first = |node| min.contains(&node);
+            let last = |node| max.contains(&node) && !min.contains(&node);
+            source != target && (first(source) && first(target) || last(source) && last(target))
+        });
+        if let Some(edge) = clash {
+            return Err(LayoutError::InfeasibleRankConstraint { edge: edge.id() });
+        }
+
+        let last_rank = layers.len().saturating_sub(1);
+        let mut pinned = Vec::new();
+        for (rank, layer) in layers.iter_mut().enumerate() {
+            layer.retain(|node| {
+                let target = match (min.contains(node), max.contains(node)) {
+                    (true, _) => 0,
+                    (false, true) => last_rank,
+                    (false, false) => return true,
+                };
+                if target != rank {
+                    pinned.push((*node, target));
+                }
+                target == rank
+            });
+        }
+        for (node, rank) in pinned {
+            layers[rank].push(node);
+        }
+        self.drop_empty_layers(&mut layers);
+        Ok(layers)
+    }
+
+    /// Move nodes without any edges onto a layer of their own after the last rank
+    ///
+    /// Only done with `isolated_lane`, and only for nodes that no `same_rank`,
+    /// `min_rank_nodes` or `max_rank_nodes` entry places. The lane is
+    /// ordered by node index; the moved nodes are returned.
+    pub(crate) fn moving_isolated_nodes<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        layers: &mut Vec<Vec<NodeIndex>>,
+    ) -> HashSet<NodeIndex> {
+        if !self.options.isolated_lane {
+            return HashSet::new();
+        }
+        let options = &self.options;
+        let constrained: HashSet<NodeIndex> = options
+            .same_rank
+            .iter()
+            .flatten()
+            .chain(&options.min_rank_nodes)
+            .chain(&options.max_rank_nodes)
+            .copied()
+            .collect();
+        let isolated: HashSet<NodeIndex> = graph
+            .node_indices()
+            .filter(|node| !constrained.contains(node))
+            .filter(|&node| graph.edges_directed(node, Incoming).next().is_none())
+            .filter(|&node| graph.edges_directed(node, Outgoing).next().is_none())
+            .collect();
+        if isolated.is_empty() {
+            return isolated;
+        }
+
+        for layer in layers.iter_mut() {
+            layer.retain(|node| !isolated.contains(node));
+        }
+        self.drop_empty_layers(layers);
+        let mut lane: Vec<NodeIndex> = isolated.iter().copied().collect();
+        lane.sort();
+        layers.push(lane);
+        isolated
+    }
+
+    /// Rank nodes by longest path with every `same_rank` group on one rank
+    ///
+    /// Each group is ranked as a single node standing in for all of its
+    /// members, so the group lands below the deepest predecessor of any of
+    /// them. Fails if the groups close a cycle, or if an edge joins two nodes
+    /// of a group and `on_flat_edge` is [`FlatEdge::Conflict`].
+    fn assign_same_ranks<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Result<Vec<Vec<NodeIndex>>, LayoutError> {
+        if self.options.same_rank.is_empty() {
+            return Ok(self.assign_layers_longest_path(graph));
+        }
+        let mut groups = UnionFind::new(graph.node_count());
+        for group in &self.options.same_rank {
+            let mut members = group
+                .iter()
+                .filter(|node| node.index() < graph.node_count());
+            if let Some(first) = members.next() {
+                for node in members {
+                    groups.union(first.index(), node.index());
+                }
+            }
+        }
+        // The lowest node of each group stands in for the whole group
+        let mut lowest: Vec<NodeIndex> = graph.node_indices().collect();
+        for node in graph.node_indices() {
+            let root = groups.find(node.index());
+            lowest[root] = lowest[root].min(node);
+        }
+        let representative = |node: NodeIndex| lowest[groups.find(node.index())];
+
+        let inside = graph.edge_references().find(|edge| {
+            edge.source() != edge.target()
+                && representative(edge.source()) == representative(edge.target())
+        });
+        if let Some(edge) = inside
+            && self.options.on_flat_edge == FlatEdge::Conflict
+        {
+            return Err(LayoutError::InfeasibleRankConstraint { edge: edge.id() });
+        }
+        let derived = DerivedGraph::new(graph, |edge| {
+            let source = representative(edge.source());
+            let target = representative(edge.target());
+            (source != target).then_some((source, target))
+        });
+        let roots: Vec<NodeIndex> = derived.graph.node_indices().collect();
+        let cycle = back_edges(&derived.graph, &roots)
+            .into_iter()
+            .map(|edge| derived.original(edge))
+            .min();
+        if let Some(edge) = cycle {
+            return Err(LayoutError::InfeasibleRankConstraint { edge });
+        }
+
+        let mut ranks = vec![0; graph.node_count()];
+        let quotient = self
+            .for_derived(&derived)
+            .assign_layers_longest_path(&derived.graph);
+        for (rank, layer) in quotient.iter().enumerate() {
+            for node in layer {
+                ranks[node.index()] = rank;
+            }
+        }
+        let mut layers = vec![Vec::new(); quotient.len()];
+        for node in graph.node_indices() {
+            layers[ranks[representative(node).index()]].push(node);
+        }
+        self.drop_empty_layers(&mut layers);
+        Ok(layers)
+    }
+
+    /// Lay out a graph on ranks computed elsewhere, skipping layer assignment
+    ///
+    /// Every node needs a rank and every edge must point to a higher rank,
+    /// otherwise the edge is handled by `on_rank_conflict`, which fails by
+    /// default. Ranks without nodes stay empty unless `compact_ranks` is
+    /// set. Crossing reduction, coordinate assignment and edge routing run as
+    /// in [`DagreLayout::compute`], but the stages around them do not, so
+    /// these options have no effect here:
+    ///
+    /// - `pack_components`, `source_ports`, `target_ports`,
+    ///   `edge_label_sizes` and `merge_bidirectional`
+    /// - `cycle_removal`, `cycle_start` and `cycle_roots`, a cycle always
+    ///   leaves an edge for `on_rank_conflict`
+    /// - `ranking`, `fixed_ranks`, `same_rank`, `min_rank_nodes`,
+    ///   `max_rank_nodes`, `defer_ties`, `width_penalty`, `shorten_edges` and
+    ///   `isolated_lane`, the given ranks take their place
+    pub fn layout_with_ranks<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        ranks: &HashMap<NodeIndex, usize>,
+    ) -> Result<LayoutResult, LayoutError> {
+        let mut layers: Vec<Vec<NodeIndex>> = Vec::new();
+        for node in graph.node_indices() {
+            let rank = *ranks.get(&node).ok_or(LayoutError::MissingRank { node })?;
+            if rank >= layers.len() {
+                layers.resize(rank + 1, Vec::new());
+            }
+            layers[rank].push(node);
+        }
+        self.layout_resolving_conflicts(graph, layers)
+    }
+
+    /// Lay out ranked layers, resolving edges that do not point down first
+    ///
+    /// Edges within a rank are split off by `on_flat_edge` before the
+    /// remaining ones are handled by `on_rank_conflict`.
+    pub(crate) fn layout_resolving_conflicts<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        mut layers: Vec<Vec<NodeIndex>>,
+    ) -> Result<LayoutResult, LayoutError> {
+        let Some(resolved) = self.resolving_conflicts(graph, &mut layers)? else {
+            return self.layout_layers(graph, layers);
+        };
+        let ResolvedConflicts {
+            derived,
+            flat,
+            conflicts,
+        } = resolved;
+        let mut result = self
+            .for_derived(&derived)
+            .layout_layers(&derived.graph, layers)?;
+        derived.restore(&mut result);
+        if self.options.on_rank_conflict == RankConflict::DropEdge {
+            result.dropped_edges = conflicts;
+        } else {
+            for edge in &conflicts {
+                result.edge_points.get_mut(edge).unwrap().reverse();
+            }
+            result.reversed_edges = conflicts;
+        }
+        for &edge in &flat {
+            let (source, target) = graph.edge_endpoints(edge).unwrap();
+            let points = self.flat_arc(source, target, &result);
+            result.edge_points.insert(edge, points);
+        }
+        result.flat_edges = flat;
+        Ok(result)
+    }
+
+    /// Copy of the graph with the edges that do not point down resolved, if there are any
+    ///
+    /// With [`FlatEdge::NewRank`] the layers are split first. The copy
+    /// leaves out flat edges drawn as arcs and turns conflicting edges
+    /// around or leaves them out, as `on_rank_conflict` says.
+    pub(crate) fn resolving_conflicts<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        layers: &mut Vec<Vec<NodeIndex>>,
+    ) -> Result<Option<ResolvedConflicts>, LayoutError> {
+        if self.options.on_flat_edge == FlatEdge::NewRank {
+            split_flat_ranks(graph, layers);
+        }
+        let ranks = rank_of(graph, layers);
+        let arcs = self.options.on_flat_edge == FlatEdge::Arc;
+        let flat: HashSet<EdgeIndex> = graph
+            .edge_references()
+            .filter(|edge| arcs && is_flat(edge, &ranks))
+            .map(|edge| edge.id())
+            .collect();
+        let conflicts: Vec<EdgeIndex> = graph
+            .edge_references()
+            .filter(|edge| {
+                edge.source() != edge.target()
+                    && ranks[edge.target().index()] <= ranks[edge.source().index()]
+                    && !flat.contains(&edge.id())
+            })
+            .map(|edge| edge.id())
+            .collect();
+
+        if conflicts.is_empty() && flat.is_empty() {
+            return Ok(None);
+        }
+        let conflicts: HashSet<EdgeIndex> = match self.options.on_rank_conflict {
+            RankConflict::Error if !conflicts.is_empty() => {
+                return Err(LayoutError::RankConflict { edge: conflicts[0] });
+            }
+            _ => conflicts.into_iter().collect(),
+        };
+        let drop = self.options.on_rank_conflict == RankConflict::DropEdge;
+
+        let derived = DerivedGraph::new(graph, |edge| {
+            let (source, target) = (edge.source(), edge.target());
+            if flat.contains(&edge.id()) {
+                return None;
+            }
+            match (conflicts.contains(&edge.id()), drop) {
+                (false, _) => Some((source, target)),
+                (true, false) => Some((target, source)),
+                (true, true) => None,
+            }
+        });
+        Ok(Some(ResolvedConflicts {
+            derived,
+            flat,
+            conflicts,
+        }))
+    }
+
+    /// Arc between two nodes of a rank, through a point between them
+    ///
+    /// The arc bows out toward the previous rank, or the next one on the
+    /// first rank, reaching a quarter of `rank_sep` past the taller node.
+    fn flat_arc(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+        result: &LayoutResult,
+    ) -> Vec<(f32, f32)> {
+        let rank_dir = self.options.rank_dir;
+        let (from, to) = (
+            result.node_positions[&source],
+            result.node_positions[&target],
+        );
+        let half = |node| {
+            let size = self.options.node_sizes.get(&node);
+            size.map_or(0.0, |&size| rank_dir.rank(size) * 0.5)
+        };
+        let reach = half(source).max(half(target)) + self.options.rank_sep * 0.25;
+        let toward_previous = result.node_ranks[&source] > 0;
+        let sign = if toward_previous != rank_dir.is_reversed() {
+            -1.0
+        } else {
+            1.0
+        };
+        let cross = (rank_dir.cross(from) + rank_dir.cross(to)) * 0.5;
+        let apex = rank_dir.point(cross, rank_dir.rank(from) + sign * reach);
+        self.clip_endpoints(source, target, vec![from, apex, to])
+    }
+}
+
+/// Rank of every node, indexed by node index
+fn rank_of<N, E>(graph: &DiGraph<N, E>, layers: &[Vec<NodeIndex>]) -> Vec<usize> {
+    let mut ranks = vec![0; graph.node_count()];
+    for (rank, layer) in layers.iter().enumerate() {
+        for node in layer {
+            ranks[node.index()] = rank;
+        }
+    }
+    ranks
+}
+
+/// Whether an edge joins two different nodes of the same rank
+fn is_flat<E>(edge: &EdgeReference<'_, E>, ranks: &[usize]) -> bool {
+    edge.source() != edge.target() && ranks[edge.source().index()] == ranks[edge.target().index()]
+}
+
+/// Split every rank with edges inside it so those edges point one rank down
+///
+/// The nodes of a rank are spread over new ranks by longest path along
+/// the edges between them, ignoring edges that close a cycle, which stay
+/// behind for `on_rank_conflict`. Later ranks move down by the added ranks.
+fn split_flat_ranks<N, E>(graph: &DiGraph<N, E>, layers: &mut Vec<Vec<NodeIndex>>) {
+    let ranks = rank_of(graph, layers);
+    let roots: Vec<NodeIndex> = graph.node_indices().collect();
+    let back = back_edges(graph, &roots);
+    let flat = |edge: &EdgeReference<'_, E>| is_flat(edge, &ranks) && !back.contains(&edge.id());
+
+    let mut depth = vec![0; graph.node_count()];
+    let mut pending = vec![0; graph.node_count()];
+    for edge in graph.edge_references().filter(flat) {
+        pending[edge.target().index()] += 1;
+    }
+    let mut ready: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|node| pending[node.index()] == 0)
+        .collect();
+    while let Some(node) = ready.pop() {
+        for edge in graph.edges_directed(node, Outgoing).filter(flat) {
+            let target = edge.target().index();
+            depth[target] = depth[target].max(depth[node.index()] + 1);
+            pending[target] -= 1;
+            if pending[target] == 0 {
+                ready.push(edge.target());
+            }
+        }
+    }
+
+    *layers = layers
+        .drain(..)
+        .flat_map(|layer| {
+            let count = layer.iter().map(|node| depth[node.index()] + 1).max();
+            let mut split = vec![Vec::new(); count.unwrap_or(1)];
+            for node in layer {
+                split[depth[node.index()]].push(node);
+            }
+            split
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::ComponentPacking;
+    use crate::layout::LayoutOptions;
+    use crate::shape::PortSide;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_rank_conflict_strategies() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let up = graph.add_edge(a, b, ());
+        graph.add_edge(a, c, ());
+        let fixed_ranks = HashMap::from([(a, 1), (b, 0)]);
+
+        let layout = |on_rank_conflict| {
+            DagreLayout::with_options(LayoutOptions {
+                fixed_ranks: fixed_ranks.clone(),
+                on_rank_conflict,
+                ..Default::default()
+            })
+            .compute(&graph)
+        };
+
+        assert_eq!(
+            layout(RankConflict::Error).unwrap_err(),
+            LayoutError::RankConflict { edge: up }
+        );
+
+        let result = layout(RankConflict::ReverseEdge).unwrap();
+        assert_eq!(result.layers, vec![vec![b], vec![a], vec![c]]);
+        assert_eq!(result.reversed_edges, HashSet::from([up]));
+        let points = &result.edge_points[&up];
+        assert_eq!(points[0], result.node_positions[&a]);
+        assert_eq!(*points.last().unwrap(), result.node_positions[&b]);
+        assert_eq!(result.edge_points.len(), 2);
+
+        let result = layout(RankConflict::DropEdge).unwrap();
+        assert_eq!(result.dropped_edges, HashSet::from([up]));
+        assert!(!result.edge_points.contains_key(&up));
+        assert_eq!(result.edge_points.len(), 1);
+    }
+
+    #[test]
+    fn test_layout_with_ranks() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        let e = graph.add_node("E");
+        graph.add_edge(a, b, ());
+        graph.add_edge(a, c, ());
+        graph.add_edge(b, d, ());
+        graph.add_edge(c, d, ());
+        // Crosses the diamond unless E ends up on C's side of B
+        graph.add_edge(c, e, ());
+
+        // Longest path would put C on B's rank
+        let ranks = HashMap::from([(a, 0), (b, 1), (c, 2), (d, 3), (e, 3)]);
+        let layout = DagreLayout::new();
+        let result = layout.layout_with_ranks(&graph, &ranks).unwrap();
+
+        assert_eq!(result.layers.len(), 4);
+        for (&node, &rank) in &ranks {
+            assert!(result.layers[rank].contains(&node));
+            assert_eq!(result.node_positions[&node].1, rank as f32 * 100.0);
+        }
+        assert_eq!(result.edge_points.len(), 5);
+        assert_eq!(result.edge_points[&graph.find_edge(b, d).unwrap()].len(), 3);
+        let x = |node| result.node_positions[&node].0;
+        assert_eq!(x(d) < x(e), x(b) < x(c));
+
+        let backward = HashMap::from([(a, 0), (b, 1), (c, 3), (d, 2), (e, 4)]);
+        assert_eq!(
+            layout.layout_with_ranks(&graph, &backward).unwrap_err(),
+            LayoutError::RankConflict {
+                edge: graph.find_edge(c, d).unwrap()
+            }
+        );
+        assert_eq!(
+            layout
+                .layout_with_ranks(&graph, &HashMap::new())
+                .unwrap_err(),
+            LayoutError::MissingRank { node: a }
+        );
+    }
+
+    #[test]
+    fn test_layout_with_ranks_skips_surrounding_stages() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        let down = graph.add_edge(a, b, ());
+        graph.add_edge(b, a, ());
+        let labeled = graph.add_edge(c, d, ());
+        let ranks = HashMap::from([(a, 0), (b, 1), (c, 0), (d, 1)]);
+
+        let plain = DagreLayout::with_options(LayoutOptions {
+            on_rank_conflict: RankConflict::ReverseEdge,
+            ..Default::default()
+        })
+        .layout_with_ranks(&graph, &ranks)
+        .unwrap();
+        let skipped = DagreLayout::with_options(LayoutOptions {
+            on_rank_conflict: RankConflict::ReverseEdge,
+            merge_bidirectional: true,
+            edge_label_sizes: HashMap::from([(labeled, (40.0, 20.0))]),
+            source_ports: HashMap::from([(down, PortSide::Right)]),
+            pack_components: Some(ComponentPacking::Row { gap: 20.0 }),
+            ..Default::default()
+        })
+        .layout_with_ranks(&graph, &ranks)
+        .unwrap();
+
+        assert!(skipped.bidirectional_edges.is_empty());
+        assert!(skipped.edge_label_positions.is_empty());
+        assert!(skipped.components.is_empty());
+        assert_eq!(skipped.node_positions, plain.node_positions);
+        assert_eq!(skipped.edge_points, plain.edge_points);
+    }
+
+    #[test]
+    fn test_same_rank_groups() {
+        let mut graph = Graph::new();
+        let source = graph.add_node("source");
+        let near = graph.add_node("near");
+        let middle = graph.add_node("middle");
+        let far = graph.add_node("far");
+        let below = graph.add_node("below");
+        graph.add_edge(source, near, ());
+        graph.add_edge(source, middle, ());
+        let chain = graph.add_edge(middle, far, ());
+        graph.add_edge(near, below, ());
+
+        // Near joins far two ranks down and pushes its successor along
+        let layout = |same_rank| {
+            DagreLayout::with_options(LayoutOptions {
+                same_rank,
+                ..Default::default()
+            })
+            .compute(&graph)
+        };
+        let result = layout(vec![vec![near, far]]).unwrap();
+        assert_eq!(result.layers.len(), 4);
+        assert!(result.layers[2].contains(&near) && result.layers[2].contains(&far));
+        assert_eq!(result.layers[3], vec![below]);
+
+        // Overlapping groups merge, so middle and far would share a rank
+        assert_eq!(
+            layout(vec![vec![near, far], vec![near, middle]]).unwrap_err(),
+            LayoutError::InfeasibleRankConstraint { edge: chain }
+        );
+        // Source above middle and below its successor close a cycle
+        assert!(matches!(
+            layout(vec![vec![source, below]]),
+            Err(LayoutError::InfeasibleRankConstraint { .. })
+        ));
+    }
+
+    #[test]
+    fn test_min_and_max_rank_nodes() {
+        let mut graph = Graph::new();
+        let note = graph.add_node("note");
+        let input = graph.add_node("input");
+        let step = graph.add_node("step");
+        let done = graph.add_node("done");
+        let archive = graph.add_node("archive");
+        let summary = graph.add_node("summary");
+        let annotation = graph.add_edge(note, input, ());
+        graph.add_edge(input, step, ());
+        graph.add_edge(step, done, ());
+        graph.add_edge(done, archive, ());
+        let feedback = graph.add_edge(summary, step, ());
+
+        let layout = |min_rank_nodes, max_rank_nodes| {
+            DagreLayout::with_options(LayoutOptions {
+                min_rank_nodes,
+                max_rank_nodes,
+                ..Default::default()
+            })
+        };
+        let pinned = layout(HashSet::from([input]), HashSet::from([summary]));
+        let result = pinned.compute(&graph).unwrap();
+        assert_eq!(pinned.validate_layout(&graph, &result, 0.01), Ok(()));
+        assert_eq!(result.layers[0], vec![input]);
+        assert_eq!(result.layers.len(), 4);
+        assert!(result.layers[3].contains(&summary));
+        assert_eq!(result.reversed_edges, HashSet::from([annotation, feedback]));
+
+        // Two first-rank nodes cannot have an edge between them
+        assert_eq!(
+            layout(HashSet::from([note, input]), HashSet::new())
+                .compute(&graph)
+                .unwrap_err(),
+            LayoutError::InfeasibleRankConstraint { edge: annotation }
+        );
+    }
+
+    #[test]
+    fn test_isolated_lane() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let lonely = graph.add_node("lonely");
+        let b = graph.add_node("B");
+        let pinned = graph.add_node("pinned");
+        let looped = graph.add_node("looped");
+        let c = graph.add_node("C");
+        let other = graph.add_node("other");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        graph.add_edge(looped, looped, ());
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            isolated_lane: true,
+            min_rank_nodes: HashSet::from([pinned]),
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+        assert_eq!(result.isolated_nodes, HashSet::from([lonely, other]));
+        assert_eq!(result.layers.len(), 4);
+        assert_eq!(result.layers[3], vec![lonely, other]);
+        assert!(result.layers[0].contains(&pinned) && result.layers[0].contains(&looped));
+        assert_eq!(result.node_ranks[&c], 2);
+    }
+
+    #[test]
+    fn test_flat_edge_strategies() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        graph.add_edge(a, b, ());
+        graph.add_edge(a, d, ());
+        let flat = graph.add_edge(b, c, ());
+        let ranks = HashMap::from([(a, 0), (b, 1), (c, 1), (d, 1)]);
+        let options = |on_flat_edge| LayoutOptions {
+            on_flat_edge,
+            ..Default::default()
+        };
+
+        let layout = DagreLayout::with_options(options(FlatEdge::Conflict));
+        assert_eq!(
+            layout.layout_with_ranks(&graph, &ranks).unwrap_err(),
+            LayoutError::RankConflict { edge: flat }
+        );
+
+        // The arc bows up between the two nodes, which keep their rank
+        let layout = DagreLayout::with_options(options(FlatEdge::Arc));
+        let result = layout.layout_with_ranks(&graph, &ranks).unwrap();
+        assert_eq!(result.flat_edges, HashSet::from([flat]));
+        assert_eq!(result.node_ranks[&b], result.node_ranks[&c]);
+        let points = &result.edge_points[&flat];
+        let (from, to) = (result.node_positions[&b], result.node_positions[&c]);
+        assert_eq!(points.len(), 3);
+        assert_eq!((points[0], points[2]), (from, to));
+        assert_eq!(points[1].0, (from.0 + to.0) * 0.5);
+        assert!(points[1].1 < from.1);
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+
+        let layout = DagreLayout::with_options(options(FlatEdge::NewRank));
+        let result = layout.layout_with_ranks(&graph, &ranks).unwrap();
+        assert!(result.flat_edges.is_empty());
+        assert_eq!(result.layers.len(), 3);
+        assert_eq!(result.node_ranks[&c], 2);
+        assert_eq!(result.node_ranks[&d], 1);
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+
+        // Edges within a same_rank group become arcs as well
+        let layout = DagreLayout::with_options(LayoutOptions {
+            same_rank: vec![vec![b, c]],
+            ..options(FlatEdge::Arc)
+        });
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(result.flat_edges, HashSet::from([flat]));
+        assert_eq!(result.node_ranks[&c], 1);
+    }
+}
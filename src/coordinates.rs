@@ -0,0 +1,208 @@
+use crate::brandes_kopf::assign_brandes_kopf;
+use crate::layout::{Alignment, LayoutOptions};
+use crate::normalize::NodeKind;
+use petgraph::prelude::*;
+use std::collections::HashMap;
+
+/// Coordinate assignment phase of the layout pipeline
+///
+/// Receives the layers after crossing reduction, including the dummy nodes
+/// of long edges, which have indices past the last node of the input graph,
+/// and the graph they form: every input node is [`NodeKind::Real`], every
+/// dummy node a [`NodeKind::Dummy`] of its edge, and edges carry the input
+/// edge they belong to. `layers` may hold only part of the graph, as for
+/// the non-tree regions placed under `hybrid`. Returns the position of
+/// every node in `layers` along with the width and height of the layout.
+/// Later phases such as `pull_weak_nodes`, `align_spine` and edge routing
+/// work on the returned positions.
+pub trait CoordinateAssigner: Send + Sync {
+    /// Place every node of the ordered layers
+    fn assign(
+        &self,
+        graph: &DiGraph<NodeKind, EdgeIndex>,
+        layers: &[Vec<NodeIndex>],
+        options: &LayoutOptions,
+    ) -> (HashMap<NodeIndex, (f32, f32)>, f32, f32);
+}
+
+/// Aligns every node with its median neighbors using the Brandes–Köpf method
+///
+/// Chains and long edges run straight wherever that keeps them from
+/// crossing, and blocks of aligned nodes are packed as closely as
+/// `node_sep` and node sizes allow. The layout is centered within
+/// `fixed_cross_axis_width` when it is set. This is the default assigner
+/// of [`crate::DagreLayout`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BrandesKopfAssigner;
+
+impl CoordinateAssigner for BrandesKopfAssigner {
+    fn assign(
+        &self,
+        graph: &DiGraph<NodeKind, EdgeIndex>,
+        layers: &[Vec<NodeIndex>],
+        options: &LayoutOptions,
+    ) -> (HashMap<NodeIndex, (f32, f32)>, f32, f32) {
+        assign_brandes_kopf(graph, layers, options)
+    }
+}
+
+/// Places the nodes of every layer side by side and centers each layer
+///
+/// Neighboring nodes are `node_sep` apart plus half the cross-axis size of
+/// each of them, so sized nodes never overlap. Layers are aligned by
+/// `alignment` against the widest layer, or within `fixed_cross_axis_width`
+/// when it is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CenteredAssigner;
+
+impl CoordinateAssigner for CenteredAssigner {
+    fn assign(
+        &self,
+        _graph: &DiGraph<NodeKind, EdgeIndex>,
+        layers: &[Vec<NodeIndex>],
+        options: &LayoutOptions,
+    ) -> (HashMap<NodeIndex, (f32, f32)>, f32, f32) {
+        let mut positions = HashMap::new();
+        let LayoutOptions {
+            rank_dir,
+            node_sep,
+            fixed_cross_axis_width,
+            node_sizes,
+            alignment,
+            ..
+        } = options;
+        let rank_coordinates = options.rank_coordinates(layers);
+        let width_of = |node: &NodeIndex| {
+            node_sizes
+                .get(node)
+                .map_or(0.0, |&size| rank_dir.cross(size))
+        };
+
+        // Every node takes its cross-axis size plus `node_sep`
+        let layer_extent = |layer: &Vec<NodeIndex>| {
+            layer
+                .iter()
+                .map(|node| width_of(node) + node_sep)
+                .sum::<f32>()
+        };
+        let max_extent = layers.iter().map(layer_extent).fold(0.0, f32::max);
+
+        for (layer, &rank) in layers.iter().zip(&rank_coordinates) {
+            let extent = layer_extent(layer);
+
+            // Align the layer, either against the widest layer or the fixed extent
+            let slack = match fixed_cross_axis_width {
+                Some(fixed) => fixed - (extent - node_sep),
+                None => max_extent - extent,
+            };
+            let mut cross = match alignment {
+                Alignment::Center => slack * 0.5,
+                Alignment::Start => 0.0,
+                Alignment::End => slack,
+            };
+
+            for node in layer {
+                let width = width_of(node);
+                positions.insert(*node, rank_dir.point(cross + width * 0.5, rank));
+                cross += width + node_sep;
+            }
+        }
+
+        // Calculate total dimensions
+        let cross_extent = match fixed_cross_axis_width {
+            Some(fixed) => fixed.max(max_extent),
+            None => max_extent,
+        };
+        let (width, height) = rank_dir.point(cross_extent, options.rank_extent(&rank_coordinates));
+
+        (positions, width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{DagreLayout, RankDir};
+    use petgraph::Graph;
+
+    /// Puts every node on the origin
+    struct Origin;
+
+    impl CoordinateAssigner for Origin {
+        fn assign(
+            &self,
+            _graph: &DiGraph<NodeKind, EdgeIndex>,
+            layers: &[Vec<NodeIndex>],
+            _options: &LayoutOptions,
+        ) -> (HashMap<NodeIndex, (f32, f32)>, f32, f32) {
+            let positions = layers.iter().flatten().map(|&node| (node, (0.0, 0.0)));
+            (positions.collect(), 0.0, 0.0)
+        }
+    }
+
+    #[test]
+    fn test_custom_coordinate_assigner() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        let long = graph.add_edge(a, c, ());
+
+        let result = DagreLayout::new()
+            .with_coordinate_assigner(Origin)
+            .compute(&graph)
+            .unwrap();
+
+        assert_eq!(result.node_positions.len(), 3);
+        assert!(result.node_positions.values().all(|&pos| pos == (0.0, 0.0)));
+        assert_eq!((result.width, result.height), (0.0, 0.0));
+        assert_eq!(result.edge_points.len(), 3);
+        assert!(result.edge_points[&long].len() >= 2);
+        assert!(
+            result.edge_points[&long]
+                .iter()
+                .all(|&pos| pos == (0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn test_layer_alignment() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        for _ in 0..3 {
+            let child = graph.add_node("child");
+            graph.add_edge(root, child, ());
+        }
+
+        let first_cross = |rank_dir: RankDir, alignment| {
+            let layout = DagreLayout::with_options(LayoutOptions {
+                rank_dir,
+                alignment,
+                ..Default::default()
+            })
+            .with_coordinate_assigner(CenteredAssigner);
+            let result = layout.compute(&graph).unwrap();
+            assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+            let cross = |node| rank_dir.cross(result.node_positions[&node]);
+            (cross(result.layers[0][0]), cross(result.layers[1][0]))
+        };
+        assert_eq!(
+            first_cross(RankDir::TopToBottom, Alignment::Center),
+            (50.0, 0.0)
+        );
+        assert_eq!(
+            first_cross(RankDir::TopToBottom, Alignment::Start),
+            (0.0, 0.0)
+        );
+        assert_eq!(
+            first_cross(RankDir::TopToBottom, Alignment::End),
+            (100.0, 0.0)
+        );
+        assert_eq!(
+            first_cross(RankDir::LeftToRight, Alignment::End),
+            (100.0, 0.0)
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use crate::normalize::{NodeKind, NormalizedGraph};
+use petgraph::prelude::*;
+use std::collections::HashMap;
+
+/// Crossing evaluation of layer orderings in the normalized graph
+///
+/// Every crossing counts 1, or with `by_span` the combined rank span of the
+/// two input edges involved, so crossings of long edges weigh more.
+pub(crate) struct CrossingCost {
+    spans: HashMap<EdgeIndex, usize>,
+    by_span: bool,
+}
+
+impl CrossingCost {
+    pub(crate) fn new(normalized: &NormalizedGraph, by_span: bool) -> Self {
+        let spans = normalized
+            .chains
+            .iter()
+            .map(|(&edge, chain)| (edge, chain.len() + 1))
+            .collect();
+        Self { spans, by_span }
+    }
+
+    /// Rank span of an input edge
+    fn span(&self, edge: EdgeIndex) -> usize {
+        self.spans.get(&edge).copied().unwrap_or(1)
+    }
+
+    /// Weight of a crossing between the segments of two input edges
+    fn weight(&self, a: EdgeIndex, b: EdgeIndex) -> usize {
+        if self.by_span {
+            self.span(a) + self.span(b)
+        } else {
+            1
+        }
+    }
+
+    /// Weighted crossings of all segments between consecutive layers
+    ///
+    /// Segments are visited from left to right by their upper end, each one
+    /// crossing the segments before it whose lower end lies further right.
+    /// Fenwick trees over the lower layer sum those up, so a pair of layers
+    /// takes `O(segments * log(width))`.
+    pub(crate) fn total(
+        &self,
+        graph: &DiGraph<NodeKind, EdgeIndex>,
+        layers: &[Vec<NodeIndex>],
+    ) -> usize {
+        let mut lower = LayerPositions::new(graph.node_count());
+        layers
+            .windows(2)
+            .map(|pair| {
+                lower.fill(&pair[1]);
+                let mut segments = Vec::new();
+                for (upper_pos, &node) in pair[0].iter().enumerate() {
+                    for edge in graph.edges_directed(node, Outgoing) {
+                        if let Some(lower_pos) = lower.get(edge.target()) {
+                            segments.push((upper_pos, lower_pos, self.span(*edge.weight())));
+                        }
+                    }
+                }
+
+                // Lower ends are mirrored so the trees sum up those further right
+                let width = pair[1].len();
+                let mut counts = Fenwick::new(width);
+                let mut spans = Fenwick::new(width);
+                let mut cost = 0;
+                for group in segments.chunk_by(|a, b| a.0 == b.0) {
+                    for &(_, lower_pos, span) in group {
+                        let crossed = counts.before(width - 1 - lower_pos);
+                        cost += match self.by_span {
+                            true => spans.before(width - 1 - lower_pos) + crossed * span,
+                            false => crossed,
+                        };
+                    }
+                    for &(_, lower_pos, span) in group {
+                        counts.add(width - 1 - lower_pos, 1);
+                        spans.add(width - 1 - lower_pos, span);
+                    }
+                }
+                cost
+            })
+            .sum()
+    }
+
+    /// Swap neighboring nodes within layers while that lowers the cost
+    ///
+    /// Each swap only changes crossings between the segments of the two
+    /// swapped nodes, so candidates are compared on those segments alone.
+    /// Stops after `max_sweeps` sweeps or once a sweep swaps nothing.
+    pub(crate) fn transpose(
+        &self,
+        graph: &DiGraph<NodeKind, EdgeIndex>,
+        layers: &mut [Vec<NodeIndex>],
+        max_sweeps: usize,
+    ) {
+        let mut upper_positions = LayerPositions::new(graph.node_count());
+        let mut lower_positions = LayerPositions::new(graph.node_count());
+        for _ in 0..max_sweeps {
+            let mut swapped = false;
+            for i in 0..layers.len() {
+                let upper = i.checked_sub(1).map(|prev| {
+                    upper_positions.fill(&layers[prev]);
+                    &upper_positions
+                });
+                let lower = layers.get(i + 1).map(|next| {
+                    lower_positions.fill(next);
+                    &lower_positions
+                });
+                for j in 0..layers[i].len().saturating_sub(1) {
+                    let (u, v) = (layers[i][j], layers[i][j + 1]);
+                    let mut kept = 0;
+                    let mut flipped = 0;
+                    for (direction, neighbors) in [(Incoming, &upper), (Outgoing, &lower)] {
+                        let Some(neighbors) = neighbors else {
+                            continue;
+                        };
+                        let ends = |node| -> Vec<(usize, EdgeIndex)> {
+                            graph
+                                .edges_directed(node, direction)
+                                .filter_map(|edge| {
+                                    let other = match direction {
+                                        Incoming => edge.source(),
+                                        Outgoing => edge.target(),
+                                    };
+                                    Some((neighbors.get(other)?, *edge.weight()))
+                                })
+                                .collect()
+                        };
+                        let (u_ends, v_ends) = (ends(u), ends(v));
+                        for &(u_pos, u_edge) in &u_ends {
+                            for &(v_pos, v_edge) in &v_ends {
+                                if u_pos > v_pos {
+                                    kept += self.weight(u_edge, v_edge);
+                                } else if u_pos < v_pos {
+                                    flipped += self.weight(u_edge, v_edge);
+                                }
+                            }
+                        }
+                    }
+                    if flipped < kept {
+                        layers[i].swap(j, j + 1);
+                        swapped = true;
+                    }
+                }
+            }
+            if !swapped {
+                break;
+            }
+        }
+    }
+}
+
+/// Index of every node within one layer, looked up by node index
+///
+/// The buffer spans all nodes and is allocated once; pointing it at
+/// another layer only clears the entries of the previous one, so a sweep
+/// over all layers takes time in their total size rather than in
+/// `layers * nodes`.
+pub(crate) struct LayerPositions {
+    positions: Vec<Option<usize>>,
+    layer: Vec<NodeIndex>,
+}
+
+impl LayerPositions {
+    pub(crate) fn new(node_count: usize) -> Self {
+        Self {
+            positions: vec![None; node_count],
+            layer: Vec::new(),
+        }
+    }
+
+    /// Buffer holding the positions of `layer`
+    #[cfg(test)]
+    pub(crate) fn of(layer: &[NodeIndex], node_count: usize) -> Self {
+        let mut positions = Self::new(node_count);
+        positions.fill(layer);
+        positions
+    }
+
+    /// Hold the positions of `layer` instead of the layer before
+    pub(crate) fn fill(&mut self, layer: &[NodeIndex]) {
+        for node in self.layer.drain(..) {
+            self.positions[node.index()] = None;
+        }
+        for (pos, &node) in layer.iter().enumerate() {
+            self.positions[node.index()] = Some(pos);
+        }
+        self.layer.extend_from_slice(layer);
+    }
+
+    /// Position of `node` in the layer, if it is in it
+    pub(crate) fn get(&self, node: NodeIndex) -> Option<usize> {
+        self.positions[node.index()]
+    }
+
+    /// Number of nodes in the layer
+    pub(crate) fn width(&self) -> usize {
+        self.layer.len()
+    }
+}
+
+/// Fenwick tree over the positions of a layer, summing values added to them
+struct Fenwick {
+    sums: Vec<usize>,
+}
+
+impl Fenwick {
+    fn new(len: usize) -> Self {
+        Self {
+            sums: vec![0; len + 1],
+        }
+    }
+
+    /// Add `value` at `pos`
+    fn add(&mut self, pos: usize, value: usize) {
+        let mut i = pos + 1;
+        while i < self.sums.len() {
+            self.sums[i] += value;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Sum of the values at all positions before `pos`
+    fn before(&self, pos: usize) -> usize {
+        let mut i = pos;
+        let mut sum = 0;
+        while i > 0 {
+            sum += self.sums[i];
+            i &= i - 1;
+        }
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalize::normalize;
+    use petgraph::Graph;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_span_weighting_prefers_crossing_short_edges() {
+        let mut graph = Graph::new();
+        let l = graph.add_node("L");
+        let z = graph.add_node("Z");
+        let p: Vec<_> = (0..3).map(|_| graph.add_node("P")).collect();
+        let q: Vec<_> = (0..3).map(|_| graph.add_node("Q")).collect();
+        graph.add_edge(l, z, ());
+        for i in 0..3 {
+            graph.add_edge(p[i], q[i], ());
+        }
+
+        let mut layers = vec![
+            vec![l, p[0], p[1], p[2]],
+            q.clone(),
+            vec![],
+            vec![],
+            vec![z],
+        ];
+        let normalized = normalize(&graph, &mut layers, &HashSet::new());
+        let dummy = normalized.chains.values().next().unwrap()[0];
+
+        // The long edge stays left of everything, the short edges cross twice
+        let mut straight = layers.clone();
+        straight[1] = vec![dummy, q[1], q[2], q[0]];
+        // The first short edge is straightened by crossing the long edge once
+        let mut crossed = layers.clone();
+        crossed[1] = vec![q[0], dummy, q[1], q[2]];
+
+        let plain = CrossingCost::new(&normalized, false);
+        assert_eq!(plain.total(&normalized.graph, &straight), 2);
+        assert_eq!(plain.total(&normalized.graph, &crossed), 1);
+
+        let weighted = CrossingCost::new(&normalized, true);
+        assert_eq!(weighted.total(&normalized.graph, &straight), 4);
+        assert_eq!(weighted.total(&normalized.graph, &crossed), 5);
+
+        // Transposing from the crossed ordering untangles the long edge
+        weighted.transpose(&normalized.graph, &mut crossed, 10);
+        assert_eq!(weighted.total(&normalized.graph, &crossed), 0);
+    }
+
+    #[test]
+    fn test_total_matches_pairwise_count() {
+        // Every node of a layer links to a scattered set of nodes two ranks down
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..24).map(|i| graph.add_node(i)).collect();
+        for i in 0..16 {
+            for step in [3, 5, 11] {
+                let target = 8 + (i * step + i / 8) % 16;
+                if target / 8 > i / 8 {
+                    graph.add_edge(nodes[i], nodes[target], ());
+                }
+            }
+        }
+        graph.add_edge(nodes[0], nodes[20], ());
+        let mut layers: Vec<Vec<_>> = nodes.chunks(8).map(|layer| layer.to_vec()).collect();
+        layers[1].reverse();
+        let normalized = normalize(&graph, &mut layers, &HashSet::new());
+
+        for by_span in [false, true] {
+            let cost = CrossingCost::new(&normalized, by_span);
+            let mut expected = 0;
+            for pair in layers.windows(2) {
+                let index = |layer: &[NodeIndex], node| layer.iter().position(|&n| n == node);
+                let segments: Vec<_> = normalized
+                    .graph
+                    .edge_references()
+                    .filter_map(|edge| {
+                        let upper = index(&pair[0], edge.source())?;
+                        let lower = index(&pair[1], edge.target())?;
+                        Some((upper, lower, *edge.weight()))
+                    })
+                    .collect();
+                for a in &segments {
+                    for b in &segments {
+                        if a.0 < b.0 && a.1 > b.1 {
+                            expected += cost.weight(a.2, b.2);
+                        }
+                    }
+                }
+            }
+            assert!(expected > 0);
+            assert_eq!(cost.total(&normalized.graph, &layers), expected);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::layout::{DagreLayout, LayoutResult};
+use crate::normalize::NodeKind;
+use crate::routing::RoutingConstraint;
+use petgraph::graph::EdgeReference;
+use petgraph::prelude::*;
+
+/// Copy of an input graph with some edges turned around or left out
+///
+/// Nodes keep their indices, edges are renumbered. Layouts of the copy are
+/// translated back to the input graph's edge indices with
+/// [`DerivedGraph::restore`].
+pub(crate) struct DerivedGraph {
+    pub(crate) graph: DiGraph<(), ()>,
+    /// Input edge of every edge of the copy, indexed by the copy's edge index
+    original: Vec<EdgeIndex>,
+    /// Edge of the copy for every input edge, `None` for left out edges
+    derived: Vec<Option<EdgeIndex>>,
+}
+
+impl DerivedGraph {
+    /// Copy `graph`, asking `endpoints` for the source and target of every kept edge
+    pub(crate) fn new<N, E>(
+        graph: &DiGraph<N, E>,
+        mut endpoints: impl FnMut(EdgeReference<'_, E>) -> Option<(NodeIndex, NodeIndex)>,
+    ) -> Self {
+        let mut derived_graph = DiGraph::with_capacity(graph.node_count(), graph.edge_count());
+        for _ in graph.node_indices() {
+            derived_graph.add_node(());
+        }
+        let mut original = Vec::with_capacity(graph.edge_count());
+        let mut derived = vec![None; graph.edge_count()];
+        for edge in graph.edge_references() {
+            if let Some((source, target)) = endpoints(edge) {
+                derived[edge.id().index()] = Some(derived_graph.add_edge(source, target, ()));
+                original.push(edge.id());
+            }
+        }
+        Self {
+            graph: derived_graph,
+            original,
+            derived,
+        }
+    }
+
+    /// Input edge of an edge of the copy
+    pub(crate) fn original(&self, edge: EdgeIndex) -> EdgeIndex {
+        self.original[edge.index()]
+    }
+
+    /// Translate the edges of the dummy nodes of a normalized copy to the input graph
+    pub(crate) fn restore_normalized(&self, normalized: &mut DiGraph<NodeKind, ()>) {
+        for kind in normalized.node_weights_mut() {
+            if let NodeKind::Dummy(edge) = kind {
+                *edge = self.original(*edge);
+            }
+        }
+    }
+
+    /// Translate the edge indices of a layout of the copy to the input graph
+    pub(crate) fn restore(&self, result: &mut LayoutResult) {
+        let map = |edge: EdgeIndex| self.original(edge);
+        result.edge_points = result
+            .edge_points
+            .drain()
+            .map(|(edge, points)| (map(edge), points))
+            .collect();
+        result.highway_edges = result.highway_edges.drain().map(map).collect();
+        result.reversed_edges = result.reversed_edges.drain().map(map).collect();
+        result.dropped_edges = result.dropped_edges.drain().map(map).collect();
+        result.flat_edges = result.flat_edges.drain().map(map).collect();
+        result.bidirectional_edges = result
+            .bidirectional_edges
+            .drain()
+            .map(|(edge, partner)| (map(edge), map(partner)))
+            .collect();
+        for slots in result.anchor_slots.values_mut() {
+            for (edge, _) in slots.incoming.iter_mut().chain(slots.outgoing.iter_mut()) {
+                *edge = map(*edge);
+            }
+        }
+    }
+}
+
+impl DagreLayout {
+    /// Layout engine for a derived graph, with options keyed by edge translated to it
+    pub(crate) fn for_derived(&self, derived: &DerivedGraph) -> DagreLayout {
+        let mut layout = self.clone();
+        let options = &mut layout.options;
+        let map = |edge: EdgeIndex| derived.derived.get(edge.index()).copied().flatten();
+        options.edge_weights = options
+            .edge_weights
+            .drain()
+            .filter_map(|(edge, weight)| Some((map(edge)?, weight)))
+            .collect();
+        options.min_lengths = options
+            .min_lengths
+            .drain()
+            .filter_map(|(edge, length)| Some((map(edge)?, length)))
+            .collect();
+        options.routing_constraints = options
+            .routing_constraints
+            .drain(..)
+            .filter_map(|constraint| {
+                Some(RoutingConstraint {
+                    edge: map(constraint.edge)?,
+                    ..constraint
+                })
+            })
+            .collect();
+        layout
+    }
+}
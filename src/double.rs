@@ -0,0 +1,126 @@
+use crate::error::LayoutError;
+use crate::layout::{DagreLayout, LayoutResult};
+use petgraph::prelude::*;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Geometry of a [`LayoutResult`] in double precision, see [`DagreLayout::compute_f64`]
+///
+/// Only the coordinates are carried over; layers, ranks and the edge sets
+/// of the layout are found in the [`LayoutResult`] it was converted from.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutResultF64 {
+    /// Final positions for each node as (x, y) coordinates
+    pub node_positions: HashMap<NodeIndex, (f64, f64)>,
+    /// Polyline each edge should be drawn along, from source to target
+    pub edge_points: HashMap<EdgeIndex, Vec<(f64, f64)>>,
+    /// Center of the label of every labeled edge in `edge_points`
+    pub edge_label_positions: HashMap<EdgeIndex, (f64, f64)>,
+    /// Width and height of the nodes that were given a size
+    pub node_sizes: HashMap<NodeIndex, (f64, f64)>,
+    /// Total width of the layout
+    pub width: f64,
+    /// Total height of the layout
+    pub height: f64,
+}
+
+impl From<&LayoutResult> for LayoutResultF64 {
+    fn from(result: &LayoutResult) -> Self {
+        Self {
+            node_positions: widen_all(&result.node_positions),
+            edge_points: result
+                .edge_points
+                .iter()
+                .map(|(&edge, route)| (edge, route.iter().map(widen).collect()))
+                .collect(),
+            edge_label_positions: widen_all(&result.edge_label_positions),
+            node_sizes: widen_all(&result.node_sizes),
+            width: f64::from(result.width),
+            height: f64::from(result.height),
+        }
+    }
+}
+
+impl From<LayoutResult> for LayoutResultF64 {
+    fn from(result: LayoutResult) -> Self {
+        Self::from(&result)
+    }
+}
+
+/// Convert a point to double precision
+fn widen(&(x, y): &(f32, f32)) -> (f64, f64) {
+    (f64::from(x), f64::from(y))
+}
+
+/// Convert every point of a map to double precision
+fn widen_all<K: Copy + Eq + Hash>(points: &HashMap<K, (f32, f32)>) -> HashMap<K, (f64, f64)> {
+    points
+        .iter()
+        .map(|(&key, point)| (key, widen(point)))
+        .collect()
+}
+
+impl DagreLayout {
+    /// Like [`Self::compute`], with the coordinates widened to `f64`
+    ///
+    /// The layout is computed in `f32` as usual; every coordinate converts
+    /// to `f64` exactly, so the result lines up with [`Self::compute`].
+    ///
+    /// # Example
+    /// ```
+    /// use dagrers::DagreLayout;
+    /// use petgraph::Graph;
+    ///
+    /// let mut graph = Graph::new();
+    /// let a = graph.add_node("A");
+    /// let b = graph.add_node("B");
+    /// graph.add_edge(a, b, ());
+    ///
+    /// let result = DagreLayout::new().compute_f64(&graph).unwrap();
+    /// let (a, b) = (result.node_positions[&a], result.node_positions[&b]);
+    /// assert!(a.1 < b.1);
+    /// ```
+    pub fn compute_f64<N, E>(&self, graph: &DiGraph<N, E>) -> Result<LayoutResultF64, LayoutError> {
+        self.compute(graph).map(LayoutResultF64::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutOptions;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_compute_f64_widens_every_coordinate() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..5).map(|_| graph.add_node(())).collect();
+        for (source, target) in [(0, 1), (0, 2), (1, 3), (2, 3), (0, 4), (4, 3)] {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            node_sizes: HashMap::from([(nodes[0], (80.0, 30.0))]),
+            ..Default::default()
+        });
+        let single = layout.compute(&graph).unwrap();
+        let double = layout.compute_f64(&graph).unwrap();
+
+        assert_eq!(double.node_positions.len(), single.node_positions.len());
+        for (node, &(x, y)) in &single.node_positions {
+            assert_eq!(double.node_positions[node], (f64::from(x), f64::from(y)));
+        }
+        for (edge, route) in &single.edge_points {
+            let widened: Vec<_> = route
+                .iter()
+                .map(|&(x, y)| (f64::from(x), f64::from(y)))
+                .collect();
+            assert_eq!(double.edge_points[edge], widened);
+        }
+        assert_eq!(double.node_sizes[&nodes[0]], (80.0, 30.0));
+        assert_eq!(double.width, f64::from(single.width));
+        assert_eq!(double.height, f64::from(single.height));
+        assert_eq!(double, LayoutResultF64::from(single));
+    }
+}
@@ -0,0 +1,56 @@
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use std::fmt;
+
+/// Errors that can occur while computing a layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutError {
+    /// The graph needs more ranks than `allowed_rank_coordinates` provides
+    TooManyRanks {
+        /// Number of ranks produced by layer assignment
+        ranks: usize,
+        /// Number of allowed rank coordinates
+        allowed: usize,
+    },
+    /// An edge does not point to a lower rank with `fixed_ranks` or supplied ranks
+    RankConflict {
+        /// The conflicting edge
+        edge: EdgeIndex,
+    },
+    /// A node has no rank in the ranks passed to `layout_with_ranks`
+    MissingRank {
+        /// The unranked node
+        node: NodeIndex,
+    },
+    /// The nodes of a `same_rank` group cannot share a rank because of an edge
+    InfeasibleRankConstraint {
+        /// An edge between nodes of the group, or on a cycle through it
+        edge: EdgeIndex,
+    },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::TooManyRanks { ranks, allowed } => write!(
+                f,
+                "layout has {} ranks but only {} rank coordinates are allowed",
+                ranks, allowed
+            ),
+            LayoutError::RankConflict { edge } => write!(
+                f,
+                "edge {} does not point to a lower rank with the given ranks",
+                edge.index()
+            ),
+            LayoutError::MissingRank { node } => {
+                write!(f, "node {} has no rank", node.index())
+            }
+            LayoutError::InfeasibleRankConstraint { edge } => write!(
+                f,
+                "edge {} keeps the nodes of a same-rank group apart",
+                edge.index()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
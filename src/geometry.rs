@@ -0,0 +1,344 @@
+use crate::layout::LayoutResult;
+use petgraph::prelude::*;
+use std::collections::HashMap;
+
+/// Tolerance used when comparing coordinates of routed edges
+const EPSILON: f32 = 1e-3;
+
+impl LayoutResult {
+    /// Find pairs of edges whose routed segments lie on top of each other
+    ///
+    /// Two edges are reported when any segment of one is collinear with a
+    /// segment of the other and the two share more than a single point.
+    /// Edges that merely touch at a shared endpoint are not reported.
+    /// Each pair is returned once, with the smaller `EdgeIndex` first.
+    pub fn overlapping_edges(&self) -> Vec<(EdgeIndex, EdgeIndex)> {
+        let mut edges: Vec<_> = self.edge_points.iter().collect();
+        edges.sort_by_key(|(edge, _)| **edge);
+
+        let mut overlaps = Vec::new();
+        for (i, &(&edge_a, points_a)) in edges.iter().enumerate() {
+            for &(&edge_b, points_b) in edges.iter().skip(i + 1) {
+                let overlapping = points_a.windows(2).any(|seg_a| {
+                    points_b
+                        .windows(2)
+                        .any(|seg_b| segments_overlap((seg_a[0], seg_a[1]), (seg_b[0], seg_b[1])))
+                });
+                if overlapping {
+                    overlaps.push((edge_a, edge_b));
+                }
+            }
+        }
+        overlaps
+    }
+
+    /// Node positions mapped into the unit square
+    ///
+    /// The bounding box of all node centers is translated to the origin and
+    /// scaled to 1 on each axis independently, so the outermost nodes land on
+    /// 0.0 and 1.0. An axis without extent maps every coordinate to 0.0.
+    pub fn normalized_positions(&self) -> HashMap<NodeIndex, (f32, f32)> {
+        let to_unit = self.unit_transform();
+        self.node_positions
+            .iter()
+            .map(|(&node, &pos)| (node, to_unit(pos)))
+            .collect()
+    }
+
+    /// Edge points mapped with the same transform as [`Self::normalized_positions`]
+    ///
+    /// Points outside the bounding box of the node centers fall outside [0, 1].
+    pub fn normalized_edge_points(&self) -> HashMap<EdgeIndex, Vec<(f32, f32)>> {
+        let to_unit = self.unit_transform();
+        self.edge_points
+            .iter()
+            .map(|(&edge, points)| (edge, points.iter().map(|&p| to_unit(p)).collect()))
+            .collect()
+    }
+
+    /// Bounding box of all nodes as `(min_x, min_y, max_x, max_y)`
+    ///
+    /// Nodes with a size in `node_sizes` contribute their box, other nodes
+    /// their center. A layout without nodes has an empty box at the origin.
+    pub fn bounds(&self) -> (f32, f32, f32, f32) {
+        if self.node_positions.is_empty() {
+            return (0.0, 0.0, 0.0, 0.0);
+        }
+        let mut bounds = (
+            f32::INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+        );
+        for (node, &(x, y)) in &self.node_positions {
+            let (width, height) = self.node_sizes.get(node).copied().unwrap_or_default();
+            let (half_width, half_height) = (width * 0.5, height * 0.5);
+            bounds = (
+                bounds.0.min(x - half_width),
+                bounds.1.min(y - half_height),
+                bounds.2.max(x + half_width),
+                bounds.3.max(y + half_height),
+            );
+        }
+        bounds
+    }
+
+    /// Shift every node, edge point, edge label and anchor slot by `(dx, dy)`
+    ///
+    /// `width` and `height` stay the same.
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        self.map_points(|point| {
+            point.0 += dx;
+            point.1 += dy;
+        });
+    }
+
+    /// Scale the layout uniformly to fit a `width` by `height` canvas
+    ///
+    /// The [`Self::bounds`] box is scaled as much as fits while keeping its
+    /// aspect ratio, along with the node sizes, and centered on the canvas,
+    /// so it spans the full width or height. `width` and `height` are set to
+    /// the canvas. A box without extent on one axis is fitted on the other.
+    pub fn scale_to(&mut self, width: f32, height: f32) {
+        let (min_x, min_y, max_x, max_y) = self.bounds();
+        let (extent_x, extent_y) = (max_x - min_x, max_y - min_y);
+        let factor = [(width, extent_x), (height, extent_y)]
+            .into_iter()
+            .filter(|&(_, extent)| extent > EPSILON)
+            .map(|(target, extent)| target / extent)
+            .reduce(f32::min)
+            .unwrap_or(1.0);
+        let offset_x = (width - extent_x * factor) * 0.5;
+        let offset_y = (height - extent_y * factor) * 0.5;
+
+        self.map_points(|point| {
+            point.0 = (point.0 - min_x) * factor + offset_x;
+            point.1 = (point.1 - min_y) * factor + offset_y;
+        });
+        for size in self.node_sizes.values_mut() {
+            *size = (size.0 * factor, size.1 * factor);
+        }
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Apply `map` to every node, edge point, edge label and anchor slot
+    fn map_points(&mut self, map: impl Fn(&mut (f32, f32))) {
+        self.node_positions.values_mut().for_each(&map);
+        self.edge_points.values_mut().flatten().for_each(&map);
+        self.edge_label_positions.values_mut().for_each(&map);
+        self.anchor_slots
+            .values_mut()
+            .flat_map(|slots| slots.incoming.iter_mut().chain(&mut slots.outgoing))
+            .for_each(|(_, point)| map(point));
+    }
+
+    /// Nodes inside the rectangle spanned by two corners, sorted by index
+    ///
+    /// A node with a size in `node_sizes` is hit when its bounding box
+    /// intersects the rectangle, other nodes when their center lies inside
+    /// it. Points on the border count as inside, and the corners may be
+    /// given in any order.
+    pub fn nodes_in_rect(&self, x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<NodeIndex> {
+        let (min_x, max_x) = (x0.min(x1), x0.max(x1));
+        let (min_y, max_y) = (y0.min(y1), y0.max(y1));
+        let mut nodes: Vec<NodeIndex> = self
+            .node_positions
+            .iter()
+            .filter(|&(node, &(x, y))| {
+                let (width, height) = self.node_sizes.get(node).copied().unwrap_or_default();
+                let (half_width, half_height) = (width * 0.5, height * 0.5);
+                x + half_width >= min_x
+                    && x - half_width <= max_x
+                    && y + half_height >= min_y
+                    && y - half_height <= max_y
+            })
+            .map(|(&node, _)| node)
+            .collect();
+        nodes.sort();
+        nodes
+    }
+
+    /// Map from layout coordinates to the unit square of the node centers
+    fn unit_transform(&self) -> impl Fn((f32, f32)) -> (f32, f32) {
+        let mut min = (f32::INFINITY, f32::INFINITY);
+        let mut max = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for &(x, y) in self.node_positions.values() {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        let scale = |value: f32, min: f32, max: f32| {
+            if max - min > EPSILON {
+                (value - min) / (max - min)
+            } else {
+                0.0
+            }
+        };
+        move |(x, y)| (scale(x, min.0, max.0), scale(y, min.1, max.1))
+    }
+}
+
+/// Check whether two segments are collinear and share a stretch of positive length
+fn segments_overlap(a: ((f32, f32), (f32, f32)), b: ((f32, f32), (f32, f32))) -> bool {
+    let (a0, a1) = a;
+    let (b0, b1) = b;
+    let dir = (a1.0 - a0.0, a1.1 - a0.1);
+    let length = (dir.0 * dir.0 + dir.1 * dir.1).sqrt();
+    if length < EPSILON {
+        return false;
+    }
+
+    // Both endpoints of `b` must lie on the infinite line through `a`
+    let distance_to_line =
+        |p: (f32, f32)| ((p.0 - a0.0) * dir.1 - (p.1 - a0.1) * dir.0).abs() / length;
+    if distance_to_line(b0) > EPSILON || distance_to_line(b1) > EPSILON {
+        return false;
+    }
+
+    // Project `b` onto `a` and intersect the parameter ranges
+    let project = |p: (f32, f32)| ((p.0 - a0.0) * dir.0 + (p.1 - a0.1) * dir.1) / length;
+    let (t0, t1) = (project(b0), project(b1));
+    let start = t0.min(t1).max(0.0);
+    let end = t0.max(t1).min(length);
+    end - start > EPSILON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlapping_edges_on_same_x() {
+        let mut result = LayoutResult::default();
+        result
+            .edge_points
+            .insert(EdgeIndex::new(0), vec![(10.0, 0.0), (10.0, 100.0)]);
+        result.edge_points.insert(
+            EdgeIndex::new(1),
+            vec![(10.0, 50.0), (10.0, 100.0), (10.0, 150.0)],
+        );
+        result
+            .edge_points
+            .insert(EdgeIndex::new(2), vec![(60.0, 0.0), (60.0, 100.0)]);
+
+        assert_eq!(
+            result.overlapping_edges(),
+            vec![(EdgeIndex::new(0), EdgeIndex::new(1))]
+        );
+    }
+
+    #[test]
+    fn test_edges_touching_at_endpoint_do_not_overlap() {
+        let mut result = LayoutResult::default();
+        result
+            .edge_points
+            .insert(EdgeIndex::new(0), vec![(0.0, 0.0), (0.0, 100.0)]);
+        result
+            .edge_points
+            .insert(EdgeIndex::new(1), vec![(0.0, 100.0), (0.0, 200.0)]);
+        result
+            .edge_points
+            .insert(EdgeIndex::new(2), vec![(0.0, 0.0), (50.0, 100.0)]);
+
+        assert!(result.overlapping_edges().is_empty());
+    }
+
+    #[test]
+    fn test_normalized_positions_span_unit_square() {
+        let mut result = LayoutResult::default();
+        result.node_positions.insert(NodeIndex::new(0), (40.0, 0.0));
+        result
+            .node_positions
+            .insert(NodeIndex::new(1), (0.0, 100.0));
+        result
+            .node_positions
+            .insert(NodeIndex::new(2), (90.0, 200.0));
+        result
+            .edge_points
+            .insert(EdgeIndex::new(0), vec![(40.0, 0.0), (0.0, 100.0)]);
+
+        let positions = result.normalized_positions();
+        let xs = positions.values().map(|p| p.0);
+        let ys = positions.values().map(|p| p.1);
+        assert_eq!(xs.clone().fold(f32::INFINITY, f32::min), 0.0);
+        assert_eq!(xs.fold(f32::NEG_INFINITY, f32::max), 1.0);
+        assert_eq!(ys.clone().fold(f32::INFINITY, f32::min), 0.0);
+        assert_eq!(ys.fold(f32::NEG_INFINITY, f32::max), 1.0);
+        assert_eq!(positions[&NodeIndex::new(1)], (0.0, 0.5));
+
+        let edges = result.normalized_edge_points();
+        assert_eq!(
+            edges[&EdgeIndex::new(0)],
+            vec![(4.0 / 9.0, 0.0), (0.0, 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_nodes_in_rect_hits_boxes_and_points() {
+        let mut result = LayoutResult::default();
+        let (a, b, c) = (NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2));
+        result.node_positions.insert(a, (0.0, 0.0));
+        result.node_positions.insert(b, (100.0, 0.0));
+        result.node_positions.insert(c, (50.0, 100.0));
+        result.node_sizes.insert(b, (60.0, 20.0));
+
+        // The box of `b` reaches into the rectangle, its center does not
+        assert_eq!(result.nodes_in_rect(-10.0, -10.0, 75.0, 10.0), vec![a, b]);
+        assert_eq!(result.nodes_in_rect(75.0, 10.0, -10.0, -10.0), vec![a, b]);
+        assert_eq!(result.nodes_in_rect(-10.0, -10.0, 65.0, 10.0), vec![a]);
+        // Unsized nodes are points, hit on the border
+        assert_eq!(result.nodes_in_rect(50.0, 100.0, 60.0, 120.0), vec![c]);
+        assert!(result.nodes_in_rect(51.0, 90.0, 60.0, 120.0).is_empty());
+    }
+
+    #[test]
+    fn test_bounds_and_translate() {
+        let mut result = LayoutResult::default();
+        assert_eq!(result.bounds(), (0.0, 0.0, 0.0, 0.0));
+        let (a, b) = (NodeIndex::new(0), NodeIndex::new(1));
+        result.node_positions.insert(a, (10.0, 20.0));
+        result.node_positions.insert(b, (100.0, 80.0));
+        result.node_sizes.insert(b, (40.0, 20.0));
+        result
+            .edge_points
+            .insert(EdgeIndex::new(0), vec![(10.0, 20.0), (100.0, 80.0)]);
+        assert_eq!(result.bounds(), (10.0, 20.0, 120.0, 90.0));
+
+        // Moving the top left corner of the box to the origin
+        result.translate(-10.0, -20.0);
+        assert_eq!(result.bounds(), (0.0, 0.0, 110.0, 70.0));
+        assert_eq!(result.node_positions[&a], (0.0, 0.0));
+        assert_eq!(
+            result.edge_points[&EdgeIndex::new(0)],
+            vec![(0.0, 0.0), (90.0, 60.0)]
+        );
+    }
+
+    #[test]
+    fn test_scale_to_fits_canvas() {
+        let mut result = LayoutResult::default();
+        let (a, b, c) = (NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2));
+        result.node_positions.insert(a, (10.0, 10.0));
+        result.node_positions.insert(b, (110.0, 10.0));
+        result.node_positions.insert(c, (60.0, 60.0));
+        result.node_sizes.insert(c, (20.0, 20.0));
+        result
+            .edge_points
+            .insert(EdgeIndex::new(0), vec![(10.0, 10.0), (60.0, 60.0)]);
+
+        // The box is 100 by 60, so the width limits the factor to 2
+        result.scale_to(200.0, 200.0);
+        assert_eq!(result.bounds(), (0.0, 40.0, 200.0, 160.0));
+        assert_eq!((result.width, result.height), (200.0, 200.0));
+        assert_eq!(result.node_sizes[&c], (40.0, 40.0));
+        assert_eq!(
+            result.edge_points[&EdgeIndex::new(0)],
+            vec![(0.0, 40.0), (100.0, 140.0)]
+        );
+
+        // Spacing stays proportional when shrinking again
+        result.scale_to(50.0, 30.0);
+        assert_eq!(result.bounds(), (0.0, 0.0, 50.0, 30.0));
+        assert_eq!(result.node_positions[&b], (50.0, 0.0));
+    }
+}
@@ -0,0 +1,533 @@
+use crate::layout::DagreLayout;
+use crate::normalize::{NodeKind, NormalizedGraph};
+use petgraph::prelude::*;
+use petgraph::unionfind::UnionFind;
+use std::collections::{HashMap, HashSet};
+
+/// Assign coordinates per weakly connected component and pack the components side by side
+///
+/// Within a component, the tree regions of [`tree_regions`] are placed with
+/// the Reingold–Tilford algorithm, so every parent is centered over its
+/// children and mirrored subtrees come out mirrored. The remaining nodes
+/// are placed by the layout's coordinate assigner, shifted to sit under the
+/// tree nodes feeding them. Regions sharing a rank are pushed apart to keep
+/// `node_sep` between them. Components are packed along the cross axis in
+/// order of their lowest node index, separated by `node_sep`.
+pub(crate) fn assign_hybrid_coordinates(
+    layout: &DagreLayout,
+    normalized: &NormalizedGraph,
+    layers: &[Vec<NodeIndex>],
+) -> (HashMap<NodeIndex, (f32, f32)>, f32, f32) {
+    let graph = &normalized.graph;
+    let options = &layout.options;
+    let rank_dir = options.rank_dir;
+
+    let mut components = UnionFind::new(graph.node_count());
+    for edge in graph.edge_references() {
+        components.union(edge.source().index(), edge.target().index());
+    }
+
+    // Group nodes by component, keeping the crossing-reduced layer order
+    let mut component_order = Vec::new();
+    let mut component_layers: HashMap<usize, Vec<Vec<NodeIndex>>> = HashMap::new();
+    for node in graph.node_indices() {
+        let label = components.find(node.index());
+        component_layers.entry(label).or_insert_with(|| {
+            component_order.push(label);
+            vec![Vec::new(); layers.len()]
+        });
+    }
+    for (layer_idx, layer) in layers.iter().enumerate() {
+        for &node in layer {
+            let label = components.find(node.index());
+            component_layers.get_mut(&label).unwrap()[layer_idx].push(node);
+        }
+    }
+
+    let rank_coordinates = options.rank_coordinates(layers);
+    let mut positions = HashMap::new();
+    let mut offset = 0.0;
+    for label in component_order {
+        let sub_layers = &component_layers[&label];
+        let (sub_positions, extent) = place_component(layout, normalized, sub_layers);
+
+        for (layer_idx, layer) in sub_layers.iter().enumerate() {
+            let rank = rank_coordinates[layer_idx];
+            for node in layer {
+                let position = rank_dir.point(offset + sub_positions[node], rank);
+                positions.insert(*node, position);
+            }
+        }
+        offset += extent;
+    }
+
+    let (width, height) = rank_dir.point(offset, options.rank_extent(&rank_coordinates));
+    (positions, width, height)
+}
+
+/// Cross-axis coordinates of the nodes of one component and its extent
+///
+/// Coordinates are shifted so the leftmost border sits at zero, and the
+/// extent has a trailing `node_sep` as in the regular placement.
+fn place_component(
+    layout: &DagreLayout,
+    normalized: &NormalizedGraph,
+    layers: &[Vec<NodeIndex>],
+) -> (HashMap<NodeIndex, f32>, f32) {
+    let graph = &normalized.graph;
+    let rank_dir = layout.options.rank_dir;
+    let node_sep = layout.options.node_sep;
+    let width = |node| layout.cross_width(normalized, node);
+    let ranks: HashMap<NodeIndex, usize> = layers
+        .iter()
+        .enumerate()
+        .flat_map(|(rank, layer)| layer.iter().map(move |&node| (node, rank)))
+        .collect();
+
+    // Rightmost border placed so far on every rank
+    let mut right = vec![f32::NEG_INFINITY; layers.len()];
+    let mut placed: HashMap<NodeIndex, f32> = HashMap::new();
+    let place = |placed: &mut HashMap<NodeIndex, f32>,
+                 right: &mut [f32],
+                 region: HashMap<NodeIndex, f32>,
+                 desired: f32| {
+        let required = region
+            .iter()
+            .map(|(node, &x)| right[ranks[node]] + node_sep - (x - width(*node) * 0.5))
+            .fold(f32::NEG_INFINITY, f32::max);
+        let offset = desired.max(required);
+        for (node, x) in region {
+            let rank = ranks[&node];
+            right[rank] = right[rank].max(offset + x + width(node) * 0.5);
+            placed.insert(node, offset + x);
+        }
+    };
+
+    let trees = tree_regions(graph, layers);
+    for tree in &trees {
+        let region = tidy_tree(tree, node_sep, width);
+        place(&mut placed, &mut right, region, 0.0);
+    }
+
+    let in_tree: HashSet<NodeIndex> = trees
+        .iter()
+        .flat_map(|tree| tree.children.keys().copied())
+        .collect();
+    let rest: Vec<Vec<NodeIndex>> = layers
+        .iter()
+        .map(|layer| {
+            let rest = layer.iter().filter(|node| !in_tree.contains(node));
+            rest.copied().collect::<Vec<_>>()
+        })
+        .filter(|layer| !layer.is_empty())
+        .collect();
+    if !rest.is_empty() {
+        let (rest_positions, _, _) = layout.assign_coordinates(normalized, &rest);
+        let region: HashMap<NodeIndex, f32> = rest_positions
+            .into_iter()
+            .map(|(node, pos)| (node, rank_dir.cross(pos)))
+            .collect();
+
+        // Center the nodes fed by a tree under the tree nodes feeding them
+        let shifts: Vec<f32> = region
+            .iter()
+            .flat_map(|(&node, &x)| {
+                graph
+                    .neighbors_directed(node, Incoming)
+                    .filter_map(|parent| placed.get(&parent))
+                    .map(move |&parent_x| parent_x - x)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let desired = match shifts.len() {
+            0 => 0.0,
+            count => shifts.iter().sum::<f32>() / count as f32,
+        };
+        place(&mut placed, &mut right, region, desired);
+    }
+
+    let border = |(&node, &x): (&NodeIndex, &f32), side: f32| x + side * width(node) * 0.5;
+    let min = placed
+        .iter()
+        .map(|entry| border(entry, -1.0))
+        .fold(f32::INFINITY, f32::min);
+    let max = placed
+        .iter()
+        .map(|entry| border(entry, 1.0))
+        .fold(f32::NEG_INFINITY, f32::max);
+    let positions = placed
+        .into_iter()
+        .map(|(node, x)| (node, x - min))
+        .collect();
+    (positions, max - min + node_sep)
+}
+
+/// Rooted tree within a component, with the children of every node in layer order
+struct Tree {
+    root: NodeIndex,
+    children: HashMap<NodeIndex, Vec<NodeIndex>>,
+}
+
+impl Tree {
+    fn children(&self, node: NodeIndex) -> &[NodeIndex] {
+        &self.children[&node]
+    }
+}
+
+/// Tree-shaped regions of a component
+///
+/// A region grows from every source of the component through the nodes
+/// with a single incoming edge, so it is a tree hanging from that source;
+/// nodes with several parents and everything below them are left to the
+/// regular placement. Sources without such a child form no region.
+fn tree_regions(graph: &DiGraph<NodeKind, EdgeIndex>, layers: &[Vec<NodeIndex>]) -> Vec<Tree> {
+    let order: HashMap<NodeIndex, usize> = layers
+        .iter()
+        .flat_map(|layer| layer.iter().enumerate().map(|(pos, &node)| (node, pos)))
+        .collect();
+    let tree_children = |node: NodeIndex| {
+        let mut children: Vec<NodeIndex> = graph
+            .neighbors_directed(node, Outgoing)
+            .filter(|&child| graph.neighbors_directed(child, Incoming).count() == 1)
+            .collect();
+        children.sort_by_key(|child| order[child]);
+        children
+    };
+
+    let mut trees = Vec::new();
+    for &root in layers.iter().flatten() {
+        if graph.neighbors_directed(root, Incoming).next().is_some() {
+            continue;
+        }
+        let mut children = HashMap::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            let below = tree_children(node);
+            stack.extend(&below);
+            children.insert(node, below);
+        }
+        if !children[&root].is_empty() {
+            trees.push(Tree { root, children });
+        }
+    }
+    trees
+}
+
+/// Cross-axis extent of a subtree at each depth below its root, from border to border
+///
+/// Depths are kept deepest first, so a parent adds its own on top, and
+/// `offset` moves all of them at once. Merging two contours then only
+/// touches the depths they share, which keeps placing a tree linear in
+/// its size.
+struct Contour {
+    offset: f32,
+    levels: Vec<(f32, f32)>,
+}
+
+impl Contour {
+    fn new(half: f32) -> Self {
+        Self {
+            offset: 0.0,
+            levels: vec![(-half, half)],
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Borders at `depth` below the top
+    fn level(&self, depth: usize) -> (f32, f32) {
+        let (left, right) = self.levels[self.levels.len() - 1 - depth];
+        (left + self.offset, right + self.offset)
+    }
+
+    /// How far `right` has to move to keep `node_sep` to the right of this contour
+    fn clearance(&self, right: &Contour, node_sep: f32) -> f32 {
+        (0..self.depth().min(right.depth()))
+            .map(|depth| self.level(depth).1 - right.level(depth).0 + node_sep)
+            .fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    /// Combine with the contour of the next sibling, already moved into place
+    ///
+    /// Left borders come from this contour where it reaches, right borders
+    /// from `right`; the deeper contour is reused so only shared depths are
+    /// written.
+    fn merge(self, right: Contour) -> Contour {
+        let shared = self.depth().min(right.depth());
+        if self.depth() >= right.depth() {
+            let mut merged = self;
+            for depth in 0..shared {
+                let index = merged.levels.len() - 1 - depth;
+                merged.levels[index].1 = right.level(depth).1 - merged.offset;
+            }
+            merged
+        } else {
+            let mut merged = right;
+            for depth in 0..shared {
+                let index = merged.levels.len() - 1 - depth;
+                merged.levels[index].0 = self.level(depth).0 - merged.offset;
+            }
+            merged
+        }
+    }
+
+    /// Put a node of the given half width on top, centered at zero
+    fn push_top(&mut self, half: f32) {
+        self.levels.push((-half - self.offset, half - self.offset));
+    }
+}
+
+/// Place a tree with the Reingold–Tilford algorithm
+///
+/// Children are visited in their layer order, and neighbors at the same
+/// depth keep `node_sep` plus half of either one's cross-axis `width`
+/// apart, like in the regular placement. Subtrees are placed bottom-up
+/// with an explicit stack, so deep trees do not exhaust the call stack.
+/// Returns the cross-axis coordinate of every node of the tree, with the
+/// leftmost border at zero.
+fn tidy_tree(
+    tree: &Tree,
+    node_sep: f32,
+    width: impl Fn(NodeIndex) -> f32,
+) -> HashMap<NodeIndex, f32> {
+    // Offset of every child from its parent, children visited before parents
+    let mut relative = HashMap::new();
+    let mut contours: HashMap<NodeIndex, Contour> = HashMap::new();
+    let mut stack = vec![(tree.root, false)];
+    while let Some((node, visited)) = stack.pop() {
+        let children = tree.children(node);
+        if !visited && !children.is_empty() {
+            stack.push((node, true));
+            stack.extend(children.iter().rev().map(|&child| (child, false)));
+            continue;
+        }
+
+        let half = width(node) * 0.5;
+        let mut merged: Option<Contour> = None;
+        let mut child_x = Vec::with_capacity(children.len());
+        // Push every child subtree right until it clears its left siblings
+        for child in children {
+            let mut contour = contours.remove(child).unwrap();
+            let shift = merged
+                .as_ref()
+                .map_or(0.0, |merged| merged.clearance(&contour, node_sep));
+            contour.offset += shift;
+            child_x.push(shift);
+            merged = Some(match merged {
+                Some(merged) => merged.merge(contour),
+                None => contour,
+            });
+        }
+        relative.insert(node, 0.0);
+        let contour = match merged {
+            Some(mut merged) => {
+                // Center the parent over its outermost children
+                let center = (child_x[0] + child_x[child_x.len() - 1]) * 0.5;
+                for (&child, &x) in children.iter().zip(&child_x) {
+                    relative.insert(child, x - center);
+                }
+                merged.offset -= center;
+                merged.push_top(half);
+                merged
+            }
+            None => Contour::new(half),
+        };
+        contours.insert(node, contour);
+    }
+
+    // Accumulate the offsets from the root downward
+    let mut absolute = HashMap::new();
+    let mut stack = vec![(tree.root, 0.0)];
+    while let Some((node, parent_x)) = stack.pop() {
+        let x = parent_x + relative[&node];
+        absolute.insert(node, x);
+        for &child in tree.children(node) {
+            stack.push((child, x));
+        }
+    }
+
+    let min = absolute
+        .iter()
+        .map(|(&node, &x)| x - width(node) * 0.5)
+        .fold(f32::INFINITY, f32::min);
+    absolute
+        .into_iter()
+        .map(|(node, x)| (node, x - min))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::{DagreLayout, LayoutOptions};
+    use petgraph::Graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_hybrid_tree_and_dag_components() {
+        let mut graph = Graph::new();
+
+        // Unbalanced tree: root -> {a, b}, a -> {a1, a2, a3}, b -> {b1}
+        let root = graph.add_node("root");
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        graph.add_edge(root, a, ());
+        graph.add_edge(root, b, ());
+        let a_children: Vec<_> = (0..3)
+            .map(|_| {
+                let child = graph.add_node("a-child");
+                graph.add_edge(a, child, ());
+                child
+            })
+            .collect();
+        let b1 = graph.add_node("b1");
+        graph.add_edge(b, b1, ());
+
+        // Crossing-prone DAG component
+        let top = graph.add_node("top");
+        let l1 = graph.add_node("l1");
+        let l2 = graph.add_node("l2");
+        let r1 = graph.add_node("r1");
+        let r2 = graph.add_node("r2");
+        graph.add_edge(top, l1, ());
+        graph.add_edge(top, l2, ());
+        graph.add_edge(l1, r2, ());
+        graph.add_edge(l2, r1, ());
+
+        let options = LayoutOptions {
+            hybrid: true,
+            ..Default::default()
+        };
+        let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+        let x = |node| result.node_positions[&node].0;
+
+        // Tree parents sit centered over their children
+        assert_eq!(x(root), (x(a) + x(b)) * 0.5);
+        let (first, last) = (x(a_children[0]), x(a_children[2]));
+        assert_eq!(x(a), (first.min(last) + first.max(last)) * 0.5);
+        assert_eq!(x(b), x(b1));
+        for pair in a_children.windows(2) {
+            assert!((x(pair[0]) - x(pair[1])).abs() >= 50.0);
+        }
+        // Subtrees do not overlap
+        assert!((x(b1) - x(a_children[0])).abs() >= 50.0);
+        assert!((x(b1) - x(a_children[2])).abs() >= 50.0);
+
+        // Components are packed side by side without overlapping
+        let tree_nodes = [root, a, b, b1, a_children[0], a_children[1], a_children[2]];
+        let tree_max = tree_nodes.iter().map(|&n| x(n)).fold(f32::MIN, f32::max);
+        let dag_min = [top, l1, l2, r1, r2]
+            .iter()
+            .map(|&n| x(n))
+            .fold(f32::MAX, f32::min);
+        assert!(dag_min >= tree_max + 50.0);
+
+        // The DAG component was crossing-reduced
+        assert_eq!(x(l1) < x(l2), x(r2) < x(r1));
+    }
+
+    #[test]
+    fn test_hybrid_tree_spaces_sized_nodes() {
+        // A tree of two wide siblings beside a single node
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let left = graph.add_node("left");
+        let right = graph.add_node("right");
+        graph.add_edge(root, left, ());
+        graph.add_edge(root, right, ());
+        let lone = graph.add_node("lone");
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            hybrid: true,
+            node_sizes: HashMap::from([(left, (200.0, 20.0)), (right, (200.0, 20.0))]),
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        let x = |node| result.node_positions[&node].0;
+        let node_sep = layout.options.node_sep;
+
+        // Siblings keep node_sep between their borders and the tree starts at zero
+        assert_eq!(x(right) - x(left), 200.0 + node_sep);
+        assert_eq!(x(left), 100.0);
+        assert_eq!(x(root), (x(left) + x(right)) * 0.5);
+        // The next component starts after the tree's full extent
+        assert_eq!(x(lone), x(right) + 100.0 + node_sep);
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+    }
+
+    #[test]
+    fn test_hybrid_tree_feeding_into_a_diamond() {
+        // root -> {a, b}, a -> {a1, a2}, b -> {b1, b2}, with the middle
+        // leaves feeding a diamond whose arms cross on their way down
+        let mut graph = Graph::new();
+        let names = [
+            "root", "a", "b", "a1", "a2", "b1", "b2", "top", "l", "r", "x", "y", "bottom",
+        ];
+        let nodes: HashMap<&str, _> = names
+            .iter()
+            .map(|&name| (name, graph.add_node(name)))
+            .collect();
+        let edges = [
+            ("root", "a"),
+            ("root", "b"),
+            ("a", "a1"),
+            ("a", "a2"),
+            ("b", "b1"),
+            ("b", "b2"),
+            ("a2", "top"),
+            ("b1", "top"),
+            ("top", "l"),
+            ("top", "r"),
+            ("l", "y"),
+            ("r", "x"),
+            ("x", "bottom"),
+            ("y", "bottom"),
+        ];
+        for (source, target) in edges {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            hybrid: true,
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+        let x = |name| result.node_positions[&nodes[name]].0;
+
+        // The tree part is symmetric around its root
+        assert_eq!(x("root"), (x("a") + x("b")) * 0.5);
+        assert_eq!(x("a"), (x("a1") + x("a2")) * 0.5);
+        assert_eq!(x("b"), (x("b1") + x("b2")) * 0.5);
+        assert_eq!(x("a2") - x("a1"), x("b2") - x("b1"));
+        assert_eq!(x("root") - x("a2"), x("b1") - x("root"));
+
+        // The diamond hangs centered below the leaves feeding it and was
+        // crossing-reduced
+        assert_eq!(x("top"), x("root"));
+        assert_eq!(x("l") < x("r"), x("y") < x("x"));
+    }
+
+    #[test]
+    fn test_hybrid_deep_tree_does_not_overflow() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..20_000).map(|_| graph.add_node(())).collect();
+        for pair in nodes.windows(2) {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+        let side = graph.add_node(());
+        graph.add_edge(nodes[0], side, ());
+
+        let result = DagreLayout::with_options(LayoutOptions {
+            hybrid: true,
+            ..Default::default()
+        })
+        .compute(&graph)
+        .unwrap();
+        assert_eq!(result.node_positions.len(), graph.node_count());
+        let x = |node| result.node_positions[&node].0;
+        assert_eq!(x(nodes[0]), (x(nodes[1]) + x(side)) * 0.5);
+        assert_eq!(x(nodes[1]), x(nodes[19_999]));
+    }
+}
@@ -0,0 +1,347 @@
+use crate::error::LayoutError;
+use crate::layout::{DagreLayout, LayoutOptions, LayoutResult};
+use crate::ranking::RankingAlgorithm;
+use petgraph::algo::is_cyclic_directed;
+use petgraph::prelude::*;
+use std::collections::HashMap;
+
+/// Layout of a graph that grows one node or edge at a time
+///
+/// Ranks are updated locally when an edge arrives, only the nodes below
+/// the new edge move down. Crossing reduction starts from the order of the
+/// previous layout, so it usually settles after a single sweep. The layout
+/// itself is computed lazily by [`IncrementalLayout::current_layout`], or
+/// right away by [`IncrementalLayout::relayout`]. The previous order also
+/// breaks ties in crossing reduction like `initial_order`, so nodes the
+/// graph change has no bearing on stay where they were.
+///
+/// With sticky children enabled, the children of every node also keep the
+/// relative order they had in the previous layout, and new children are
+/// placed among them by crossing reduction.
+pub struct IncrementalLayout<N, E> {
+    layout: DagreLayout,
+    graph: DiGraph<N, E>,
+    /// Longest-path rank of every node, indexed by node index
+    ranks: Vec<usize>,
+    /// Position of each node within its layer in the previous layout
+    order: HashMap<NodeIndex, usize>,
+    /// Ranks can no longer be maintained locally and are recomputed on demand
+    stale_ranks: bool,
+    /// Restore each node's recorded child order after crossing reduction
+    sticky_children: bool,
+    /// Children of each node ordered by rank and position in the previous layout
+    child_order: HashMap<NodeIndex, Vec<NodeIndex>>,
+    cached: Option<LayoutResult>,
+}
+
+impl<N, E> IncrementalLayout<N, E> {
+    /// Create an empty incremental layout with default options
+    pub fn new() -> Self {
+        Self::with_options(LayoutOptions::default())
+    }
+
+    /// Create an empty incremental layout with custom options
+    pub fn with_options(options: LayoutOptions) -> Self {
+        Self {
+            stale_ranks: needs_full_ranking(&options),
+            layout: DagreLayout::with_options(options),
+            graph: DiGraph::new(),
+            ranks: Vec::new(),
+            order: HashMap::new(),
+            sticky_children: false,
+            child_order: HashMap::new(),
+            cached: None,
+        }
+    }
+
+    /// Keep the relative order of every node's children across relayouts
+    pub fn set_sticky_children(&mut self, sticky: bool) {
+        self.sticky_children = sticky;
+        self.cached = None;
+    }
+
+    /// The graph built so far
+    pub fn graph(&self) -> &DiGraph<N, E> {
+        &self.graph
+    }
+
+    /// Add a node on the first rank
+    pub fn add_node(&mut self, weight: N) -> NodeIndex {
+        self.cached = None;
+        self.ranks.push(0);
+        self.graph.add_node(weight)
+    }
+
+    /// Add an edge and push its target and everything below it down as needed
+    pub fn add_edge(&mut self, source: NodeIndex, target: NodeIndex, weight: E) -> EdgeIndex {
+        self.cached = None;
+        let edge = self.graph.add_edge(source, target, weight);
+        if !self.stale_ranks {
+            self.propagate_rank(source, target);
+        }
+        edge
+    }
+
+    /// Lay the current graph out again, starting from the previous order
+    pub fn relayout(&mut self) -> Result<&LayoutResult, LayoutError> {
+        self.cached = None;
+        self.current_layout()
+    }
+
+    /// Layout of the current graph, recomputed only if it changed
+    pub fn current_layout(&mut self) -> Result<&LayoutResult, LayoutError> {
+        if self.cached.is_none() {
+            if self.stale_ranks {
+                self.rerank();
+            }
+
+            let rank_count = self.ranks.iter().max().map_or(0, |max| max + 1);
+            let mut layers = vec![Vec::new(); rank_count];
+            for node in self.graph.node_indices() {
+                layers[self.ranks[node.index()]].push(node);
+            }
+            for layer in &mut layers {
+                layer.sort_by_key(|node| {
+                    (self.order.get(node).copied().unwrap_or(usize::MAX), *node)
+                });
+            }
+
+            let mut layout = self.layout.clone();
+            layout.options.initial_order.extend(&self.order);
+            let result = layout.layout_layers_with(&self.graph, layers, |layers| {
+                if self.sticky_children {
+                    restore_child_order(&self.child_order, layers);
+                }
+            })?;
+            let position: HashMap<NodeIndex, (usize, usize)> = result
+                .layers
+                .iter()
+                .enumerate()
+                .flat_map(|(rank, layer)| {
+                    layer
+                        .iter()
+                        .enumerate()
+                        .map(move |(pos, &node)| (node, (rank, pos)))
+                })
+                .collect();
+            self.order = position
+                .iter()
+                .map(|(&node, &(_, pos))| (node, pos))
+                .collect();
+            self.child_order = self
+                .graph
+                .node_indices()
+                .map(|parent| {
+                    let mut children: Vec<NodeIndex> =
+                        self.graph.neighbors_directed(parent, Outgoing).collect();
+                    children.sort_by_key(|child| (position[child], *child));
+                    children.dedup();
+                    (parent, children)
+                })
+                .collect();
+            self.cached = Some(result);
+        }
+        Ok(self.cached.as_ref().unwrap())
+    }
+
+    /// Raise the ranks below a new edge until every edge points down again
+    fn propagate_rank(&mut self, source: NodeIndex, target: NodeIndex) {
+        let mut stack = vec![(target, self.ranks[source.index()] + 1)];
+        while let Some((node, rank)) = stack.pop() {
+            if rank <= self.ranks[node.index()] {
+                continue;
+            }
+            if node == source {
+                // The edge closed a cycle, fall back to full ranking
+                self.stale_ranks = true;
+                return;
+            }
+            self.ranks[node.index()] = rank;
+            stack.extend(
+                self.graph
+                    .neighbors_directed(node, Outgoing)
+                    .map(|successor| (successor, rank + 1)),
+            );
+        }
+    }
+
+    /// Recompute all ranks from scratch
+    ///
+    /// Ranks of a cyclic graph stay stale, a new edge into a cycle would
+    /// otherwise push the nodes of the cycle down forever.
+    fn rerank(&mut self) {
+        let layers = self.layout.assign_layers_longest_path(&self.graph);
+        for (rank, layer) in layers.iter().enumerate() {
+            for node in layer {
+                self.ranks[node.index()] = rank;
+            }
+        }
+        self.stale_ranks =
+            needs_full_ranking(&self.layout.options) || is_cyclic_directed(&self.graph);
+    }
+}
+
+/// Put the recorded children of every parent back into their recorded order
+///
+/// Within each layer the recorded children of a parent are permuted among
+/// the slots they occupy, so other nodes, including new children, keep the
+/// slots crossing reduction gave them. Parents are applied in index order.
+fn restore_child_order(
+    child_order: &HashMap<NodeIndex, Vec<NodeIndex>>,
+    layers: &mut [Vec<NodeIndex>],
+) {
+    let mut parents: Vec<NodeIndex> = child_order.keys().copied().collect();
+    parents.sort();
+
+    for parent in parents {
+        let recorded: HashMap<NodeIndex, usize> = child_order[&parent]
+            .iter()
+            .enumerate()
+            .map(|(order, &child)| (child, order))
+            .collect();
+        if recorded.len() < 2 {
+            continue;
+        }
+        for layer in layers.iter_mut() {
+            let slots: Vec<usize> = (0..layer.len())
+                .filter(|&slot| recorded.contains_key(&layer[slot]))
+                .collect();
+            let mut children: Vec<NodeIndex> = slots.iter().map(|&slot| layer[slot]).collect();
+            children.sort_by_key(|child| recorded[child]);
+            for (slot, child) in slots.into_iter().zip(children) {
+                layer[slot] = child;
+            }
+        }
+    }
+}
+
+/// Whether the ranking options reshape ranks globally, so local updates would diverge
+fn needs_full_ranking(options: &LayoutOptions) -> bool {
+    options.defer_ties
+        || options.shorten_edges
+        || options.width_penalty > 0.0
+        || options.ranking != RankingAlgorithm::LongestPath
+}
+
+impl<N, E> Default for IncrementalLayout<N, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_streamed_diamond_matches_full_layout() {
+        let mut incremental = IncrementalLayout::new();
+        let nodes: Vec<_> = ["A", "B", "C", "D"]
+            .into_iter()
+            .map(|name| incremental.add_node(name))
+            .collect();
+        for (source, target) in [(0, 1), (0, 2), (1, 3), (2, 3)] {
+            incremental.add_edge(nodes[source], nodes[target], ());
+            incremental.current_layout().unwrap();
+        }
+        let streamed = incremental.current_layout().unwrap().clone();
+
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        graph.add_edge(a, b, ());
+        graph.add_edge(a, c, ());
+        graph.add_edge(b, d, ());
+        graph.add_edge(c, d, ());
+        let full = DagreLayout::new().compute(&graph).unwrap();
+
+        let sorted = |layers: &[Vec<NodeIndex>]| -> Vec<Vec<NodeIndex>> {
+            layers
+                .iter()
+                .map(|layer| {
+                    let mut layer = layer.clone();
+                    layer.sort();
+                    layer
+                })
+                .collect()
+        };
+        assert_eq!(sorted(&streamed.layers), sorted(&full.layers));
+        assert_eq!((streamed.width, streamed.height), (full.width, full.height));
+        assert_eq!(streamed.edge_points.len(), full.edge_points.len());
+        for node in graph.node_indices() {
+            let (x, y) = streamed.node_positions[&node];
+            assert_eq!(y, full.node_positions[&node].1);
+            assert!(full.node_positions.values().any(|&(fx, _)| fx == x));
+        }
+    }
+
+    #[test]
+    fn test_edge_into_existing_cycle_keeps_full_ranking() {
+        let mut incremental = IncrementalLayout::new();
+        let [a, b, c, d] = ["A", "B", "C", "D"].map(|name| incremental.add_node(name));
+        incremental.add_edge(a, b, ());
+        incremental.add_edge(b, a, ());
+        incremental.current_layout().unwrap();
+
+        // The cycle is still there, so new edges must not push ranks locally
+        incremental.add_edge(c, d, ());
+        incremental.add_edge(d, a, ());
+        let streamed = incremental.current_layout().unwrap().clone();
+        let full = DagreLayout::new().compute(incremental.graph()).unwrap();
+        let ranks = |result: &LayoutResult| [a, b, c, d].map(|node| result.node_ranks[&node]);
+        assert_eq!(ranks(&streamed), ranks(&full));
+        assert_eq!(streamed.edge_points.len(), 4);
+    }
+
+    #[test]
+    fn test_sticky_children_keep_their_order() {
+        let mut incremental = IncrementalLayout::new();
+        incremental.set_sticky_children(true);
+        let parent = incremental.add_node("parent");
+        let children: Vec<_> = (0..3)
+            .map(|_| {
+                let child = incremental.add_node("child");
+                incremental.add_edge(parent, child, ());
+                child
+            })
+            .collect();
+        let x = |layout: &LayoutResult, node| layout.node_positions[&node].0;
+        let before = incremental.current_layout().unwrap().clone();
+        let mut original = children.clone();
+        original.sort_by(|&a, &b| x(&before, a).total_cmp(&x(&before, b)));
+
+        // A new source next to the parent pulls the first child to its side
+        let fourth = incremental.add_node("fourth");
+        incremental.add_edge(parent, fourth, ());
+        let other = incremental.add_node("other");
+        incremental.add_edge(other, original[0], ());
+        let after = incremental.current_layout().unwrap();
+
+        let mut kept = children.clone();
+        kept.sort_by(|&a, &b| x(after, a).total_cmp(&x(after, b)));
+        assert_eq!(kept, original);
+        assert!(after.node_positions.contains_key(&fourth));
+    }
+
+    #[test]
+    fn test_relayout_keeps_previous_order_on_ties() {
+        let mut incremental = IncrementalLayout::new();
+        let a = incremental.add_node("A");
+        let b = incremental.add_node("B");
+        let x = incremental.add_node("X");
+        let y = incremental.add_node("Y");
+        incremental.add_edge(a, y, ());
+        incremental.add_edge(b, x, ());
+        assert_eq!(incremental.relayout().unwrap().layers[1], vec![y, x]);
+
+        // Both children now hang off both parents and tie on every sweep
+        incremental.add_edge(a, x, ());
+        incremental.add_edge(b, y, ());
+        assert_eq!(incremental.relayout().unwrap().layers[1], vec![y, x]);
+        let full = DagreLayout::new().compute(incremental.graph()).unwrap();
+        assert_eq!(full.layers[1], vec![x, y]);
+    }
+}
@@ -0,0 +1,331 @@
+use crate::error::LayoutError;
+use crate::layout::{DagreLayout, LayoutResult};
+use crate::normalize::NodeKind;
+use crate::routing::RoutingConstraint;
+use petgraph::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Copy of an input graph with every labeled edge split at a label node
+///
+/// Input nodes keep their indices and label nodes follow them. A labeled
+/// edge becomes an edge into its label node and one out of it, all other
+/// edges are copied. Layouts of the copy are translated back to the input
+/// graph with [`LabeledGraph::restore`].
+pub(crate) struct LabeledGraph {
+    pub(crate) graph: DiGraph<(), ()>,
+    /// Input edge of every edge of the copy, indexed by the copy's edge index
+    original: Vec<EdgeIndex>,
+    /// Label node of every labeled input edge
+    labels: HashMap<EdgeIndex, NodeIndex>,
+    real_count: usize,
+}
+
+impl LabeledGraph {
+    /// Translate a layout of the copy to the input graph
+    ///
+    /// Label nodes leave the layout and their positions become the label
+    /// positions. The two halves of a labeled edge are joined at the label,
+    /// and the edge counts as reversed when its target ends up on an
+    /// earlier rank than its source.
+    pub(crate) fn restore(&self, result: &mut LayoutResult) {
+        let real = |node: &NodeIndex| node.index() < self.real_count;
+        let map = |edge: EdgeIndex| self.original[edge.index()];
+
+        let mut halves: HashMap<EdgeIndex, Vec<_>> = HashMap::new();
+        for (edge, points) in result.edge_points.drain() {
+            halves.entry(map(edge)).or_default().push((edge, points));
+        }
+        result.edge_points = halves
+            .into_iter()
+            .filter_map(|(edge, mut halves)| {
+                let Some(&label) = self.labels.get(&edge) else {
+                    return halves.pop().map(|(_, points)| (edge, points));
+                };
+                halves.sort_by_key(|&(half, _)| half);
+                let [(_, first), (_, second)] = <[_; 2]>::try_from(halves).ok()?;
+                let mut points = first[..first.len() - 1].to_vec();
+                points.push(result.node_positions[&label]);
+                points.extend_from_slice(&second[1..]);
+                Some((edge, points))
+            })
+            .collect();
+        result.edge_label_positions = self
+            .labels
+            .iter()
+            .filter(|(edge, _)| result.edge_points.contains_key(edge))
+            .map(|(&edge, label)| (edge, result.node_positions[label]))
+            .collect();
+
+        let reversed: HashSet<EdgeIndex> = result.reversed_edges.drain().map(map).collect();
+        result.reversed_edges = reversed
+            .into_iter()
+            .filter(|edge| !self.labels.contains_key(edge))
+            .collect();
+        for (&edge, &label) in &self.labels {
+            let source = self.graph.edges_directed(label, Incoming).next();
+            let target = self.graph.edges_directed(label, Outgoing).next();
+            if let (Some(source), Some(target)) = (source, target)
+                && result.node_ranks[&target.target()] < result.node_ranks[&source.source()]
+            {
+                result.reversed_edges.insert(edge);
+            }
+        }
+        result.highway_edges = result.highway_edges.drain().map(map).collect();
+        result.dropped_edges = result.dropped_edges.drain().map(map).collect();
+        result.flat_edges = result.flat_edges.drain().map(map).collect();
+        for edge in &result.dropped_edges {
+            result.edge_points.remove(edge);
+            result.edge_label_positions.remove(edge);
+        }
+        result.bidirectional_edges = result
+            .bidirectional_edges
+            .drain()
+            .map(|(edge, partner)| (map(edge), map(partner)))
+            .collect();
+
+        result.node_positions.retain(|node, _| real(node));
+        result.node_sizes.retain(|node, _| real(node));
+        result.node_ranks.retain(|node, _| real(node));
+        result.anchor_slots.retain(|node, _| real(node));
+        for slots in result.anchor_slots.values_mut() {
+            for (edge, _) in slots.incoming.iter_mut().chain(slots.outgoing.iter_mut()) {
+                *edge = map(*edge);
+            }
+        }
+        for layer in &mut result.layers {
+            layer.retain(real);
+        }
+    }
+
+    /// Translate a normalized copy to the input graph
+    ///
+    /// Label nodes become [`NodeKind::Dummy`] nodes of their labeled edge,
+    /// like the bend points of the edge.
+    pub(crate) fn restore_normalized(&self, normalized: &mut DiGraph<NodeKind, ()>) {
+        let edge_of: HashMap<NodeIndex, EdgeIndex> = self
+            .labels
+            .iter()
+            .map(|(&edge, &label)| (label, edge))
+            .collect();
+        for kind in normalized.node_weights_mut() {
+            *kind = match *kind {
+                NodeKind::Real(node) => match edge_of.get(&node) {
+                    Some(&edge) => NodeKind::Dummy(edge),
+                    None => NodeKind::Real(node),
+                },
+                NodeKind::Dummy(edge) => NodeKind::Dummy(self.original[edge.index()]),
+            };
+        }
+    }
+}
+
+impl DagreLayout {
+    /// Layout engine and copy of the graph with labeled edges split, if `edge_label_sizes` has any
+    ///
+    /// Every label node takes the cross-axis size of its label and no space
+    /// along the rank axis, so it gets a rank of its own without moving the
+    /// ranks around it apart. Self-loops keep no label. Options keyed by
+    /// edge apply to both halves of a labeled edge, except `min_lengths`,
+    /// which the label's rank counts toward.
+    pub(crate) fn splitting_labeled_edges<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Option<(DagreLayout, LabeledGraph)> {
+        let label_sizes = &self.options.edge_label_sizes;
+        let labeled = |edge: &petgraph::graph::EdgeReference<'_, E>| {
+            edge.source() != edge.target() && label_sizes.contains_key(&edge.id())
+        };
+        if !graph.edge_references().any(|edge| labeled(&edge)) {
+            return None;
+        }
+
+        let mut layout = self.clone();
+        if let Some(compare) = layout.node_order.take() {
+            // Label nodes come after the input nodes, which `compare` knows about
+            let count = graph.node_count();
+            layout.node_order = Some(Arc::new(move |a: NodeIndex, b: NodeIndex| {
+                match (a.index() < count, b.index() < count) {
+                    (true, true) => compare(a, b),
+                    _ => (a.index() >= count, a).cmp(&(b.index() >= count, b)),
+                }
+            }));
+        }
+        let options = &mut layout.options;
+        let rank_dir = options.rank_dir;
+        let mut split = DiGraph::with_capacity(graph.node_count(), graph.edge_count());
+        for _ in graph.node_indices() {
+            split.add_node(());
+        }
+        let mut original = Vec::with_capacity(graph.edge_count());
+        let mut copies: Vec<Vec<EdgeIndex>> = vec![Vec::new(); graph.edge_count()];
+        let mut labels = HashMap::new();
+        for edge in graph.edge_references() {
+            let (source, target) = (edge.source(), edge.target());
+            if labeled(&edge) {
+                let label = split.add_node(());
+                let size = label_sizes[&edge.id()];
+                options
+                    .node_sizes
+                    .insert(label, rank_dir.point(rank_dir.cross(size), 0.0));
+                labels.insert(edge.id(), label);
+                copies[edge.id().index()].push(split.add_edge(source, label, ()));
+                copies[edge.id().index()].push(split.add_edge(label, target, ()));
+                original.extend([edge.id(), edge.id()]);
+            } else {
+                copies[edge.id().index()].push(split.add_edge(source, target, ()));
+                original.push(edge.id());
+            }
+        }
+
+        let copies_of = |edge: EdgeIndex| copies.get(edge.index()).into_iter().flatten().copied();
+        options.edge_label_sizes.clear();
+        options.edge_weights = options
+            .edge_weights
+            .iter()
+            .flat_map(|(&edge, &weight)| copies_of(edge).map(move |copy| (copy, weight)))
+            .collect();
+        // The half into a label spans one rank, the other half the rest
+        options.min_lengths = options
+            .min_lengths
+            .iter()
+            .filter_map(|(&edge, &length)| {
+                let halves = copies_of(edge).count();
+                Some((copies_of(edge).last()?, length.saturating_sub(halves - 1)))
+            })
+            .collect();
+        options.routing_constraints = options
+            .routing_constraints
+            .iter()
+            .flat_map(|constraint| {
+                copies_of(constraint.edge).map(|edge| RoutingConstraint {
+                    edge,
+                    ..*constraint
+                })
+            })
+            .collect();
+
+        let labeled = LabeledGraph {
+            graph: split,
+            original,
+            labels,
+            real_count: graph.node_count(),
+        };
+        Some((layout, labeled))
+    }
+
+    /// Smallest `rank_sep` that fits every edge label between its ranks
+    ///
+    /// A label is expected centered in the gap between its edge's source
+    /// rank and the rank after it. As `rank_sep` is the gap between the
+    /// borders of the nodes on either side, that is the largest extent of
+    /// such a label along the rank axis of `rank_dir`, sizes being
+    /// `(width, height)`. Ranks are assigned like [`Self::compute`] does
+    /// with the current options, failing where it would, so the result can
+    /// be used directly as `rank_sep` for the same graph.
+    pub fn suggest_rank_sep<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        label_sizes: &HashMap<EdgeIndex, (f32, f32)>,
+    ) -> Result<f32, LayoutError> {
+        let rank_dir = self.options.rank_dir;
+        let (_, ranks) = self.normalized_graph(graph)?;
+
+        let mut suggested: f32 = 0.0;
+        for (&edge, &size) in label_sizes {
+            let Some((source, target)) = graph.edge_endpoints(edge) else {
+                continue;
+            };
+            if ranks[&target] > ranks[&source] {
+                suggested = suggested.max(rank_dir.rank(size));
+            }
+        }
+        Ok(suggested)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::FlatEdge;
+    use crate::layout::{DagreLayout, LayoutOptions};
+    use petgraph::Graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_suggested_rank_sep_clears_tall_labels() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        let ab = graph.add_edge(a, b, ());
+        let bc = graph.add_edge(b, c, ());
+        let cd = graph.add_edge(c, d, ());
+
+        // C and D share a rank, so the label of their flat edge needs no gap
+        let node_sizes = HashMap::from([(a, (60.0, 40.0)), (b, (60.0, 40.0)), (c, (60.0, 40.0))]);
+        let label_sizes =
+            HashMap::from([(ab, (50.0, 90.0)), (bc, (50.0, 120.0)), (cd, (50.0, 300.0))]);
+        let options = LayoutOptions {
+            node_sizes,
+            same_rank: vec![vec![c, d]],
+            on_flat_edge: FlatEdge::Arc,
+            ..Default::default()
+        };
+        let layout = DagreLayout::with_options(options.clone());
+        let rank_sep = layout.suggest_rank_sep(&graph, &label_sizes).unwrap();
+        assert_eq!(rank_sep, 120.0);
+
+        // The suggestion fits every label, anything less leaves one short
+        let fits = |rank_sep| {
+            let options = LayoutOptions {
+                rank_sep,
+                ..options.clone()
+            };
+            let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+            [ab, bc].iter().all(|&edge| {
+                let (source, target) = graph.edge_endpoints(edge).unwrap();
+                let source_bottom = result.node_positions[&source].1 + 20.0;
+                let target_top = result.node_positions[&target].1 - 20.0;
+                target_top - source_bottom >= label_sizes[&edge].1 - 1e-3
+            })
+        };
+        assert!(fits(rank_sep));
+        assert!(!fits(rank_sep - 1.0));
+    }
+
+    #[test]
+    fn test_edge_labels_reserve_space() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let x = graph.add_node("X");
+        let b = graph.add_node("B");
+        let labeled = graph.add_edge(a, b, ());
+        graph.add_edge(a, x, ());
+        graph.add_edge(x, b, ());
+        let looped = graph.add_edge(b, b, ());
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            node_sizes: HashMap::from([(x, (40.0, 40.0))]),
+            edge_label_sizes: HashMap::from([(labeled, (120.0, 30.0)), (looped, (10.0, 10.0))]),
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+        assert_eq!(result.layers, vec![vec![a], vec![x], vec![b]]);
+        assert_eq!(result.node_positions.len(), 3);
+        assert!(!result.edge_label_positions.contains_key(&looped));
+
+        // The label sits on X's rank and keeps clear of it like a node
+        let label = result.edge_label_positions[&labeled];
+        let x_position = result.node_positions[&x];
+        assert_eq!(label.1, x_position.1);
+        assert!((label.0 - x_position.0).abs() >= 60.0 + 20.0 + layout.options.node_sep - 1e-3);
+        let points = &result.edge_points[&labeled];
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0], result.node_positions[&a]);
+        assert_eq!(points[1], label);
+        assert_eq!(points[2], result.node_positions[&b]);
+        assert_eq!(result.edge_points.len(), 4);
+    }
+}
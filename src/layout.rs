@@ -1,8 +1,25 @@
+use crate::acyclic::{CycleRemoval, back_edges, restore_reversed, unreachable_nodes};
+use crate::anchors::{AnchorSlots, assign_anchor_slots};
+use crate::components::{ComponentPacking, PackedComponent};
+use crate::constraints::{FlatEdge, RankConflict};
+use crate::coordinates::{BrandesKopfAssigner, CoordinateAssigner};
+use crate::crossing::{CrossingCost, LayerPositions};
+use crate::error::LayoutError;
+use crate::hybrid::assign_hybrid_coordinates;
+use crate::metrics::MetricsRecorder;
+use crate::normalize::{NodeKind, NormalizedGraph, normalize};
+use crate::ranking::{RankingAlgorithm, refine_ranks};
+use crate::routing::{RoutingConstraint, RoutingPins, bundle_edges, find_highways, route_highways};
+use crate::shape::{NodeShape, PortSide};
 use petgraph::prelude::*;
+use petgraph::unionfind::UnionFind;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Configuration options for graph layout calculation
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayoutOptions {
     /// Primary layout direction
     pub rank_dir: RankDir,
@@ -12,15 +29,425 @@ pub struct LayoutOptions {
     pub rank_sep: f32,
     /// Maximum number of iterations for crossing reduction
     pub max_iterations: usize,
+    /// Keep the bend points of long edges moving steadily toward the target
+    /// along the cross axis instead of swinging sideways and back
+    pub monotone: bool,
+    /// Width and height of individual nodes, used to space nodes and ranks
+    /// and to clip edges at node borders
+    pub node_sizes: HashMap<NodeIndex, (f32, f32)>,
+    /// Outline of individual nodes, nodes without an entry are rectangles
+    pub node_shapes: HashMap<NodeIndex, NodeShape>,
+    /// Center every layer within this cross-axis extent instead of the widest layer
+    pub fixed_cross_axis_width: Option<f32>,
+    /// Spread the edges of each node over separate slots on its border
+    pub anchor_slots: bool,
+    /// Place tree-shaped regions with Reingold–Tilford and pack all components side by side
+    pub hybrid: bool,
+    /// Sorted rank-axis coordinates that ranks are mapped to in order,
+    /// instead of multiples of `rank_sep`
+    pub allowed_rank_coordinates: Option<Vec<f32>>,
+    /// Move nodes whose successors all sit more than one rank below them
+    /// down toward those successors, when that shortens more edges than it stretches
+    pub defer_ties: bool,
+    /// Cost of wide ranks relative to edge length, higher values move nodes
+    /// onto additional ranks for taller and narrower layouts, 0 disables it
+    pub width_penalty: f32,
+    /// Points that long edges should pass near, honored during crossing reduction
+    pub routing_constraints: Vec<RoutingConstraint>,
+    /// Drop ranks without real nodes, which would only hold dummy nodes of long edges
+    pub compact_ranks: bool,
+    /// Move nodes with a single edge toward the node at its other end,
+    /// as far as their layer neighbors allow
+    pub pull_weak_nodes: bool,
+    /// Route edges spanning more than this many ranks along the margin
+    /// instead of through the layers they cross
+    pub highway_span: Option<usize>,
+    /// Node that cycle removal and ranking start from when every node has a predecessor
+    pub cycle_start: CycleStart,
+    /// Rule for picking the edges to reverse when the graph has cycles
+    pub cycle_removal: CycleRemoval,
+    /// Weigh each crossing by the combined rank span of both edges and swap
+    /// neighboring nodes to avoid costly crossings, keeping long edges uncrossed
+    pub weight_crossings_by_span: bool,
+    /// Ranks that individual nodes are pinned to, other nodes are ranked below their predecessors
+    pub fixed_ranks: HashMap<NodeIndex, usize>,
+    /// How to handle edges that `fixed_ranks` make point upward or sideways
+    pub on_rank_conflict: RankConflict,
+    /// Line up the nodes that every path of a component passes through,
+    /// such as the start and end of a diamond, on one cross-axis coordinate
+    pub align_spine: bool,
+    /// Weight of individual edges, edges without an entry weigh 1;
+    /// crossing reduction pulls nodes harder toward neighbors behind heavy edges
+    pub edge_weights: HashMap<EdgeIndex, f32>,
+    /// Lay out each pair of edges running both ways between two nodes as a single edge
+    pub merge_bidirectional: bool,
+    /// Algorithm that ranks the nodes of graphs without `fixed_ranks`
+    pub ranking: RankingAlgorithm,
+    /// How crossing reduction estimates the position of a node from its neighbors
+    pub crossing_heuristic: CrossingHeuristic,
+    /// Shuffle the initial order of every layer and break ordering ties with
+    /// this seed, each seed giving its own reproducible layout
+    pub seed: Option<u64>,
+    /// Cross-axis distance between the middles of edges that share their
+    /// source and target and span one rank, 0 draws them on top of each other
+    pub parallel_edge_sep: f32,
+    /// Groups of nodes that must share a rank, ignored with `fixed_ranks`;
+    /// groups sharing a node are merged
+    pub same_rank: Vec<Vec<NodeIndex>>,
+    /// Nodes placed on the first rank, ignored with `fixed_ranks`;
+    /// their incoming edges are reversed
+    pub min_rank_nodes: HashSet<NodeIndex>,
+    /// Nodes placed on the last rank, ignored with `fixed_ranks`;
+    /// their outgoing edges are reversed
+    pub max_rank_nodes: HashSet<NodeIndex>,
+    /// Empty space left and right of the layout, included in its width
+    pub margin_x: f32,
+    /// Empty space above and below the layout, included in its height
+    pub margin_y: f32,
+    /// Width and height of individual edge labels, honored by `compute`;
+    /// each labeled edge passes through a rank of its own where the label
+    /// takes cross-axis space like a node, see `edge_label_positions`
+    pub edge_label_sizes: HashMap<EdgeIndex, (f32, f32)>,
+    /// Position of individual nodes within their layer that crossing
+    /// reduction starts from and breaks ties by, such as the positions in a
+    /// previous layout; nodes without an entry keep their usual place
+    pub initial_order: HashMap<NodeIndex, usize>,
+    /// Start crossing reduction from every layer in node index order, with
+    /// the bend points of a long edge right after the index of its source,
+    /// instead of the real nodes followed by the bend points; replaces the
+    /// shuffle of `seed`
+    pub index_order: bool,
+    /// Stop crossing reduction once an iteration removes no more than this
+    /// many crossings, instead of only when the order stops changing
+    pub crossing_improvement_threshold: Option<usize>,
+    /// Move nodes between their nearest predecessor and successor wherever
+    /// that lowers the total span of their edges, weighted by `edge_weights`
+    pub shorten_edges: bool,
+    /// Put nodes without any edges on a layer of their own after the last
+    /// rank, see `isolated_nodes`; ignored with `fixed_ranks` and for nodes
+    /// in `same_rank`, `min_rank_nodes` or `max_rank_nodes`
+    pub isolated_lane: bool,
+    /// Separation between a layer and the next one for individual layers,
+    /// keyed by the index of the former in `layers`; other layers use `rank_sep`
+    pub rank_sep_overrides: HashMap<usize, f32>,
+    /// Placement of narrower layers by [`crate::CenteredAssigner`], against the
+    /// widest layer or within `fixed_cross_axis_width`
+    pub alignment: Alignment,
+    /// Pull nodes toward the mean of their neighbors on the cross axis after
+    /// coordinate assignment, within the layout's previous extent, and
+    /// shrink `width` or `height` to the space left in use; ignored with
+    /// `fixed_cross_axis_width`
+    pub pack_cross_axis: bool,
+    /// Line up the bend points of every long edge on one cross-axis
+    /// coordinate after coordinate assignment, where their layer neighbors
+    /// leave room, so the edge runs straight between its first and last bend
+    pub straighten_long_edges: bool,
+    /// Side of the source node that individual edges leave from, honored
+    /// by `compute`; the first point of their `edge_points` is moved to
+    /// the middle of that side, also in `anchor_slots`
+    pub source_ports: HashMap<EdgeIndex, PortSide>,
+    /// Side of the target node that individual edges enter at, like
+    /// `source_ports` for the last point of their `edge_points`
+    pub target_ports: HashMap<EdgeIndex, PortSide>,
+    /// How to handle edges between two nodes of the same rank, which
+    /// `fixed_ranks` or a `same_rank` group can put there
+    pub on_flat_edge: FlatEdge,
+    /// Nodes that cycle removal and ranking start from, before `cycle_start`,
+    /// in parts of the graph that no source reaches; the first listed node of
+    /// such a part comes first
+    pub cycle_roots: Vec<NodeIndex>,
+    /// Lay out every weakly connected component on its own and pack their
+    /// boxes this way, reported in `components`
+    pub pack_components: Option<ComponentPacking>,
+    /// Wrap layers wider than this cross-axis extent into rows that fit,
+    /// each row a layer of its own, see `wrapped_ranks`; ignored with
+    /// `allowed_rank_coordinates`
+    pub max_width: Option<f32>,
+    /// Fewest ranks individual edges span, pushing their targets further
+    /// down; edges without an entry span at least one rank, like an entry of 1
+    pub min_lengths: HashMap<EdgeIndex, usize>,
+    /// Center every node over its successors after coordinate assignment,
+    /// from the last rank up, so symmetric trees come out symmetric; the
+    /// layout widens where layer neighbors would otherwise overlap
+    pub center_parents: bool,
+    /// Wall-clock time crossing reduction may take in one layout; no sweep
+    /// starts once it is used up, and the best order seen so far is kept
+    pub time_budget: Option<Duration>,
+    /// Strength from 0 to 1 by which edges running side by side in about the
+    /// same direction between two ranks are drawn together at a shared
+    /// waypoint in the middle of the gap; only `edge_points` change
+    pub edge_bundling: Option<f32>,
+    /// Leave out the neighbor swaps that follow every crossing reduction
+    /// sweep with `weight_crossings_by_span`, which still weighs crossings
+    pub skip_transpose: bool,
+}
+
+/// Chained construction of [`LayoutOptions`], starting from the defaults
+///
+/// Every option has a setter of the same name that replaces its value,
+/// and `node_size` adds a single entry to `node_sizes`.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutOptionsBuilder {
+    options: LayoutOptions,
+}
+
+/// Setters of [`LayoutOptionsBuilder`], one per listed option
+macro_rules! option_setters {
+    ($($name:ident: $ty:ty,)*) => {
+        impl LayoutOptionsBuilder {
+            $(
+                #[doc = concat!("Set [`LayoutOptions::", stringify!($name), "`]")]
+                pub fn $name(mut self, $name: $ty) -> Self {
+                    self.options.$name = $name;
+                    self
+                }
+            )*
+        }
+    };
+}
+
+option_setters! {
+    rank_dir: RankDir,
+    node_sep: f32,
+    rank_sep: f32,
+    max_iterations: usize,
+    monotone: bool,
+    node_sizes: HashMap<NodeIndex, (f32, f32)>,
+    node_shapes: HashMap<NodeIndex, NodeShape>,
+    fixed_cross_axis_width: Option<f32>,
+    anchor_slots: bool,
+    hybrid: bool,
+    allowed_rank_coordinates: Option<Vec<f32>>,
+    defer_ties: bool,
+    width_penalty: f32,
+    routing_constraints: Vec<RoutingConstraint>,
+    compact_ranks: bool,
+    pull_weak_nodes: bool,
+    highway_span: Option<usize>,
+    cycle_start: CycleStart,
+    cycle_removal: CycleRemoval,
+    weight_crossings_by_span: bool,
+    fixed_ranks: HashMap<NodeIndex, usize>,
+    on_rank_conflict: RankConflict,
+    align_spine: bool,
+    edge_weights: HashMap<EdgeIndex, f32>,
+    merge_bidirectional: bool,
+    ranking: RankingAlgorithm,
+    crossing_heuristic: CrossingHeuristic,
+    seed: Option<u64>,
+    parallel_edge_sep: f32,
+    same_rank: Vec<Vec<NodeIndex>>,
+    min_rank_nodes: HashSet<NodeIndex>,
+    max_rank_nodes: HashSet<NodeIndex>,
+    margin_x: f32,
+    margin_y: f32,
+    edge_label_sizes: HashMap<EdgeIndex, (f32, f32)>,
+    initial_order: HashMap<NodeIndex, usize>,
+    index_order: bool,
+    crossing_improvement_threshold: Option<usize>,
+    shorten_edges: bool,
+    isolated_lane: bool,
+    rank_sep_overrides: HashMap<usize, f32>,
+    alignment: Alignment,
+    pack_cross_axis: bool,
+    straighten_long_edges: bool,
+    source_ports: HashMap<EdgeIndex, PortSide>,
+    target_ports: HashMap<EdgeIndex, PortSide>,
+    on_flat_edge: FlatEdge,
+    cycle_roots: Vec<NodeIndex>,
+    pack_components: Option<ComponentPacking>,
+    max_width: Option<f32>,
+    min_lengths: HashMap<EdgeIndex, usize>,
+    center_parents: bool,
+    time_budget: Option<Duration>,
+    edge_bundling: Option<f32>,
+    skip_transpose: bool,
+}
+
+impl LayoutOptionsBuilder {
+    /// Give a node a width and height
+    pub fn node_size(mut self, node: NodeIndex, size: (f32, f32)) -> Self {
+        self.options.node_sizes.insert(node, size);
+        self
+    }
+
+    /// Finish building the options
+    pub fn build(self) -> LayoutOptions {
+        self.options
+    }
+}
+
+/// Rule for picking the first node of a graph without sources
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CycleStart {
+    /// The node with the lowest index
+    #[default]
+    LowestIndex,
+    /// The node with the most outgoing edges, ties go to the lowest index
+    HighestOutDegree,
+}
+
+/// Position estimate that crossing reduction sorts the nodes of a layer by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CrossingHeuristic {
+    /// Weighted mean position of the neighbors in the adjacent layer
+    #[default]
+    Barycenter,
+    /// Median position of the neighbors, less swayed by single far neighbors
+    Median,
+}
+
+/// Where layers narrower than the widest one sit along the cross axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Alignment {
+    /// Centered against the widest layer
+    #[default]
+    Center,
+    /// Sharing the left edge, or the top edge for `LeftToRight`
+    Start,
+    /// Sharing the right edge, or the bottom edge for `LeftToRight`
+    End,
 }
 
 /// Layout direction for the graph
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RankDir {
     /// Nodes flow from top to bottom
     TopToBottom,
     /// Nodes flow from left to right
     LeftToRight,
+    /// Nodes flow from bottom to top
+    BottomToTop,
+    /// Nodes flow from right to left
+    RightToLeft,
+}
+
+impl RankDir {
+    /// Coordinate of a point along the axis nodes of one layer are spread on
+    pub(crate) fn cross(self, (x, y): (f32, f32)) -> f32 {
+        match self {
+            RankDir::TopToBottom | RankDir::BottomToTop => x,
+            RankDir::LeftToRight | RankDir::RightToLeft => y,
+        }
+    }
+
+    /// Coordinate of a point along the axis layers follow each other on
+    pub(crate) fn rank(self, (x, y): (f32, f32)) -> f32 {
+        match self {
+            RankDir::TopToBottom | RankDir::BottomToTop => y,
+            RankDir::LeftToRight | RankDir::RightToLeft => x,
+        }
+    }
+
+    /// Build a point from its cross-axis and rank-axis coordinates
+    pub(crate) fn point(self, cross: f32, rank: f32) -> (f32, f32) {
+        match self {
+            RankDir::TopToBottom | RankDir::BottomToTop => (cross, rank),
+            RankDir::LeftToRight | RankDir::RightToLeft => (rank, cross),
+        }
+    }
+
+    /// Whether the first rank sits at the far end of the rank axis
+    ///
+    /// Layouts in these directions are computed like their forward
+    /// counterparts and mirrored along the rank axis at the end.
+    pub(crate) fn is_reversed(self) -> bool {
+        matches!(self, RankDir::BottomToTop | RankDir::RightToLeft)
+    }
+}
+
+impl LayoutOptions {
+    /// Start building options from the defaults
+    pub fn builder() -> LayoutOptionsBuilder {
+        LayoutOptionsBuilder::default()
+    }
+
+    /// Preset for very large graphs that trades layout quality for speed
+    ///
+    /// Ranks come from longest paths alone, crossing reduction is capped at
+    /// a few sweeps without neighbor swaps, and coordinates are assigned
+    /// without any refinement passes. The coordinate assigner belongs to the
+    /// layout rather than its options; pair this with [`crate::CenteredAssigner`]
+    /// for the cheapest placement.
+    pub fn fast() -> Self {
+        Self {
+            max_iterations: 4,
+            skip_transpose: true,
+            ranking: RankingAlgorithm::LongestPath,
+            defer_ties: false,
+            shorten_edges: false,
+            width_penalty: 0.0,
+            hybrid: false,
+            pack_cross_axis: false,
+            center_parents: false,
+            straighten_long_edges: false,
+            pull_weak_nodes: false,
+            align_spine: false,
+            monotone: false,
+            ..Default::default()
+        }
+    }
+
+    /// Rank-axis coordinate of every layer
+    ///
+    /// Consecutive layers are `rank_sep` apart, or the first one's entry in
+    /// `rank_sep_overrides`, plus half the height of the tallest sized node
+    /// on each of them, or the rank-axis size for `LeftToRight`. The first
+    /// layer sits at zero. With `allowed_rank_coordinates` the layers take
+    /// those coordinates instead; layers beyond the last of them, which
+    /// [`DagreLayout::compute`] rejects with [`LayoutError::TooManyRanks`],
+    /// continue below it as without them.
+    pub fn rank_coordinates(&self, layers: &[Vec<NodeIndex>]) -> Vec<f32> {
+        let allowed = self.allowed_rank_coordinates.as_deref().unwrap_or_default();
+
+        let thickness = |layer: &Vec<NodeIndex>| {
+            layer
+                .iter()
+                .filter_map(|node| self.node_sizes.get(node))
+                .map(|&size| self.rank_dir.rank(size))
+                .fold(0.0, f32::max)
+        };
+        let mut coordinates = Vec::with_capacity(layers.len());
+        let mut previous: Option<(f32, f32)> = None;
+        for (index, layer) in layers.iter().enumerate() {
+            let half = thickness(layer) * 0.5;
+            let coordinate = match allowed.get(index) {
+                Some(&coordinate) => coordinate,
+                None => previous.map_or(0.0, |(coordinate, previous_half)| {
+                    let rank_sep = self.rank_sep_overrides.get(&(index - 1));
+                    coordinate + previous_half + rank_sep.copied().unwrap_or(self.rank_sep) + half
+                }),
+            };
+            coordinates.push(coordinate);
+            previous = Some((coordinate, half));
+        }
+        coordinates
+    }
+
+    /// Rank-axis coordinate of every layer in the finished layout
+    ///
+    /// Equal to [`LayoutOptions::rank_coordinates`] except for `BottomToTop`
+    /// and `RightToLeft`, where the first layer takes the last coordinate.
+    pub fn final_rank_coordinates(&self, layers: &[Vec<NodeIndex>]) -> Vec<f32> {
+        let coordinates = self.rank_coordinates(layers);
+        if !self.rank_dir.is_reversed() {
+            return coordinates;
+        }
+        let sum = coordinates.first().unwrap_or(&0.0) + coordinates.last().unwrap_or(&0.0);
+        coordinates
+            .iter()
+            .map(|coordinate| sum - coordinate)
+            .collect()
+    }
+
+    /// Rank-axis extent of a layout whose layers sit at `coordinates`
+    pub fn rank_extent(&self, coordinates: &[f32]) -> f32 {
+        coordinates.last().map_or(0.0, |last| last + self.rank_sep)
+    }
 }
 
 impl Default for LayoutOptions {
@@ -30,55 +457,196 @@ impl Default for LayoutOptions {
             node_sep: 50.0,
             rank_sep: 100.0,
             max_iterations: 24,
+            monotone: false,
+            node_sizes: HashMap::new(),
+            node_shapes: HashMap::new(),
+            fixed_cross_axis_width: None,
+            anchor_slots: false,
+            hybrid: false,
+            allowed_rank_coordinates: None,
+            defer_ties: false,
+            width_penalty: 0.0,
+            routing_constraints: Vec::new(),
+            compact_ranks: false,
+            pull_weak_nodes: false,
+            highway_span: None,
+            cycle_start: CycleStart::default(),
+            cycle_removal: CycleRemoval::default(),
+            weight_crossings_by_span: false,
+            fixed_ranks: HashMap::new(),
+            on_rank_conflict: RankConflict::default(),
+            align_spine: false,
+            edge_weights: HashMap::new(),
+            merge_bidirectional: false,
+            ranking: RankingAlgorithm::default(),
+            crossing_heuristic: CrossingHeuristic::default(),
+            seed: None,
+            parallel_edge_sep: 10.0,
+            same_rank: Vec::new(),
+            min_rank_nodes: HashSet::new(),
+            max_rank_nodes: HashSet::new(),
+            margin_x: 0.0,
+            margin_y: 0.0,
+            edge_label_sizes: HashMap::new(),
+            initial_order: HashMap::new(),
+            index_order: false,
+            crossing_improvement_threshold: None,
+            shorten_edges: false,
+            isolated_lane: false,
+            rank_sep_overrides: HashMap::new(),
+            alignment: Alignment::default(),
+            pack_cross_axis: false,
+            straighten_long_edges: false,
+            source_ports: HashMap::new(),
+            target_ports: HashMap::new(),
+            on_flat_edge: FlatEdge::default(),
+            cycle_roots: Vec::new(),
+            pack_components: None,
+            max_width: None,
+            min_lengths: HashMap::new(),
+            center_parents: false,
+            time_budget: None,
+            edge_bundling: None,
+            skip_transpose: false,
         }
     }
 }
 
 /// Result of layout calculation containing node positions and layer information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayoutResult {
     /// Final positions for each node as (x, y) coordinates
     pub node_positions: HashMap<NodeIndex, (f32, f32)>,
+    /// Polyline each edge should be drawn along, from source to target,
+    /// with one bend point per layer crossed by a long edge
+    pub edge_points: HashMap<EdgeIndex, Vec<(f32, f32)>>,
+    /// Center of the label of every labeled edge in `edge_points`, which
+    /// passes through it
+    pub edge_label_positions: HashMap<EdgeIndex, (f32, f32)>,
+    /// Width and height of the nodes that were given a size
+    pub node_sizes: HashMap<NodeIndex, (f32, f32)>,
+    /// Border slots of each node's edges, empty unless `anchor_slots` is enabled
+    pub anchor_slots: HashMap<NodeIndex, AnchorSlots>,
+    /// Edges in `edge_points` that were routed along the margin because of `highway_span`
+    pub highway_edges: HashSet<EdgeIndex>,
+    /// Edges laid out from target to source, their `edge_points` still run source to target
+    pub reversed_edges: HashSet<EdgeIndex>,
+    /// Edges left out of the layout, they have no `edge_points`
+    pub dropped_edges: HashSet<EdgeIndex>,
+    /// Edges between two nodes of the same rank, drawn as arcs within the
+    /// rank because `on_flat_edge` is [`FlatEdge::Arc`]
+    pub flat_edges: HashSet<EdgeIndex>,
+    /// Edges drawn in both directions, mapped to their merged partner edge,
+    /// which has no `edge_points` of its own
+    pub bidirectional_edges: HashMap<EdgeIndex, EdgeIndex>,
     /// Nodes organized by layers, from first to last
     pub layers: Vec<Vec<NodeIndex>>,
+    /// Index of the layer in `layers` containing each node
+    pub node_ranks: HashMap<NodeIndex, usize>,
+    /// Nodes moved to the last layer because they have no edges, empty
+    /// unless `isolated_lane` is enabled
+    pub isolated_nodes: HashSet<NodeIndex>,
+    /// Edge crossings between consecutive layers, counting the segments of
+    /// long edges through every layer they cross
+    pub crossing_count: usize,
     /// Total width of the layout
     pub width: f32,
     /// Total height of the layout
     pub height: f32,
+    /// Place of every weakly connected component, empty unless `pack_components`
+    /// packed more than one
+    pub components: Vec<PackedComponent>,
+    /// Rank that every entry of `layers` was wrapped from by `max_width`,
+    /// empty unless a layer was wrapped
+    pub wrapped_ranks: Vec<usize>,
+    /// Nodes that no source of the graph reaches: cycles no edge enters and
+    /// everything only they lead to, laid out by starting cycle removal from
+    /// `cycle_roots`, `cycle_start` or the lowest index
+    pub unreachable_nodes: HashSet<NodeIndex>,
 }
 
+/// Comparison of two input nodes, see [`DagreLayout::with_node_order`]
+pub(crate) type NodeOrder = Arc<dyn Fn(NodeIndex, NodeIndex) -> std::cmp::Ordering + Send + Sync>;
+
 /// Main layout engine implementing the Sugiyama method
+#[derive(Clone)]
 pub struct DagreLayout {
     /// Layout configuration options
     pub options: LayoutOptions,
+    /// Placement of the ordered layers
+    coordinate_assigner: Arc<dyn CoordinateAssigner>,
+    /// Order of nodes that crossing reduction cannot tell apart
+    pub(crate) node_order: Option<NodeOrder>,
+    /// Where the phases of [`Self::compute_with_metrics`] report to
+    pub(crate) metrics: Option<MetricsRecorder>,
 }
 
 impl DagreLayout {
     /// Create a new layout engine with default options
     pub fn new() -> Self {
-        Self {
-            options: LayoutOptions::default(),
-        }
+        Self::with_options(LayoutOptions::default())
     }
 
     /// Create a new layout engine with custom options
     pub fn with_options(options: LayoutOptions) -> Self {
-        Self { options }
+        Self {
+            options,
+            coordinate_assigner: Arc::new(BrandesKopfAssigner),
+            node_order: None,
+            metrics: None,
+        }
+    }
+
+    /// Replace the coordinate assignment phase with a custom implementation
+    ///
+    /// The default is [`BrandesKopfAssigner`]. With `hybrid` the assigner
+    /// places the nodes outside tree regions.
+    pub fn with_coordinate_assigner(mut self, assigner: impl CoordinateAssigner + 'static) -> Self {
+        self.coordinate_assigner = Arc::new(assigner);
+        self
+    }
+
+    /// Order nodes within their layer by `compare` where crossing reduction has no preference
+    ///
+    /// Layers start out sorted by `compare`, so nodes without edges to an
+    /// adjacent layer keep that order, and it breaks ties between nodes with
+    /// the same barycenter or median after `initial_order`. Dummy nodes of
+    /// long edges stay in place; `compare` only ever sees input nodes and
+    /// should be a total order, such as comparing node labels.
+    pub fn with_node_order(
+        mut self,
+        compare: impl Fn(NodeIndex, NodeIndex) -> std::cmp::Ordering + Send + Sync + 'static,
+    ) -> Self {
+        self.node_order = Some(Arc::new(compare));
+        self
     }
 
     /// Compute the layout for a directed graph using the Sugiyama method
     ///
     /// This method implements the four phases of the Sugiyama algorithm:
-    /// 1. Cycle removal (assumes DAG input for now)
-    /// 2. Layer assignment using longest path
-    /// 3. Crossing reduction using barycenter heuristic
-    /// 4. Coordinate assignment with proper spacing
+    /// 1. Cycle removal by reversing the edges picked by `cycle_removal`,
+    ///    the back edges of a depth-first search or a greedy feedback arc set
+    /// 2. Layer assignment by `ranking`, longest path, tight tree or network
+    ///    simplex, within `fixed_ranks`, `same_rank` and the other rank
+    ///    constraints
+    /// 3. Crossing reduction by `crossing_heuristic`, barycenter or median,
+    ///    followed by neighbor swaps
+    /// 4. Coordinate assignment by the layout's [`CoordinateAssigner`],
+    ///    [`BrandesKopfAssigner`] unless replaced, and edge routing
+    ///
+    /// Components packed by `pack_components`, ports, edge labels and
+    /// merged bidirectional edges are handled around these phases. With the
+    /// default longest-path ranking every source starts on the first rank,
+    /// so independent chains and components share ranks instead of being
+    /// stacked, and the height is that of the longest chain.
     ///
     /// # Arguments
     /// * `graph` - The directed graph to layout
     ///
     /// # Returns
-    /// A `LayoutResult` containing node positions and metadata
+    /// A `LayoutResult` containing node positions and metadata, or a
+    /// `LayoutError` if the graph cannot be laid out with the given options
     ///
     /// # Example
     /// ```
@@ -91,114 +659,684 @@ impl DagreLayout {
     /// graph.add_edge(a, b, ());
     ///
     /// let layout = DagreLayout::new();
-    /// let result = layout.compute(&graph);
+    /// let result = layout.compute(&graph).unwrap();
     /// ```
-    pub fn compute<N, E>(&self, graph: &DiGraph<N, E>) -> LayoutResult {
-        // Phase 1: Cycle removal (assume DAG for now)
-        // TODO: Implement cycle detection and removal
+    pub fn compute<N, E>(&self, graph: &DiGraph<N, E>) -> Result<LayoutResult, LayoutError> {
+        let mut result = self.compute_stages(graph)?;
+        result.unreachable_nodes = unreachable_nodes(graph);
+        Ok(result)
+    }
+
+    /// Run the stages of [`Self::compute`], each one that rewrites the graph
+    /// computing the layout of its rewritten graph
+    fn compute_stages<N, E>(&self, graph: &DiGraph<N, E>) -> Result<LayoutResult, LayoutError> {
+        if let Some(result) = self.packing_components(graph) {
+            return result;
+        }
+
+        if let Some(layout) = self.without_ports() {
+            let mut result = layout.compute(graph)?;
+            self.attach_ports(graph, &mut result);
+            return Ok(result);
+        }
+
+        if let Some((layout, labeled)) = self.splitting_labeled_edges(graph) {
+            let mut result = layout.compute(&labeled.graph)?;
+            labeled.restore(&mut result);
+            return Ok(result);
+        }
+
+        if let Some((layout, derived, partners)) = self.merging_bidirectional(graph) {
+            let mut result = layout.compute(&derived.graph)?;
+            derived.restore(&mut result);
+            result.bidirectional_edges = partners;
+            return Ok(result);
+        }
+
+        // Phase 1: Cycle removal
+        if let Some((layout, derived, reversed)) = self.removing_cycles(graph) {
+            let mut result = layout.compute(&derived.graph)?;
+            derived.restore(&mut result);
+            restore_reversed(&mut result, reversed);
+            return Ok(result);
+        }
 
         // Phase 2: Layer assignment
-        let mut layers = self.assign_layers_longest_path(graph);
+        let start = Instant::now();
+        if self.options.fixed_ranks.is_empty() {
+            let mut layers = self.assign_constrained_ranks(graph)?;
+            let isolated = self.moving_isolated_nodes(graph, &mut layers);
+            self.record(|metrics| metrics.ranking += start.elapsed());
+            let mut result = match self.options.on_flat_edge {
+                FlatEdge::Conflict => self.layout_layers(graph, layers)?,
+                _ => self.layout_resolving_conflicts(graph, layers)?,
+            };
+            result.isolated_nodes = isolated;
+            Ok(result)
+        } else {
+            let layers = self.assign_fixed_ranks(graph);
+            self.record(|metrics| metrics.ranking += start.elapsed());
+            self.layout_resolving_conflicts(graph, layers)
+        }
+    }
 
-        // Phase 3: Crossing reduction
-        self.reduce_crossings(graph, &mut layers);
+    /// Like [`Self::compute`], taking the weight of every edge from `weight`
+    ///
+    /// The weights replace `edge_weights` for this layout and pull nodes
+    /// toward neighbors behind heavy edges during crossing reduction.
+    pub fn compute_with_edge_weights<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        weight: impl Fn(EdgeIndex) -> f32,
+    ) -> Result<LayoutResult, LayoutError> {
+        let mut layout = self.clone();
+        layout.options.edge_weights = graph
+            .edge_indices()
+            .map(|edge| (edge, weight(edge)))
+            .collect();
+        layout.compute(graph)
+    }
+
+    /// Run the phases after layer assignment on the given layers
+    ///
+    /// The order of the nodes within each layer is the starting point for
+    /// crossing reduction.
+    pub(crate) fn layout_layers<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        layers: Vec<Vec<NodeIndex>>,
+    ) -> Result<LayoutResult, LayoutError> {
+        self.layout_layers_with(graph, layers, |_| {})
+    }
+
+    /// Drop empty ranks with `compact_ranks`, failing if more ranks are
+    /// left than `allowed_rank_coordinates` has coordinates
+    pub(crate) fn compacting_ranks(
+        &self,
+        layers: &mut Vec<Vec<NodeIndex>>,
+    ) -> Result<(), LayoutError> {
+        // Long edges crossing an empty rank would only add bend points there
+        if self.options.compact_ranks {
+            layers.retain(|layer| !layer.is_empty());
+        }
+        match &self.options.allowed_rank_coordinates {
+            Some(allowed) if layers.len() > allowed.len() => Err(LayoutError::TooManyRanks {
+                ranks: layers.len(),
+                allowed: allowed.len(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Like [`Self::layout_layers`], letting `adjust_order` rearrange the
+    /// layers after crossing reduction
+    ///
+    /// The layers passed to `adjust_order` include dummy nodes, which have
+    /// indices past the last real node.
+    pub(crate) fn layout_layers_with<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        mut layers: Vec<Vec<NodeIndex>>,
+        adjust_order: impl FnOnce(&mut [Vec<NodeIndex>]),
+    ) -> Result<LayoutResult, LayoutError> {
+        self.compacting_ranks(&mut layers)?;
+
+        let start = Instant::now();
+        let (normalized, highway_edges) = self.order_layers(graph, &mut layers);
+        adjust_order(&mut layers);
+        self.record(|metrics| metrics.crossing_reduction += start.elapsed());
+        let crossing_count =
+            CrossingCost::new(&normalized, false).total(&normalized.graph, &layers);
+        let wrapped_ranks = self.wrap_layers(&normalized, &mut layers);
 
         // Phase 4: Coordinate assignment
-        let (node_positions, width, height) = self.assign_coordinates(&layers);
+        let start = Instant::now();
+        let (mut positions, mut width, mut height) = if self.options.hybrid {
+            assign_hybrid_coordinates(self, &normalized, &layers)
+        } else {
+            self.assign_coordinates(&normalized, &layers)
+        };
+        if self.options.pack_cross_axis && self.options.fixed_cross_axis_width.is_none() {
+            let rank_dir = self.options.rank_dir;
+            let cross_extent = rank_dir.cross((width, height));
+            let cross_extent =
+                self.pack_cross_axis(&normalized, &layers, &mut positions, cross_extent);
+            (width, height) = rank_dir.point(cross_extent, rank_dir.rank((width, height)));
+        }
+        if self.options.center_parents {
+            let rank_dir = self.options.rank_dir;
+            let cross_extent = rank_dir.cross((width, height));
+            let cross_extent =
+                self.center_parents(&normalized, &layers, &mut positions, cross_extent);
+            (width, height) = rank_dir.point(cross_extent, rank_dir.rank((width, height)));
+        }
+        if self.options.straighten_long_edges {
+            self.straighten_long_edges(&normalized, &layers, &mut positions);
+        }
+        if self.options.pull_weak_nodes {
+            self.pull_weak_nodes(&normalized, &layers, &mut positions);
+        }
+        if self.options.align_spine {
+            self.align_spine(&normalized, &layers, &mut positions);
+        }
+        if self.options.monotone {
+            self.make_routes_monotone(&normalized, &layers, &mut positions);
+        }
+        self.record(|metrics| metrics.coordinate_assignment += start.elapsed());
+        let mut edge_points = self.route_edges(graph, &normalized, &positions);
+        if !highway_edges.is_empty() {
+            let rank_dir = self.options.rank_dir;
+            let cross_extent = rank_dir.cross((width, height));
+            let cross_extent = route_highways(
+                self,
+                graph,
+                &highway_edges,
+                &positions,
+                cross_extent,
+                &mut edge_points,
+            );
+            (width, height) = rank_dir.point(cross_extent, rank_dir.rank((width, height)));
+        }
+        let mut anchor_slots = if self.options.anchor_slots {
+            assign_anchor_slots(
+                graph,
+                &positions,
+                &self.options.node_sizes,
+                self.options.rank_dir,
+                &mut edge_points,
+            )
+        } else {
+            HashMap::new()
+        };
+
+        if let Some(strength) = self.options.edge_bundling {
+            bundle_edges(self, &layers, &highway_edges, strength, &mut edge_points);
+        }
+
+        if self.options.rank_dir.is_reversed() {
+            self.mirror_ranks(&layers, &mut positions, &mut edge_points, &mut anchor_slots);
+        }
+
+        let (margin_x, margin_y) = (self.options.margin_x, self.options.margin_y);
+        if margin_x != 0.0 || margin_y != 0.0 {
+            let shift = |point: &mut (f32, f32)| {
+                point.0 += margin_x;
+                point.1 += margin_y;
+            };
+            positions.values_mut().for_each(shift);
+            edge_points.values_mut().flatten().for_each(shift);
+            anchor_slots
+                .values_mut()
+                .flat_map(|slots| slots.incoming.iter_mut().chain(&mut slots.outgoing))
+                .for_each(|(_, point)| shift(point));
+            width += 2.0 * margin_x;
+            height += 2.0 * margin_y;
+        }
+
+        // Dummy nodes are an internal detail and never leave the layout
+        for layer in &mut layers {
+            layer.retain(|&node| !normalized.is_dummy(node));
+        }
+        let node_ranks = layers
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, layer)| layer.iter().map(move |&node| (node, rank)))
+            .collect();
+        let node_positions = positions
+            .into_iter()
+            .filter(|&(node, _)| !normalized.is_dummy(node))
+            .collect();
+        let node_sizes = graph
+            .node_indices()
+            .filter_map(|node| Some((node, *self.options.node_sizes.get(&node)?)))
+            .collect();
 
-        LayoutResult {
+        Ok(LayoutResult {
             node_positions,
+            edge_points,
+            edge_label_positions: HashMap::new(),
+            node_sizes,
+            anchor_slots,
+            highway_edges,
+            reversed_edges: HashSet::new(),
+            dropped_edges: HashSet::new(),
+            flat_edges: HashSet::new(),
+            bidirectional_edges: HashMap::new(),
             layers,
+            node_ranks,
+            isolated_nodes: HashSet::new(),
+            crossing_count,
             width,
             height,
+            components: Vec::new(),
+            wrapped_ranks,
+            unreachable_nodes: HashSet::new(),
+        })
+    }
+
+    /// Insert dummy nodes into the layers and order them to reduce crossings
+    ///
+    /// Returns the normalized graph together with the edges that are routed
+    /// as highways and therefore left out of it.
+    pub(crate) fn order_layers<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        layers: &mut [Vec<NodeIndex>],
+    ) -> (NormalizedGraph, HashSet<EdgeIndex>) {
+        // Split long edges into chains of dummy nodes, one per spanned layer
+        let highway_edges = find_highways(self, graph, layers);
+        let normalized = normalize(graph, layers, &highway_edges);
+        if self.options.index_order {
+            let key = |node: NodeIndex| match normalized.graph[node] {
+                NodeKind::Real(_) => (node.index(), false, 0),
+                NodeKind::Dummy(edge) => {
+                    let source = graph.edge_endpoints(edge).map_or(0, |(s, _)| s.index());
+                    (source, true, edge.index())
+                }
+            };
+            for layer in layers.iter_mut() {
+                layer.sort_by_key(|&node| key(node));
+            }
+        } else if let Some(seed) = self.options.seed {
+            for layer in layers.iter_mut() {
+                layer.sort_by_key(|&node| tie_key(Some(seed), node));
+            }
+        }
+        if let Some(compare) = &self.node_order {
+            for layer in layers.iter_mut() {
+                let slots: Vec<usize> = (0..layer.len())
+                    .filter(|&slot| !normalized.is_dummy(layer[slot]))
+                    .collect();
+                let mut nodes: Vec<NodeIndex> = slots.iter().map(|&slot| layer[slot]).collect();
+                nodes.sort_by(|&a, &b| compare(a, b));
+                for (slot, node) in slots.into_iter().zip(nodes) {
+                    layer[slot] = node;
+                }
+            }
+        }
+        if !self.options.initial_order.is_empty() {
+            let order = &self.options.initial_order;
+            for layer in layers.iter_mut() {
+                let slots: Vec<usize> = (0..layer.len())
+                    .filter(|&slot| order.contains_key(&layer[slot]))
+                    .collect();
+                let mut nodes: Vec<NodeIndex> = slots.iter().map(|&slot| layer[slot]).collect();
+                nodes.sort_by_key(|node| (order[node], *node));
+                for (slot, node) in slots.into_iter().zip(nodes) {
+                    layer[slot] = node;
+                }
+            }
         }
+
+        // Phase 3: Crossing reduction
+        let pins = RoutingPins::new(
+            &self.options.routing_constraints,
+            &normalized,
+            layers,
+            self.options.node_sep,
+        );
+        self.reduce_crossings(&normalized, layers, &pins);
+        (normalized, highway_edges)
+    }
+
+    /// Cross-axis extent of every rank once its nodes are ordered
+    ///
+    /// Each node takes `node_sep`, or its cross-axis size if that is larger.
+    /// Dummy nodes of long edges take `node_sep` like unsized nodes, since
+    /// they occupy a slot of their rank as well.
+    pub fn layer_widths<N, E>(&self, graph: &DiGraph<N, E>) -> Vec<f32> {
+        if let Some((layout, derived, _)) = self.removing_cycles(graph) {
+            return layout.layer_widths(&derived.graph);
+        }
+        let mut layers = self.assign_layers_longest_path(graph);
+        if self.options.compact_ranks {
+            layers.retain(|layer| !layer.is_empty());
+        }
+        let (normalized, _) = self.order_layers(graph, &mut layers);
+
+        let rank_dir = self.options.rank_dir;
+        layers
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .map(|&node| {
+                        let size = self
+                            .options
+                            .node_sizes
+                            .get(&node)
+                            .filter(|_| !normalized.is_dummy(node));
+                        let size = size.map_or(0.0, |&size| rank_dir.cross(size));
+                        size.max(self.options.node_sep)
+                    })
+                    .sum()
+            })
+            .collect()
     }
 
     /// Assign nodes to layers using longest path algorithm
     /// This creates more balanced layouts than simple topological sorting
-    fn assign_layers_longest_path<N, E>(&self, graph: &DiGraph<N, E>) -> Vec<Vec<NodeIndex>> {
-        let mut distances = HashMap::new();
-        let mut visited = HashSet::new();
+    ///
+    /// The longest-path ranks are the starting point of the configured
+    /// `ranking` algorithm and of the `defer_ties` and `width_penalty` passes.
+    pub(crate) fn assign_layers_longest_path<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Vec<Vec<NodeIndex>> {
+        // Edges closing a cycle are ignored, searching from the sources first
+        // and then from any node left in a component without one
+        let back = back_edges(graph, &self.search_roots(graph));
+        let forward = |edge: &petgraph::graph::EdgeReference<'_, E>| {
+            edge.source() != edge.target() && !back.contains(&edge.id())
+        };
 
-        // Find all source nodes (no incoming edges)
-        let sources: Vec<_> = graph
+        // Visit nodes in topological order, each one rank below its deepest predecessor
+        let mut pending = vec![0; graph.node_count()];
+        for edge in graph.edge_references().filter(forward) {
+            pending[edge.target().index()] += 1;
+        }
+        let mut ready: Vec<NodeIndex> = graph
             .node_indices()
-            .filter(|&n| graph.neighbors_directed(n, Incoming).count() == 0)
+            .filter(|node| pending[node.index()] == 0)
             .collect();
+        let mut distances = vec![0; graph.node_count()];
+        let mut order = Vec::with_capacity(graph.node_count());
+        while let Some(node) = ready.pop() {
+            distances[node.index()] = graph
+                .edges_directed(node, Incoming)
+                .filter(forward)
+                .map(|edge| distances[edge.source().index()] + self.min_length(edge.id()))
+                .max()
+                .unwrap_or(0);
+            order.push(node);
+            for edge in graph.edges_directed(node, Outgoing).filter(forward) {
+                pending[edge.target().index()] -= 1;
+                if pending[edge.target().index()] == 0 {
+                    ready.push(edge.target());
+                }
+            }
+        }
 
-        // If no sources found, pick an arbitrary starting node
-        let sources = if sources.is_empty() {
-            graph.node_indices().take(1).collect()
-        } else {
-            sources
-        };
-
-        // Calculate longest paths from sources using DFS
-        for &source in &sources {
-            self.dfs_longest_path(graph, source, 0, &mut distances, &mut visited);
+        let ranking = self.options.ranking;
+        let min_length = |edge| self.min_length(edge);
+        let weights = &self.options.edge_weights;
+        refine_ranks(ranking, graph, &mut distances, weights, min_length);
+        if self.options.defer_ties {
+            self.defer_tied_nodes(graph, &mut distances);
+        }
+        if self.options.width_penalty > 0.0 {
+            self.penalize_wide_ranks(graph, &mut distances);
+        }
+        if self.options.shorten_edges {
+            self.shorten_edge_spans(graph, &mut distances);
         }
 
-        // Handle any remaining unvisited nodes (disconnected components)
-        for node in graph.node_indices() {
-            if !distances.contains_key(&node) {
-                self.dfs_longest_path(graph, node, 0, &mut distances, &mut visited);
-            }
+        // Push targets below their sources wherever a pass left an edge too short
+        for &node in &order {
+            let lowest = graph
+                .edges_directed(node, Incoming)
+                .filter(forward)
+                .map(|edge| distances[edge.source().index()] + self.min_length(edge.id()))
+                .max()
+                .unwrap_or(0);
+            let distance = &mut distances[node.index()];
+            *distance = (*distance).max(lowest);
         }
 
         // Group nodes by their layer (distance)
-        let max_layer = distances.values().copied().max().unwrap_or(0);
+        let max_layer = distances.iter().copied().max().unwrap_or(0);
         let mut layers = vec![Vec::new(); max_layer + 1];
 
-        for (&node, &layer) in &distances {
-            layers[layer].push(node);
+        for node in graph.node_indices() {
+            layers[distances[node.index()]].push(node);
         }
 
-        // Remove empty layers
+        self.drop_empty_layers(&mut layers);
         layers
-            .into_iter()
-            .filter(|layer| !layer.is_empty())
-            .collect()
     }
 
-    /// Depth-first search to calculate longest path distances
-    fn dfs_longest_path<N, E>(
-        &self,
-        graph: &DiGraph<N, E>,
-        node: NodeIndex,
-        current_distance: usize,
-        distances: &mut HashMap<NodeIndex, usize>,
-        visited: &mut HashSet<NodeIndex>,
-    ) {
-        if visited.contains(&node) {
-            return;
+    /// Fewest ranks an edge spans, its `min_lengths` entry but at least one
+    pub(crate) fn min_length(&self, edge: EdgeIndex) -> usize {
+        self.options
+            .min_lengths
+            .get(&edge)
+            .map_or(1, |&length| length.max(1))
+    }
+
+    /// Remove layers without nodes
+    ///
+    /// With `min_lengths` only trailing empty layers are removed, the others
+    /// keep stretched edges apart.
+    pub(crate) fn drop_empty_layers(&self, layers: &mut Vec<Vec<NodeIndex>>) {
+        if self.options.min_lengths.is_empty() {
+            layers.retain(|layer| !layer.is_empty());
+        } else {
+            while layers.last().is_some_and(Vec::is_empty) {
+                layers.pop();
+            }
+        }
+    }
+
+    /// Nodes to start the depth-first searches for cycles from, in order
+    ///
+    /// The sources come first in index order, then the `cycle_roots` and, if
+    /// there are no sources, the node picked by `cycle_start`. All nodes
+    /// follow in index order, so parts of the graph none of these reach are
+    /// searched from their lowest index.
+    pub(crate) fn search_roots<N, E>(&self, graph: &DiGraph<N, E>) -> Vec<NodeIndex> {
+        let mut roots: Vec<NodeIndex> = graph
+            .node_indices()
+            .filter(|&node| graph.neighbors_directed(node, Incoming).next().is_none())
+            .collect();
+        let no_sources = roots.is_empty();
+        let preferred = self.options.cycle_roots.iter();
+        roots.extend(preferred.filter(|node| node.index() < graph.node_count()));
+        if no_sources {
+            roots.extend(self.cycle_start_node(graph));
+        }
+        roots.extend(graph.node_indices());
+        roots
+    }
+
+    /// Node to start from in a graph where every node has a predecessor
+    fn cycle_start_node<N, E>(&self, graph: &DiGraph<N, E>) -> Option<NodeIndex> {
+        match self.options.cycle_start {
+            CycleStart::LowestIndex => graph.node_indices().next(),
+            CycleStart::HighestOutDegree => graph.node_indices().min_by_key(|&node| {
+                let out_degree = graph.neighbors_directed(node, Outgoing).count();
+                (std::cmp::Reverse(out_degree), node)
+            }),
+        }
+    }
+
+    /// Push nodes without a successor on the next rank down toward their successors
+    ///
+    /// Longest-path ranking puts every node as high as possible, so a node
+    /// sharing a rank with the start of a long chain may sit several ranks above
+    /// all of its successors. Such a node is moved as close to its nearest
+    /// successor as `min_lengths` allow if it has at least as many outgoing as
+    /// incoming edges, so its edges get shorter overall. Nodes are processed
+    /// from the deepest rank up, letting a deferred node make room for its
+    /// predecessors.
+    fn defer_tied_nodes<N, E>(&self, graph: &DiGraph<N, E>, distances: &mut [usize]) {
+        let mut nodes: Vec<NodeIndex> = graph.node_indices().collect();
+        nodes.sort_by_key(|node| std::cmp::Reverse(distances[node.index()]));
+
+        for node in nodes {
+            let outgoing = graph.neighbors_directed(node, Outgoing).count();
+            let incoming = graph.neighbors_directed(node, Incoming).count();
+            if outgoing == 0 || outgoing < incoming {
+                continue;
+            }
+            let nearest = graph
+                .edges_directed(node, Outgoing)
+                .map(|edge| {
+                    let length = self.min_length(edge.id());
+                    distances[edge.target().index()].saturating_sub(length)
+                })
+                .min()
+                .unwrap();
+            let rank = &mut distances[node.index()];
+            if nearest > *rank {
+                *rank = nearest;
+            }
+        }
+    }
+
+    /// Move nodes with slack on all their edges toward their neighbors
+    ///
+    /// Every node may sit anywhere between the rank below its lowest
+    /// predecessor and the rank above its highest successor. A node whose
+    /// outgoing edges weigh more than its incoming ones moves down to the
+    /// latter bound, one whose incoming edges weigh more moves up to the
+    /// former. Each move lowers the total weighted edge span, so nodes are
+    /// visited top to bottom until none moves.
+    fn shorten_edge_spans<N, E>(&self, graph: &DiGraph<N, E>, distances: &mut [usize]) {
+        let weight = |edge: EdgeIndex| self.options.edge_weights.get(&edge).copied().unwrap_or(1.0);
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let mut nodes: Vec<NodeIndex> = graph.node_indices().collect();
+            nodes.sort_by_key(|node| (distances[node.index()], *node));
+
+            for node in nodes {
+                let incoming: Vec<_> = graph
+                    .edges_directed(node, Incoming)
+                    .filter(|edge| edge.source() != node)
+                    .collect();
+                let outgoing: Vec<_> = graph
+                    .edges_directed(node, Outgoing)
+                    .filter(|edge| edge.target() != node)
+                    .collect();
+                let lowest = incoming
+                    .iter()
+                    .map(|edge| distances[edge.source().index()] + self.min_length(edge.id()))
+                    .max();
+                let highest = outgoing
+                    .iter()
+                    .map(|edge| {
+                        let length = self.min_length(edge.id());
+                        distances[edge.target().index()].checked_sub(length)
+                    })
+                    .min()
+                    .flatten();
+                let pull_up: f32 = incoming.iter().map(|edge| weight(edge.id())).sum();
+                let pull_down: f32 = outgoing.iter().map(|edge| weight(edge.id())).sum();
+
+                let rank = distances[node.index()];
+                let target = match (lowest, highest) {
+                    (_, Some(highest)) if pull_down > pull_up && highest > rank => highest,
+                    (Some(lowest), _) if pull_up > pull_down && lowest < rank => lowest,
+                    _ => continue,
+                };
+                if lowest.is_some_and(|lowest| target < lowest)
+                    || highest.is_some_and(|highest| target > highest)
+                {
+                    continue;
+                }
+                distances[node.index()] = target;
+                changed = true;
+            }
+        }
+    }
+
+    /// Move nodes down a rank while that lowers edge length plus the width penalty
+    ///
+    /// The cost of a ranking is the total edge span plus `width_penalty` times
+    /// the squared node count of every rank. A node may only move down if all
+    /// of its successors stay below it, which can open new ranks at the
+    /// bottom. Nodes are visited top to bottom until no move pays off.
+    fn penalize_wide_ranks<N, E>(&self, graph: &DiGraph<N, E>, distances: &mut [usize]) {
+        let penalty = self.options.width_penalty;
+        let mut widths = Vec::new();
+        for &rank in distances.iter() {
+            if widths.len() <= rank + 1 {
+                widths.resize(rank + 2, 0);
+            }
+            widths[rank] += 1;
         }
 
-        visited.insert(node);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let mut nodes: Vec<NodeIndex> = graph.node_indices().collect();
+            nodes.sort_by_key(|node| (distances[node.index()], *node));
 
-        // Update distance if we found a longer path
-        let best_distance = distances.get(&node).copied().unwrap_or(0);
-        let new_distance = current_distance.max(best_distance);
-        distances.insert(node, new_distance);
+            for node in nodes {
+                let rank = distances[node.index()];
+                let movable = graph.edges_directed(node, Outgoing).all(|edge| {
+                    distances[edge.target().index()] > rank + self.min_length(edge.id())
+                });
+                if !movable {
+                    continue;
+                }
 
-        // Recursively visit successors
-        for successor in graph.neighbors_directed(node, Outgoing) {
-            self.dfs_longest_path(graph, successor, new_distance + 1, distances, visited);
+                let incoming = graph.neighbors_directed(node, Incoming).count() as f32;
+                let outgoing = graph.neighbors_directed(node, Outgoing).count() as f32;
+                let width_change = widths[rank + 1] as f32 - widths[rank] as f32 + 1.0;
+                if incoming - outgoing + 2.0 * penalty * width_change < 0.0 {
+                    distances[node.index()] = rank + 1;
+                    widths[rank] -= 1;
+                    widths[rank + 1] += 1;
+                    if widths.len() <= rank + 2 {
+                        widths.push(0);
+                    }
+                    changed = true;
+                }
+            }
         }
     }
 
     /// Reduce edge crossings using the barycenter heuristic
     /// This iteratively reorders nodes within layers to minimize crossings
-    fn reduce_crossings<N, E>(&self, graph: &DiGraph<N, E>, layers: &mut Vec<Vec<NodeIndex>>) {
+    ///
+    /// The ordering with the fewest crossings seen wins. With
+    /// `weight_crossings_by_span` every sweep is followed by swapping
+    /// neighbors that cross costly edges, unless `skip_transpose` is set,
+    /// and crossings are weighed by span.
+    /// Sweeps stop early once `time_budget` has passed since the first one.
+    fn reduce_crossings(
+        &self,
+        normalized: &NormalizedGraph,
+        layers: &mut [Vec<NodeIndex>],
+        pins: &RoutingPins,
+    ) {
         if layers.len() < 2 {
             return;
         }
+        let graph = &normalized.graph;
+        let weighted = self.options.weight_crossings_by_span;
+        let cost = CrossingCost::new(normalized, weighted);
+        let mut best_total = cost.total(graph, layers);
+        let mut best_order = layers.to_vec();
+        let mut previous_total = best_total;
+        let heuristic = self.options.crossing_heuristic;
+        let mut adjacent = LayerPositions::new(graph.node_count());
+        let order = |layer: &[NodeIndex], adjacent: &LayerPositions, forward| match heuristic {
+            CrossingHeuristic::Barycenter => {
+                self.order_by_barycenter(graph, layer, adjacent, forward, pins)
+            }
+            CrossingHeuristic::Median => {
+                self.order_by_median(graph, layer, adjacent, forward, pins)
+            }
+        };
 
+        let deadline = self
+            .options
+            .time_budget
+            .map(|budget| Instant::now() + budget);
         for _ in 0..self.options.max_iterations {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            self.record(|metrics| metrics.iterations += 1);
             let mut improved = false;
 
             // Forward pass: order layers 1..n based on their predecessors
             for i in 1..layers.len() {
-                let new_order = self.order_by_barycenter(graph, &layers[i], &layers[i - 1], true);
+                adjacent.fill(&layers[i - 1]);
+                let new_order = order(&layers[i], &adjacent, true);
                 if new_order != layers[i] {
                     layers[i] = new_order;
                     improved = true;
@@ -207,279 +1345,2724 @@ impl DagreLayout {
 
             // Backward pass: order layers n-1..0 based on their successors
             for i in (0..layers.len() - 1).rev() {
-                let new_order = self.order_by_barycenter(graph, &layers[i], &layers[i + 1], false);
+                adjacent.fill(&layers[i + 1]);
+                let new_order = order(&layers[i], &adjacent, false);
                 if new_order != layers[i] {
                     layers[i] = new_order;
                     improved = true;
                 }
             }
 
-            // If no improvement, we can stop early
-            if !improved {
-                break;
+            if weighted && !self.options.skip_transpose {
+                cost.transpose(graph, layers, self.options.max_iterations);
+            }
+            // Sweeps can add crossings back, so the best order seen is kept
+            let total = cost.total(graph, layers);
+            if total <= best_total {
+                best_total = total;
+                best_order.clone_from_slice(layers);
+            }
+
+            // If no improvement, we can stop early
+            if !improved {
+                break;
+            }
+            if let Some(threshold) = self.options.crossing_improvement_threshold {
+                if previous_total.saturating_sub(total) <= threshold {
+                    break;
+                }
+                previous_total = total;
+            }
+        }
+
+        layers.clone_from_slice(&best_order);
+    }
+
+    /// Order nodes in a layer based on barycenter of connected nodes in adjacent layer
+    fn order_by_barycenter(
+        &self,
+        graph: &DiGraph<NodeKind, EdgeIndex>,
+        layer: &[NodeIndex],
+        adjacent: &LayerPositions,
+        use_predecessors: bool,
+        pins: &RoutingPins,
+    ) -> Vec<NodeIndex> {
+        // Calculate barycenter for each node in current layer
+        let barycenter_of = |(index, &node): (usize, &NodeIndex)| {
+            let direction = if use_predecessors { Incoming } else { Outgoing };
+            // Every edge counts once per unit of weight, parallel edges add up
+            let (weighted_sum, total_weight) = graph
+                .edges_directed(node, direction)
+                .filter_map(|edge| {
+                    let neighbor = if use_predecessors {
+                        edge.source()
+                    } else {
+                        edge.target()
+                    };
+                    let weight = self.options.edge_weights.get(edge.weight()).copied();
+                    Some((adjacent.get(neighbor)? as f32, weight.unwrap_or(1.0)))
+                })
+                .fold((0.0, 0.0), |(sum, total), (pos, weight)| {
+                    (sum + pos * weight, total + weight)
+                });
+
+            let barycenter = if total_weight <= 0.0 {
+                // No connections, maintain relative position
+                index as f32
+            } else {
+                weighted_sum / total_weight
+            };
+
+            // Pull constrained dummies halfway toward their routing point
+            let barycenter = match pins.slot(node, adjacent.width()) {
+                Some(slot) => (barycenter + slot) * 0.5,
+                None => barycenter,
+            };
+
+            (node, barycenter)
+        };
+        // Barycenters only read the adjacent layer, so nodes are independent
+        #[cfg(feature = "parallel")]
+        let node_barycenters = {
+            use rayon::prelude::*;
+            layer.par_iter().enumerate().map(barycenter_of).collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let node_barycenters = layer.iter().enumerate().map(barycenter_of).collect();
+
+        sort_by_value(node_barycenters, self, graph)
+    }
+
+    /// Order nodes in a layer by the weighted median position of their
+    /// neighbors in the adjacent layer
+    ///
+    /// Odd neighbor counts take the middle position. Even counts interpolate
+    /// between the two middle positions, leaning toward the side whose other
+    /// neighbors lie closer together. Every edge counts once, `edge_weights`
+    /// are not considered. Nodes without neighbors keep their position.
+    fn order_by_median(
+        &self,
+        graph: &DiGraph<NodeKind, EdgeIndex>,
+        layer: &[NodeIndex],
+        adjacent: &LayerPositions,
+        use_predecessors: bool,
+        pins: &RoutingPins,
+    ) -> Vec<NodeIndex> {
+        let direction = if use_predecessors { Incoming } else { Outgoing };
+
+        let medians = layer
+            .iter()
+            .enumerate()
+            .map(|(index, &node)| {
+                let mut neighbors: Vec<f32> = graph
+                    .neighbors_directed(node, direction)
+                    .filter_map(|neighbor| Some(adjacent.get(neighbor)? as f32))
+                    .collect();
+                neighbors.sort_by(f32::total_cmp);
+
+                let count = neighbors.len();
+                let middle = count / 2;
+                let median = match count {
+                    0 => index as f32,
+                    _ if count % 2 == 1 => neighbors[middle],
+                    2 => (neighbors[0] + neighbors[1]) * 0.5,
+                    _ => {
+                        let left = neighbors[middle - 1] - neighbors[0];
+                        let right = neighbors[count - 1] - neighbors[middle];
+                        if left + right > 0.0 {
+                            (neighbors[middle - 1] * right + neighbors[middle] * left)
+                                / (left + right)
+                        } else {
+                            (neighbors[middle - 1] + neighbors[middle]) * 0.5
+                        }
+                    }
+                };
+
+                // Pull constrained dummies halfway toward their routing point
+                let median = match pins.slot(node, adjacent.width()) {
+                    Some(slot) => (median + slot) * 0.5,
+                    None => median,
+                };
+                (node, median)
+            })
+            .collect();
+
+        sort_by_value(medians, self, graph)
+    }
+
+    /// Assign final coordinates to nodes with the configured coordinate assigner
+    pub(crate) fn assign_coordinates(
+        &self,
+        normalized: &NormalizedGraph,
+        layers: &[Vec<NodeIndex>],
+    ) -> (HashMap<NodeIndex, (f32, f32)>, f32, f32) {
+        self.coordinate_assigner
+            .assign(&normalized.graph, layers, &self.options)
+    }
+
+    /// Mirror all coordinates along the rank axis so the first layer takes the last coordinate
+    fn mirror_ranks(
+        &self,
+        layers: &[Vec<NodeIndex>],
+        positions: &mut HashMap<NodeIndex, (f32, f32)>,
+        edge_points: &mut HashMap<EdgeIndex, Vec<(f32, f32)>>,
+        anchor_slots: &mut HashMap<NodeIndex, AnchorSlots>,
+    ) {
+        let coordinates = self.options.rank_coordinates(layers);
+        let (Some(first), Some(last)) = (coordinates.first(), coordinates.last()) else {
+            return;
+        };
+        let rank_dir = self.options.rank_dir;
+        let sum = first + last;
+        let mirror = |point: &mut (f32, f32)| {
+            *point = rank_dir.point(rank_dir.cross(*point), sum - rank_dir.rank(*point));
+        };
+
+        positions.values_mut().for_each(mirror);
+        edge_points.values_mut().flatten().for_each(mirror);
+        for slots in anchor_slots.values_mut() {
+            for (_, anchor) in slots.incoming.iter_mut().chain(slots.outgoing.iter_mut()) {
+                mirror(anchor);
+            }
+        }
+    }
+
+    /// Cross-axis size of a node of the normalized graph, zero for dummy nodes
+    pub(crate) fn cross_width(&self, normalized: &NormalizedGraph, node: NodeIndex) -> f32 {
+        if normalized.is_dummy(node) {
+            return 0.0;
+        }
+        let rank_dir = self.options.rank_dir;
+        let size = self.options.node_sizes.get(&node);
+        size.map_or(0.0, |&size| rank_dir.cross(size))
+    }
+
+    /// Split every layer wider than `max_width` into rows that fit, keeping its order
+    ///
+    /// Each node takes its cross-axis size plus `node_sep`, like with
+    /// [`CenteredAssigner`], and every row holds at least one node. Rows
+    /// become layers of their own after the crossings have been counted, so
+    /// edges into a later row pass the rows before it without bend points.
+    /// Returns the rank every layer was wrapped from, empty if none was.
+    pub(crate) fn wrap_layers(
+        &self,
+        normalized: &NormalizedGraph,
+        layers: &mut Vec<Vec<NodeIndex>>,
+    ) -> Vec<usize> {
+        let Some(max_width) = self.options.max_width else {
+            return Vec::new();
+        };
+        if self.options.allowed_rank_coordinates.is_some() {
+            return Vec::new();
+        }
+
+        let node_sep = self.options.node_sep;
+        let mut wrapped = Vec::with_capacity(layers.len());
+        let mut ranks = Vec::with_capacity(layers.len());
+        for (rank, layer) in std::mem::take(layers).into_iter().enumerate() {
+            let mut row: Vec<NodeIndex> = Vec::new();
+            let mut extent = -node_sep;
+            for node in layer {
+                let width = self.cross_width(normalized, node) + node_sep;
+                if !row.is_empty() && extent + width > max_width {
+                    wrapped.push(std::mem::take(&mut row));
+                    ranks.push(rank);
+                    extent = -node_sep;
+                }
+                extent += width;
+                row.push(node);
+            }
+            wrapped.push(row);
+            ranks.push(rank);
+        }
+
+        *layers = wrapped;
+        if ranks.len() == ranks.last().map_or(0, |rank| rank + 1) {
+            return Vec::new();
+        }
+        ranks
+    }
+
+    /// Lowest and highest cross-axis coordinate covered by the nodes of `layers`
+    fn cross_bounds(
+        &self,
+        normalized: &NormalizedGraph,
+        layers: &[Vec<NodeIndex>],
+        positions: &HashMap<NodeIndex, (f32, f32)>,
+    ) -> (f32, f32) {
+        let rank_dir = self.options.rank_dir;
+        let edges = layers.iter().flatten().map(|&node| {
+            let cross = rank_dir.cross(positions[&node]);
+            let half = self.cross_width(normalized, node) * 0.5;
+            (cross - half, cross + half)
+        });
+        edges.fold((f32::MAX, f32::MIN), |(low, high), (left, right)| {
+            (low.min(left), high.max(right))
+        })
+    }
+
+    /// Pull every node toward the mean cross-axis coordinate of its neighbors
+    ///
+    /// Layers are swept down and up a few times. Each node moves as far
+    /// toward its neighbors as its layer neighbors allow, keeping `node_sep`
+    /// plus half of both cross-axis sizes between them, and never past the
+    /// edges of the nodes' previous extent. The nodes are then shifted back
+    /// to the previous left edge and the cross-axis extent they span,
+    /// plus `node_sep`, is returned; it is never more than `cross_extent`.
+    fn pack_cross_axis(
+        &self,
+        normalized: &NormalizedGraph,
+        layers: &[Vec<NodeIndex>],
+        positions: &mut HashMap<NodeIndex, (f32, f32)>,
+        cross_extent: f32,
+    ) -> f32 {
+        const SWEEPS: usize = 4;
+        let rank_dir = self.options.rank_dir;
+        let node_sep = self.options.node_sep;
+        let graph = &normalized.graph;
+        let width_of = |node| self.cross_width(normalized, node);
+        let (left, right) = self.cross_bounds(normalized, layers, positions);
+        if left > right {
+            return cross_extent;
+        }
+
+        for sweep in 0..SWEEPS * 2 {
+            let downward = sweep % 2 == 0;
+            for index in 0..layers.len() {
+                let layer = match downward {
+                    true => &layers[index],
+                    false => &layers[layers.len() - 1 - index],
+                };
+                for (order, &node) in layer.iter().enumerate() {
+                    let neighbors: Vec<f32> = graph
+                        .neighbors_undirected(node)
+                        .filter(|&neighbor| neighbor != node)
+                        .map(|neighbor| rank_dir.cross(positions[&neighbor]))
+                        .collect();
+                    if neighbors.is_empty() {
+                        continue;
+                    }
+                    let target = neighbors.iter().sum::<f32>() / neighbors.len() as f32;
+
+                    let half = width_of(node) * 0.5;
+                    let gap = |other: NodeIndex| node_sep + half + width_of(other) * 0.5;
+                    let mut lower = left + half;
+                    let mut upper = right - half;
+                    if let Some(&previous) = order.checked_sub(1).map(|left| &layer[left]) {
+                        lower = lower.max(rank_dir.cross(positions[&previous]) + gap(previous));
+                    }
+                    if let Some(&next) = layer.get(order + 1) {
+                        upper = upper.min(rank_dir.cross(positions[&next]) - gap(next));
+                    }
+                    if lower > upper {
+                        continue;
+                    }
+
+                    let position = positions.get_mut(&node).unwrap();
+                    let cross = target.clamp(lower, upper);
+                    *position = rank_dir.point(cross, rank_dir.rank(*position));
+                }
+            }
+        }
+
+        let (packed_left, packed_right) = self.cross_bounds(normalized, layers, positions);
+        for position in positions.values_mut() {
+            let cross = rank_dir.cross(*position) - (packed_left - left);
+            *position = rank_dir.point(cross, rank_dir.rank(*position));
+        }
+        cross_extent.min(packed_right - packed_left + node_sep)
+    }
+
+    /// Move every node to the mean cross-axis coordinate of its successors
+    ///
+    /// Layers are visited from the last one up, so every node follows its
+    /// already centered successors; nodes without successors on the next
+    /// layer stay put. Where the targets of a layer would bring neighbors
+    /// closer than `node_sep` plus half of both cross-axis sizes, the layer
+    /// is pushed apart once from the left and once from the right and every
+    /// node takes the middle of both, which keeps mirrored layers mirrored.
+    /// The nodes are shifted back to the previous left edge and the larger of
+    /// `cross_extent` and the extent they span, plus `node_sep`, is returned.
+    fn center_parents(
+        &self,
+        normalized: &NormalizedGraph,
+        layers: &[Vec<NodeIndex>],
+        positions: &mut HashMap<NodeIndex, (f32, f32)>,
+        cross_extent: f32,
+    ) -> f32 {
+        let rank_dir = self.options.rank_dir;
+        let node_sep = self.options.node_sep;
+        let graph = &normalized.graph;
+        let (left, right) = self.cross_bounds(normalized, layers, positions);
+        if left > right {
+            return cross_extent;
+        }
+        let gap = |a: NodeIndex, b: NodeIndex| {
+            node_sep + (self.cross_width(normalized, a) + self.cross_width(normalized, b)) * 0.5
+        };
+
+        for pair in layers.windows(2).rev() {
+            let (layer, next) = (&pair[0], &pair[1]);
+            let next: HashSet<NodeIndex> = next.iter().copied().collect();
+            let targets: Vec<f32> = layer
+                .iter()
+                .map(|&node| {
+                    let successors: Vec<f32> = graph
+                        .neighbors_directed(node, Outgoing)
+                        .filter(|successor| next.contains(successor))
+                        .map(|successor| rank_dir.cross(positions[&successor]))
+                        .collect();
+                    match successors.is_empty() {
+                        true => rank_dir.cross(positions[&node]),
+                        false => successors.iter().sum::<f32>() / successors.len() as f32,
+                    }
+                })
+                .collect();
+
+            let mut pushed_right = targets.clone();
+            for order in 1..layer.len() {
+                let lowest = pushed_right[order - 1] + gap(layer[order - 1], layer[order]);
+                pushed_right[order] = pushed_right[order].max(lowest);
+            }
+            let mut pushed_left = targets;
+            for order in (0..layer.len().saturating_sub(1)).rev() {
+                let highest = pushed_left[order + 1] - gap(layer[order], layer[order + 1]);
+                pushed_left[order] = pushed_left[order].min(highest);
+            }
+            for (order, node) in layer.iter().enumerate() {
+                let cross = (pushed_right[order] + pushed_left[order]) * 0.5;
+                let position = positions.get_mut(node).unwrap();
+                *position = rank_dir.point(cross, rank_dir.rank(*position));
+            }
+        }
+
+        let (centered_left, centered_right) = self.cross_bounds(normalized, layers, positions);
+        for position in positions.values_mut() {
+            let cross = rank_dir.cross(*position) - (centered_left - left);
+            *position = rank_dir.point(cross, rank_dir.rank(*position));
+        }
+        cross_extent.max(centered_right - centered_left + node_sep)
+    }
+
+    /// Put the dummy nodes of every long edge on a shared cross-axis coordinate
+    ///
+    /// The candidates are the coordinates of the edge's endpoints and of its
+    /// dummy nodes. A candidate fits if every dummy node stays `node_sep`
+    /// plus half of both cross-axis sizes from its layer neighbors and
+    /// within the extent of all nodes; the fitting one that moves the dummy
+    /// nodes least wins. Edges are visited in index order, and chains
+    /// without a fitting candidate are left as they are.
+    fn straighten_long_edges(
+        &self,
+        normalized: &NormalizedGraph,
+        layers: &[Vec<NodeIndex>],
+        positions: &mut HashMap<NodeIndex, (f32, f32)>,
+    ) {
+        let rank_dir = self.options.rank_dir;
+        let node_sep = self.options.node_sep;
+        let width_of = |node| self.cross_width(normalized, node);
+        let slots: HashMap<NodeIndex, (usize, usize)> = layers
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, layer)| {
+                layer
+                    .iter()
+                    .enumerate()
+                    .map(move |(order, &node)| (node, (rank, order)))
+            })
+            .collect();
+        let (left, right) = self.cross_bounds(normalized, layers, positions);
+
+        let mut edges: Vec<EdgeIndex> = normalized.chains.keys().copied().collect();
+        edges.sort();
+        for edge in edges {
+            let chain = &normalized.chains[&edge];
+            let cross = |node: &NodeIndex| rank_dir.cross(positions[node]);
+            let fits = |coordinate: f32| {
+                chain.iter().all(|dummy| {
+                    let (rank, order) = slots[dummy];
+                    let layer = &layers[rank];
+                    let before = order.checked_sub(1).map(|previous| layer[previous]);
+                    let after = layer.get(order + 1).copied();
+                    let gap = |other: NodeIndex| node_sep + width_of(other) * 0.5;
+                    (left..=right).contains(&coordinate)
+                        && before.is_none_or(|other| coordinate >= cross(&other) + gap(other))
+                        && after.is_none_or(|other| coordinate <= cross(&other) - gap(other))
+                })
+            };
+            let displacement = |coordinate: f32| {
+                let moves = chain.iter().map(|dummy| (cross(dummy) - coordinate).abs());
+                moves.sum::<f32>()
+            };
+
+            let first = chain[0];
+            let last = chain[chain.len() - 1];
+            let ends = normalized.graph.neighbors_directed(first, Incoming);
+            let ends = ends.chain(normalized.graph.neighbors_directed(last, Outgoing));
+            let best = ends
+                .chain(chain.iter().copied())
+                .map(|node| cross(&node))
+                .filter(|&coordinate| fits(coordinate))
+                .min_by(|&a, &b| displacement(a).total_cmp(&displacement(b)));
+            if let Some(coordinate) = best {
+                for dummy in chain {
+                    let position = positions.get_mut(dummy).unwrap();
+                    *position = rank_dir.point(coordinate, rank_dir.rank(*position));
+                }
+            }
+        }
+    }
+
+    /// Move real nodes with exactly one edge toward the other end of that edge
+    ///
+    /// Such nodes have nothing else to balance against, so they follow the
+    /// node or bend point they attach to on the cross axis. Like dummy
+    /// straightening they stay `node_sep` plus half of both cross-axis sizes
+    /// from their layer neighbors.
+    fn pull_weak_nodes(
+        &self,
+        normalized: &NormalizedGraph,
+        layers: &[Vec<NodeIndex>],
+        positions: &mut HashMap<NodeIndex, (f32, f32)>,
+    ) {
+        let rank_dir = self.options.rank_dir;
+        let cross = |pos: (f32, f32)| rank_dir.cross(pos);
+        let graph = &normalized.graph;
+        let gap = |a: NodeIndex, b: NodeIndex| {
+            let widths = self.cross_width(normalized, a) + self.cross_width(normalized, b);
+            self.options.node_sep + widths * 0.5
+        };
+
+        for layer in layers {
+            for (order, &node) in layer.iter().enumerate() {
+                if normalized.is_dummy(node) {
+                    continue;
+                }
+                let mut neighbors = graph.neighbors_undirected(node);
+                let (Some(neighbor), None) = (neighbors.next(), neighbors.next()) else {
+                    continue;
+                };
+
+                let mut coordinate = cross(positions[&neighbor]);
+                let min = order
+                    .checked_sub(1)
+                    .map(|left| cross(positions[&layer[left]]) + gap(node, layer[left]));
+                let max = layer
+                    .get(order + 1)
+                    .map(|&right| cross(positions[&right]) - gap(node, right));
+                if let Some(max) = max {
+                    coordinate = coordinate.min(max);
+                }
+                if let Some(min) = min {
+                    coordinate = coordinate.max(min);
+                }
+
+                let position = positions.get_mut(&node).unwrap();
+                *position = rank_dir.point(coordinate, rank_dir.rank(*position));
+            }
+        }
+    }
+
+    /// Move the spine nodes of every component onto a shared cross-axis coordinate
+    ///
+    /// A spine node is the only node of its weakly connected component in
+    /// its layer, dummy nodes included, so every path crossing that rank
+    /// passes through it. The shared coordinate is the mean of the spine
+    /// nodes, clamped to the range all of them can reach while staying
+    /// `node_sep` plus half of both cross-axis sizes from their layer neighbors.
+    fn align_spine(
+        &self,
+        normalized: &NormalizedGraph,
+        layers: &[Vec<NodeIndex>],
+        positions: &mut HashMap<NodeIndex, (f32, f32)>,
+    ) {
+        let rank_dir = self.options.rank_dir;
+        let cross = |pos: (f32, f32)| rank_dir.cross(pos);
+        let graph = &normalized.graph;
+        let gap = |a: NodeIndex, b: NodeIndex| {
+            let widths = self.cross_width(normalized, a) + self.cross_width(normalized, b);
+            self.options.node_sep + widths * 0.5
+        };
+
+        let mut components = UnionFind::new(graph.node_count());
+        for edge in graph.edge_references() {
+            components.union(edge.source().index(), edge.target().index());
+        }
+
+        // Spine nodes per component with the range their layer neighbors leave them
+        let mut spines: HashMap<usize, Vec<(NodeIndex, f32, f32)>> = HashMap::new();
+        for layer in layers {
+            let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+            for (order, node) in layer.iter().enumerate() {
+                members
+                    .entry(components.find(node.index()))
+                    .or_default()
+                    .push(order);
+            }
+            for (component, orders) in members {
+                let [order] = orders[..] else {
+                    continue;
+                };
+                if normalized.is_dummy(layer[order]) {
+                    continue;
+                }
+                let node = layer[order];
+                let min = order.checked_sub(1).map_or(f32::NEG_INFINITY, |left| {
+                    cross(positions[&layer[left]]) + gap(node, layer[left])
+                });
+                let max = layer.get(order + 1).map_or(f32::INFINITY, |&right| {
+                    cross(positions[&right]) - gap(node, right)
+                });
+                spines
+                    .entry(component)
+                    .or_default()
+                    .push((layer[order], min, max));
+            }
+        }
+
+        for spine in spines.values().filter(|spine| spine.len() > 1) {
+            let mean = spine
+                .iter()
+                .map(|&(node, ..)| cross(positions[&node]))
+                .sum::<f32>()
+                / spine.len() as f32;
+            let min = spine
+                .iter()
+                .map(|&(_, min, _)| min)
+                .fold(f32::NEG_INFINITY, f32::max);
+            let max = spine
+                .iter()
+                .map(|&(.., max)| max)
+                .fold(f32::INFINITY, f32::min);
+            let target = if min <= max {
+                mean.clamp(min, max)
+            } else {
+                mean
+            };
+
+            for &(node, min, max) in spine {
+                let coordinate = target.min(max).max(min);
+                let position = positions.get_mut(&node).unwrap();
+                *position = rank_dir.point(coordinate, rank_dir.rank(*position));
+            }
+        }
+    }
+
+    /// Pull the dummy nodes of long edges between their neighbors on the
+    /// cross axis so that successive bend points never move away from the target
+    ///
+    /// Dummy nodes are only moved within the free space left by their layer
+    /// neighbors, so the ordering and separation of every layer is preserved.
+    fn make_routes_monotone(
+        &self,
+        normalized: &NormalizedGraph,
+        layers: &[Vec<NodeIndex>],
+        positions: &mut HashMap<NodeIndex, (f32, f32)>,
+    ) {
+        let rank_dir = self.options.rank_dir;
+        let cross = |pos: (f32, f32)| rank_dir.cross(pos);
+        let layer_of: HashMap<NodeIndex, (usize, usize)> = layers
+            .iter()
+            .enumerate()
+            .flat_map(|(layer_idx, layer)| {
+                layer
+                    .iter()
+                    .enumerate()
+                    .map(move |(order, &node)| (node, (layer_idx, order)))
+            })
+            .collect();
+
+        let gap = |a: NodeIndex, b: NodeIndex| {
+            let widths = self.cross_width(normalized, a) + self.cross_width(normalized, b);
+            self.options.node_sep + widths * 0.5
+        };
+
+        let mut edges: Vec<_> = normalized.chains.keys().copied().collect();
+        edges.sort();
+
+        for edge in edges {
+            let chain = &normalized.chains[&edge];
+            let graph = &normalized.graph;
+            let (Some(source), Some(target)) = (
+                graph.neighbors_directed(chain[0], Incoming).next(),
+                graph
+                    .neighbors_directed(chain[chain.len() - 1], Outgoing)
+                    .next(),
+            ) else {
+                continue;
+            };
+            let end = cross(positions[&target]);
+            let mut previous = cross(positions[&source]);
+
+            for &dummy in chain {
+                let (layer_idx, order) = layer_of[&dummy];
+                let layer = &layers[layer_idx];
+
+                // Stay between the previous bend and the target
+                let (low, high) = if previous <= end {
+                    (previous, end)
+                } else {
+                    (end, previous)
+                };
+                let mut coordinate = cross(positions[&dummy]).clamp(low, high);
+
+                // Never get closer than node_sep to the border of a layer neighbor
+                let min = order
+                    .checked_sub(1)
+                    .map(|left| cross(positions[&layer[left]]) + gap(layer[left], dummy));
+                let max = layer
+                    .get(order + 1)
+                    .map(|&right| cross(positions[&right]) - gap(right, dummy));
+                if let Some(min) = min {
+                    coordinate = coordinate.max(min);
+                }
+                if let Some(max) = max {
+                    coordinate = coordinate.min(max);
+                }
+
+                let position = positions.get_mut(&dummy).unwrap();
+                *position = rank_dir.point(coordinate, rank_dir.rank(*position));
+                previous = coordinate;
+            }
+        }
+    }
+
+    /// Route the edges of a graph whose nodes were placed by the caller
+    ///
+    /// Ranks are inferred from the distinct rank-axis coordinates of the
+    /// given positions. Edges spanning several ranks get a bend point on every
+    /// rank in between, interpolated along the straight line between their
+    /// endpoints. Endpoints are clipped to node shapes as in [`Self::compute`].
+    /// Edges with an endpoint missing from `node_positions` are skipped.
+    pub fn edges_only<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        node_positions: &HashMap<NodeIndex, (f32, f32)>,
+    ) -> HashMap<EdgeIndex, Vec<(f32, f32)>> {
+        let rank_dir = self.options.rank_dir;
+        let placed = graph.filter_map(
+            |node, _| node_positions.contains_key(&node).then_some(node),
+            |edge, _| Some(edge),
+        );
+
+        // Group the placed nodes into ranks by their rank coordinate
+        let mut rank_coords: Vec<f32> = placed
+            .node_weights()
+            .map(|node| rank_dir.rank(node_positions[node]))
+            .collect();
+        rank_coords.sort_by(f32::total_cmp);
+        rank_coords.dedup_by(|a, b| (*a - *b).abs() < 1e-3);
+        let rank_of = |pos| {
+            let coord = rank_dir.rank(pos);
+            rank_coords.partition_point(|&c| c < coord - 1e-3)
+        };
+
+        let mut layers = vec![Vec::new(); rank_coords.len()];
+        for node in placed.node_indices() {
+            layers[rank_of(node_positions[&placed[node]])].push(node);
+        }
+        let normalized = normalize(&placed, &mut layers, &HashSet::new());
+
+        // Interpolate dummies between the endpoints of their edge
+        let mut positions: HashMap<NodeIndex, (f32, f32)> = placed
+            .node_indices()
+            .map(|node| (node, node_positions[&placed[node]]))
+            .collect();
+        for (&edge, chain) in &normalized.chains {
+            let (source, target) = placed.edge_endpoints(edge).unwrap();
+            let (from, to) = (positions[&source], positions[&target]);
+            let (from_rank, to_rank) = (rank_dir.rank(from), rank_dir.rank(to));
+            for (i, &dummy) in chain.iter().enumerate() {
+                let rank = rank_coords[rank_of(from) + i + 1];
+                let t = (rank - from_rank) / (to_rank - from_rank);
+                let cross = rank_dir.cross(from) + (rank_dir.cross(to) - rank_dir.cross(from)) * t;
+                positions.insert(dummy, rank_dir.point(cross, rank));
+            }
+        }
+
+        // Clip against the nodes of the caller's graph
+        placed
+            .edge_references()
+            .map(|edge| {
+                let points = polyline(edge, &normalized, &positions);
+                let (source, target) = (placed[edge.source()], placed[edge.target()]);
+                (*edge.weight(), self.clip_endpoints(source, target, points))
+            })
+            .collect()
+    }
+
+    /// Build the polyline of every input edge, passing through its dummy nodes
+    ///
+    /// Endpoints of nodes with a known size are clipped to the node's shape.
+    fn route_edges<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        normalized: &NormalizedGraph,
+        positions: &HashMap<NodeIndex, (f32, f32)>,
+    ) -> HashMap<EdgeIndex, Vec<(f32, f32)>> {
+        let offsets = self.parallel_offsets(graph);
+        graph
+            .edge_references()
+            .map(|edge| {
+                let mut points = polyline(edge, normalized, positions);
+                if let Some(&offset) = offsets.get(&edge.id())
+                    && points.len() == 2
+                {
+                    let rank_dir = self.options.rank_dir;
+                    let (from, to) = (points[0], points[1]);
+                    let cross = (rank_dir.cross(from) + rank_dir.cross(to)) * 0.5 + offset;
+                    let rank = (rank_dir.rank(from) + rank_dir.rank(to)) * 0.5;
+                    points.insert(1, rank_dir.point(cross, rank));
+                }
+                let points = self.clip_endpoints(edge.source(), edge.target(), points);
+                (edge.id(), points)
+            })
+            .collect()
+    }
+
+    /// Cross-axis offset of every edge that shares its source and target with others
+    ///
+    /// The edges of such a group are `parallel_edge_sep` apart in index
+    /// order, centered on the straight line. Edges without an offset, such as
+    /// the middle one of an odd group, are left out.
+    fn parallel_offsets<N, E>(&self, graph: &DiGraph<N, E>) -> HashMap<EdgeIndex, f32> {
+        let mut groups: HashMap<(NodeIndex, NodeIndex), Vec<EdgeIndex>> = HashMap::new();
+        for edge in graph.edge_references() {
+            if edge.source() != edge.target() {
+                groups
+                    .entry((edge.source(), edge.target()))
+                    .or_default()
+                    .push(edge.id());
+            }
+        }
+
+        let sep = self.options.parallel_edge_sep;
+        let mut offsets = HashMap::new();
+        for edges in groups.values_mut().filter(|edges| edges.len() > 1) {
+            edges.sort();
+            let center = (edges.len() - 1) as f32 * 0.5;
+            for (i, &edge) in edges.iter().enumerate() {
+                let offset = (i as f32 - center) * sep;
+                if offset != 0.0 {
+                    offsets.insert(edge, offset);
+                }
+            }
+        }
+        offsets
+    }
+
+    /// Clip the first and last point of a polyline to its source and target node
+    pub(crate) fn clip_endpoints(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+        mut points: Vec<(f32, f32)>,
+    ) -> Vec<(f32, f32)> {
+        let last = points.len() - 1;
+        points[0] = self.clip_to_node(source, points[0], points[1]);
+        points[last] = self.clip_to_node(target, points[last], points[last - 1]);
+        points
+    }
+
+    /// Move an edge endpoint from a node's center to its border, facing `toward`
+    pub(crate) fn clip_to_node(
+        &self,
+        node: NodeIndex,
+        center: (f32, f32),
+        toward: (f32, f32),
+    ) -> (f32, f32) {
+        match self.options.node_sizes.get(&node) {
+            Some(&size) => {
+                let shape = self
+                    .options
+                    .node_shapes
+                    .get(&node)
+                    .copied()
+                    .unwrap_or_default();
+                shape.clip(center, size, toward)
+            }
+            None => center,
+        }
+    }
+}
+
+/// Nodes sorted by their value, ties broken by `initial_order`, then by
+/// the layout's node order, then by [`tie_key`]
+///
+/// Nodes with an `initial_order` entry come before tied nodes without one.
+/// With a node order, tied input nodes come before dummy nodes.
+fn sort_by_value(
+    mut values: Vec<(NodeIndex, f32)>,
+    layout: &DagreLayout,
+    graph: &DiGraph<NodeKind, EdgeIndex>,
+) -> Vec<NodeIndex> {
+    let options = &layout.options;
+    let initial = |node: NodeIndex| {
+        let initial = options.initial_order.get(&node).copied();
+        initial.unwrap_or(usize::MAX)
+    };
+    let real = |node: NodeIndex| matches!(graph[node], NodeKind::Real(_));
+    values.sort_by(|&(a, a_value), &(b, b_value)| {
+        a_value
+            .partial_cmp(&b_value)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| initial(a).cmp(&initial(b)))
+            .then_with(|| match &layout.node_order {
+                Some(compare) if real(a) && real(b) => compare(a, b),
+                Some(_) => real(b).cmp(&real(a)),
+                None => std::cmp::Ordering::Equal,
+            })
+            .then_with(|| tie_key(options.seed, a).cmp(&tie_key(options.seed, b)))
+    });
+    values.into_iter().map(|(node, _)| node).collect()
+}
+
+/// Key that orders tied nodes, the node index unless a seed scrambles it
+///
+/// Seeded keys come from the SplitMix64 finalizer, so every seed gives a
+/// fixed pseudo-random order of the nodes.
+fn tie_key(seed: Option<u64>, node: NodeIndex) -> u64 {
+    let Some(seed) = seed else {
+        return node.index() as u64;
+    };
+    let mut z = seed
+        .wrapping_add(node.index() as u64)
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Points of an edge from its source center through its dummy nodes to its target center
+fn polyline<E>(
+    edge: petgraph::graph::EdgeReference<'_, E>,
+    normalized: &NormalizedGraph,
+    positions: &HashMap<NodeIndex, (f32, f32)>,
+) -> Vec<(f32, f32)> {
+    let mut points = vec![positions[&edge.source()]];
+    if let Some(chain) = normalized.chains.get(&edge.id()) {
+        points.extend(chain.iter().map(|dummy| positions[dummy]));
+    }
+    points.push(positions[&edge.target()]);
+    points
+}
+
+impl Default for DagreLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinates::CenteredAssigner;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_simple_chain() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+
+        let layout = DagreLayout::new();
+        let result = layout.compute(&graph).unwrap();
+
+        assert_eq!(result.layers.len(), 3);
+        assert_eq!(result.layers[0], vec![a]);
+        assert_eq!(result.layers[1], vec![b]);
+        assert_eq!(result.layers[2], vec![c]);
+    }
+
+    #[test]
+    fn test_diamond_pattern() {
+        let mut graph = Graph::new();
+        let start = graph.add_node("start");
+        let left = graph.add_node("left");
+        let right = graph.add_node("right");
+        let end = graph.add_node("end");
+
+        graph.add_edge(start, left, ());
+        graph.add_edge(start, right, ());
+        graph.add_edge(left, end, ());
+        graph.add_edge(right, end, ());
+
+        let layout = DagreLayout::new();
+        let result = layout.compute(&graph).unwrap();
+
+        assert_eq!(result.layers.len(), 3);
+        assert_eq!(result.layers[0], vec![start]);
+        assert_eq!(result.layers[1].len(), 2);
+        assert!(result.layers[1].contains(&left));
+        assert!(result.layers[1].contains(&right));
+        assert_eq!(result.layers[2], vec![end]);
+    }
+
+    #[test]
+    fn test_left_to_right_layout() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        graph.add_edge(a, b, ());
+
+        let options = LayoutOptions {
+            rank_dir: RankDir::LeftToRight,
+            ..Default::default()
+        };
+        let layout = DagreLayout::with_options(options);
+        let result = layout.compute(&graph).unwrap();
+
+        let pos_a = result.node_positions[&a];
+        let pos_b = result.node_positions[&b];
+
+        // In left-to-right layout, B should be to the right of A
+        assert!(pos_b.0 > pos_a.0);
+    }
+
+    #[test]
+    fn test_empty_graph() {
+        let graph: Graph<&str, (), petgraph::Directed> = Graph::new();
+        let layout = DagreLayout::new();
+        let result = layout.compute(&graph).unwrap();
+
+        assert!(result.layers.is_empty());
+        assert!(result.node_positions.is_empty());
+    }
+
+    #[test]
+    fn test_single_node() {
+        let mut graph: Graph<&str, (), petgraph::Directed> = Graph::new();
+        let node = graph.add_node("single");
+
+        let layout = DagreLayout::new();
+        let result = layout.compute(&graph).unwrap();
+
+        assert_eq!(result.layers.len(), 1);
+        assert_eq!(result.layers[0], vec![node]);
+        assert_eq!(result.node_positions[&node], (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_layer_ordering_properties() {
+        // Test that edges only go from lower layer indices to higher layer indices
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+
+        graph.add_edge(a, b, ());
+        graph.add_edge(a, c, ());
+        graph.add_edge(b, d, ());
+        graph.add_edge(c, d, ());
+
+        let layout = DagreLayout::new();
+        let result = layout.compute(&graph).unwrap();
+
+        // Create a mapping from node to layer index
+        let mut node_to_layer = std::collections::HashMap::new();
+        for (layer_idx, layer) in result.layers.iter().enumerate() {
+            for &node in layer {
+                node_to_layer.insert(node, layer_idx);
+            }
+        }
+
+        // Verify all edges respect layer ordering
+        for edge in graph.edge_indices() {
+            if let Some((source, target)) = graph.edge_endpoints(edge) {
+                let source_layer = node_to_layer[&source];
+                let target_layer = node_to_layer[&target];
+                assert!(
+                    source_layer < target_layer,
+                    "Edge from layer {} to layer {} violates hierarchy",
+                    source_layer,
+                    target_layer
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_coordinate_consistency() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+
+        let layout = DagreLayout::new();
+        let result = layout.compute(&graph).unwrap();
+
+        // In top-to-bottom layout, Y coordinates should increase with layer depth
+        let pos_a = result.node_positions[&a];
+        let pos_b = result.node_positions[&b];
+        let pos_c = result.node_positions[&c];
+
+        assert!(pos_a.1 < pos_b.1, "A should be above B");
+        assert!(pos_b.1 < pos_c.1, "B should be above C");
+    }
+
+    #[test]
+    fn test_edge_points_follow_node_positions() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let edge = graph.add_edge(a, b, ());
+
+        let layout = DagreLayout::new();
+        let result = layout.compute(&graph).unwrap();
+
+        assert_eq!(
+            result.edge_points[&edge],
+            vec![result.node_positions[&a], result.node_positions[&b]]
+        );
+    }
+
+    #[test]
+    fn test_long_edge_points_cross_every_layer() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        let short = graph.add_edge(c, d, ());
+        let long = graph.add_edge(a, d, ());
+
+        let result = DagreLayout::new().compute(&graph).unwrap();
+
+        assert_eq!(result.edge_points[&short].len(), 2);
+        let points = &result.edge_points[&long];
+        assert_eq!(points.len(), 4);
+        assert_eq!(points[0], result.node_positions[&a]);
+        assert_eq!(points[3], result.node_positions[&d]);
+        for (point, node) in points[1..3].iter().zip([b, c]) {
+            assert_eq!(point.1, result.node_positions[&node].1);
+            assert_ne!(point.0, result.node_positions[&node].0);
+        }
+    }
+
+    #[test]
+    fn test_dummy_nodes_do_not_leak_into_result() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..5).map(|i| graph.add_node(i)).collect();
+        for pair in nodes.windows(2) {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+        graph.add_edge(nodes[0], nodes[4], ());
+        graph.add_edge(nodes[1], nodes[4], ());
+
+        let result = DagreLayout::new().compute(&graph).unwrap();
+
+        assert_eq!(result.node_positions.len(), graph.node_count());
+        let placed: usize = result.layers.iter().map(Vec::len).sum();
+        assert_eq!(placed, graph.node_count());
+        assert!(
+            result
+                .layers
+                .iter()
+                .flatten()
+                .all(|node| nodes.contains(node))
+        );
+    }
+
+    #[test]
+    fn test_crossing_count_of_complete_bipartite_layers() {
+        let mut graph = Graph::new();
+        let upper: Vec<_> = (0..2).map(|_| graph.add_node("upper")).collect();
+        let lower: Vec<_> = (0..2).map(|_| graph.add_node("lower")).collect();
+        for &source in &upper {
+            for &target in &lower {
+                graph.add_edge(source, target, ());
+            }
+        }
+
+        // Every order of a complete bipartite pair of layers crosses once
+        let result = DagreLayout::new().compute(&graph).unwrap();
+        assert_eq!(result.crossing_count, 1);
+
+        // Dropping one edge leaves an order without crossings
+        graph.remove_edge(graph.find_edge(upper[0], lower[1]).unwrap());
+        let result = DagreLayout::new().compute(&graph).unwrap();
+        assert_eq!(result.crossing_count, 0);
+    }
+
+    #[test]
+    fn test_options_builder_matches_struct_literal() {
+        let node = NodeIndex::new(0);
+        let built = LayoutOptions::builder()
+            .rank_dir(RankDir::LeftToRight)
+            .node_sep(20.0)
+            .rank_sep(40.0)
+            .max_iterations(3)
+            .node_size(node, (10.0, 5.0))
+            .hybrid(true)
+            .max_width(Some(300.0))
+            .edge_label_sizes(HashMap::from([(EdgeIndex::new(0), (30.0, 10.0))]))
+            .build();
+        let literal = LayoutOptions {
+            rank_dir: RankDir::LeftToRight,
+            node_sep: 20.0,
+            rank_sep: 40.0,
+            max_iterations: 3,
+            node_sizes: HashMap::from([(node, (10.0, 5.0))]),
+            hybrid: true,
+            max_width: Some(300.0),
+            edge_label_sizes: HashMap::from([(EdgeIndex::new(0), (30.0, 10.0))]),
+            ..Default::default()
+        };
+
+        // Options have no equality, their debug output covers every field
+        assert_eq!(format!("{:?}", built), format!("{:?}", literal));
+    }
+
+    #[test]
+    fn test_compute_with_edge_weights_matches_weight_option() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let leaves: Vec<_> = (0..3).map(|_| graph.add_node("leaf")).collect();
+        for &leaf in &leaves {
+            graph.add_edge(root, leaf, ());
+        }
+        let tail = graph.add_node("tail");
+        graph.add_edge(leaves[0], tail, ());
+        graph.add_edge(leaves[2], tail, ());
+        let heavy = |edge: EdgeIndex| if edge.index() % 2 == 0 { 5.0 } else { 1.0 };
+
+        let layout = DagreLayout::new();
+        let unweighted = layout.compute_with_edge_weights(&graph, |_| 1.0).unwrap();
+        assert!(unweighted.approx_eq(&layout.compute(&graph).unwrap()));
+
+        let weighted = layout.compute_with_edge_weights(&graph, heavy).unwrap();
+        let options = LayoutOptions {
+            edge_weights: graph
+                .edge_indices()
+                .map(|edge| (edge, heavy(edge)))
+                .collect(),
+            ..Default::default()
+        };
+        let expected = DagreLayout::with_options(options).compute(&graph).unwrap();
+        assert!(weighted.approx_eq(&expected));
+        assert!(layout.options.edge_weights.is_empty());
+    }
+
+    #[test]
+    fn test_parallel_edges_fan_out() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let edges: Vec<_> = (0..3).map(|_| graph.add_edge(a, b, ())).collect();
+        let c = graph.add_node("C");
+        let single = graph.add_edge(b, c, ());
+
+        let result = DagreLayout::new().compute(&graph).unwrap();
+        let (source, target) = (result.node_positions[&a], result.node_positions[&b]);
+        let middle = (source.0 + target.0) * 0.5;
+        let rank = (source.1 + target.1) * 0.5;
+        assert_eq!(result.edge_points[&edges[0]][1], (middle - 10.0, rank));
+        assert_eq!(result.edge_points[&edges[1]], vec![source, target]);
+        assert_eq!(result.edge_points[&edges[2]][1], (middle + 10.0, rank));
+        assert_eq!(result.edge_points[&single].len(), 2);
+
+        let result = DagreLayout::with_options(LayoutOptions {
+            parallel_edge_sep: 0.0,
+            ..Default::default()
+        })
+        .compute(&graph)
+        .unwrap();
+        assert!(edges.iter().all(|edge| result.edge_points[edge].len() == 2));
+    }
+
+    #[test]
+    fn test_rank_sep_overrides() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..4).map(|i| graph.add_node(i)).collect();
+        for pair in nodes.windows(2) {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            rank_sep_overrides: HashMap::from([(0, 250.0), (2, 20.0)]),
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+        let y = |node| result.node_positions[&node].1;
+        let ys: Vec<f32> = nodes.iter().map(|&node| y(node)).collect();
+        assert_eq!(ys, [0.0, 250.0, 350.0, 370.0]);
+    }
+
+    #[test]
+    fn test_margins_offset_layout() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let middle = graph.add_node("middle");
+        let end = graph.add_node("end");
+        graph.add_edge(root, middle, ());
+        graph.add_edge(middle, end, ());
+        graph.add_edge(root, end, ());
+
+        let options = LayoutOptions {
+            rank_dir: RankDir::BottomToTop,
+            ..Default::default()
+        };
+        let plain = DagreLayout::with_options(options.clone())
+            .compute(&graph)
+            .unwrap();
+        let layout = DagreLayout::with_options(LayoutOptions {
+            margin_x: 20.0,
+            margin_y: 30.0,
+            ..options
+        });
+        let result = layout.compute(&graph).unwrap();
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+        assert_eq!(result.width, plain.width + 40.0);
+        assert_eq!(result.height, plain.height + 60.0);
+
+        let shifted = |(x, y): (f32, f32)| (x + 20.0, y + 30.0);
+        for (node, &position) in &plain.node_positions {
+            assert_eq!(result.node_positions[node], shifted(position));
+        }
+        for (edge, points) in &plain.edge_points {
+            let expected: Vec<_> = points.iter().map(|&point| shifted(point)).collect();
+            assert_eq!(result.edge_points[edge], expected);
+        }
+    }
+
+    #[test]
+    fn test_node_ranks_match_layers() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..5).map(|i| graph.add_node(i)).collect();
+        graph.add_edge(nodes[0], nodes[1], ());
+        graph.add_edge(nodes[1], nodes[2], ());
+        graph.add_edge(nodes[2], nodes[0], ());
+        graph.add_edge(nodes[0], nodes[3], ());
+        graph.add_edge(nodes[3], nodes[4], ());
+
+        let result = DagreLayout::new().compute(&graph).unwrap();
+        assert_eq!(result.node_ranks.len(), graph.node_count());
+        for (rank, layer) in result.layers.iter().enumerate() {
+            for node in layer {
+                assert_eq!(result.node_ranks[node], rank);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ranks_keep_edges_pointing_down() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..6).map(|i| graph.add_node(i)).collect();
+        let edges = [(0, 1), (1, 2), (2, 3), (0, 3), (4, 5), (5, 3)];
+        for (source, target) in edges {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+
+        for ranking in [
+            RankingAlgorithm::LongestPath,
+            RankingAlgorithm::TightTree,
+            RankingAlgorithm::NetworkSimplex,
+        ] {
+            let layout = DagreLayout::with_options(LayoutOptions {
+                ranking,
+                defer_ties: true,
+                width_penalty: 4.0,
+                ..Default::default()
+            });
+            let layers = layout.assign_layers_longest_path(&graph);
+            let rank = |node| layers.iter().position(|layer| layer.contains(&node));
+            for edge in graph.edge_references() {
+                assert!(rank(edge.source()) < rank(edge.target()));
+            }
+            assert_eq!(rank(nodes[3]), Some(3));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_round_trip() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        graph.add_edge(a, c, ());
+        graph.add_edge(c, a, ());
+
+        let options = LayoutOptions {
+            rank_dir: RankDir::LeftToRight,
+            node_sizes: HashMap::from([(b, (40.0, 20.0))]),
+            anchor_slots: true,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&options).unwrap();
+        assert!(json.contains("\"node_sizes\":{\"1\":[40.0,20.0]}"));
+        let restored: LayoutOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(format!("{restored:?}"), format!("{options:?}"));
+
+        let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+        let json = serde_json::to_string(&result).unwrap();
+        let restored: LayoutResult = serde_json::from_str(&json).unwrap();
+        assert!(restored.approx_eq(&result));
+        assert_eq!(restored.node_positions, result.node_positions);
+        assert_eq!(restored.edge_points, result.edge_points);
+        assert_eq!(restored.anchor_slots, result.anchor_slots);
+        assert_eq!(restored.reversed_edges, result.reversed_edges);
+        assert_eq!(restored.node_ranks, result.node_ranks);
+    }
+
+    #[test]
+    fn test_initial_order_seeds_layers() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let children: Vec<_> = (0..3).map(|_| graph.add_node("child")).collect();
+        for &child in &children {
+            graph.add_edge(root, child, ());
+        }
+        let grandchild = graph.add_node("grandchild");
+        graph.add_edge(children[0], grandchild, ());
+
+        let layout = |initial_order| {
+            DagreLayout::with_options(LayoutOptions {
+                initial_order,
+                ..Default::default()
+            })
+            .compute(&graph)
+            .unwrap()
+        };
+        let previous = HashMap::from([(children[2], 0), (children[0], 1), (children[1], 2)]);
+        assert_eq!(
+            layout(previous).layers,
+            vec![
+                vec![root],
+                vec![children[2], children[0], children[1]],
+                vec![grandchild]
+            ]
+        );
+
+        // Nodes without an entry stay in place while the others are rearranged
+        let partial = HashMap::from([(children[2], 0), (children[1], 1)]);
+        let result = layout(partial);
+        assert_eq!(result.layers[1].len(), 3);
+        let position = |node| result.layers[1].iter().position(|&n| n == node).unwrap();
+        assert!(position(children[2]) < position(children[1]));
+    }
+
+    #[test]
+    fn test_index_order_places_long_edges_by_source() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let c = graph.add_node("c");
+        let d = graph.add_node("d");
+        graph.add_edge(b, c, ());
+        graph.add_edge(c, d, ());
+        let long = graph.add_edge(a, d, ());
+
+        // Without sweeps the layers keep the order they start from
+        let layout = |index_order| {
+            DagreLayout::with_options(LayoutOptions {
+                index_order,
+                max_iterations: 0,
+                ..Default::default()
+            })
+            .compute(&graph)
+            .unwrap()
+        };
+        let bend_x = |result: &LayoutResult| result.edge_points[&long][1].0;
+
+        let appended = layout(false);
+        assert!(bend_x(&appended) > appended.node_positions[&c].0);
+
+        let result = layout(true);
+        assert_eq!(result.layers, vec![vec![a, b], vec![c], vec![d]]);
+        assert!(bend_x(&result) < result.node_positions[&c].0);
+    }
+
+    #[test]
+    fn test_max_width_wraps_wide_layers() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let children: Vec<_> = (0..10).map(|_| graph.add_node("child")).collect();
+        let edges: Vec<_> = children
+            .iter()
+            .map(|&child| graph.add_edge(root, child, ()))
+            .collect();
+
+        let options = LayoutOptions {
+            max_width: Some(200.0),
+            ..Default::default()
+        };
+        let centered = DagreLayout::with_options(options.clone());
+        let centered = centered.with_coordinate_assigner(CenteredAssigner);
+        for layout in [DagreLayout::with_options(options), centered] {
+            let result = layout.compute(&graph).unwrap();
+
+            // Five nodes 50 apart fit into 200, the other five wrap below them
+            assert_eq!(result.wrapped_ranks, [0, 1, 1]);
+            assert_eq!(result.layers.len(), 3);
+            assert_eq!(result.layers[1].len(), 5);
+            let y = |node: &NodeIndex| result.node_positions[node].1;
+            assert!(y(&result.layers[2][0]) > y(&result.layers[1][0]));
+            assert!(result.width <= 250.0 + 0.01);
+            assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+
+            // Edges into the second row run straight to its nodes
+            for (&child, edge) in children.iter().zip(&edges) {
+                let points = &result.edge_points[edge];
+                assert_eq!(points.len(), 2);
+                let target = result.node_positions[&child];
+                assert!((points[1].1 - target.1).abs() < 30.0);
+            }
+        }
+
+        // Layers that fit are left alone
+        let layout = DagreLayout::with_options(LayoutOptions {
+            max_width: Some(500.0),
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        assert!(result.wrapped_ranks.is_empty());
+        assert!(result.approx_eq(&DagreLayout::new().compute(&graph).unwrap()));
+    }
+
+    #[test]
+    fn test_center_parents_keeps_balanced_trees_symmetric() {
+        // Balanced binary tree with 8 leaves, children of node i at 2i+1 and 2i+2
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..15).map(|_| graph.add_node(())).collect();
+        for i in 0..7 {
+            graph.add_edge(nodes[i], nodes[2 * i + 1], ());
+            graph.add_edge(nodes[i], nodes[2 * i + 2], ());
+        }
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            center_parents: true,
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        let x = |i: usize| result.node_positions[&nodes[i]].0;
+        for i in 0..7 {
+            assert!((x(i) - (x(2 * i + 1) + x(2 * i + 2)) * 0.5).abs() < 1e-3);
+        }
+
+        // The root sits over the middle of its subtree, which mirrors around it
+        let leaves: Vec<f32> = (7..15).map(x).collect();
+        assert!((x(0) - (leaves[0] + leaves[7]) * 0.5).abs() < 1e-3);
+        for (rank, layer) in result.layers.iter().enumerate() {
+            assert_eq!(layer.len(), 1 << rank);
+            for (a, b) in layer.iter().zip(layer.iter().rev()) {
+                let (a, b) = (result.node_positions[a].0, result.node_positions[b].0);
+                assert!((a + b - 2.0 * x(0)).abs() < 1e-3);
+            }
+        }
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+    }
+
+    #[test]
+    fn test_time_budget_stops_crossing_reduction() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..8).map(|_| graph.add_node(())).collect();
+        for (source, target) in [(0, 7), (0, 5), (1, 4), (2, 6), (3, 4), (1, 7), (2, 5)] {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+
+        let layout = |max_iterations, time_budget| {
+            DagreLayout::with_options(LayoutOptions {
+                max_iterations,
+                time_budget,
+                ..Default::default()
+            })
+        };
+        let unsorted = layout(0, None).compute(&graph).unwrap();
+        let (spent, metrics) = layout(24, Some(Duration::ZERO))
+            .compute_with_metrics(&graph)
+            .unwrap();
+        assert_eq!(metrics.iterations, 0);
+        assert_eq!(spent.layers, unsorted.layers);
+
+        // A generous budget leaves the sweeps alone
+        let budget = Some(Duration::from_secs(60));
+        let result = layout(24, budget).compute(&graph).unwrap();
+        assert!(result.approx_eq(&layout(24, None).compute(&graph).unwrap()));
+        assert!(result.crossing_count < unsorted.crossing_count);
+    }
+
+    #[test]
+    fn test_edge_bundling_pulls_parallel_edges_together() {
+        // Four parallel edges, and a fan whose edges spread apart
+        let mut graph = Graph::new();
+        let sources: Vec<_> = (0..4).map(|_| graph.add_node(())).collect();
+        let targets: Vec<_> = (0..4).map(|_| graph.add_node(())).collect();
+        let parallel: Vec<_> = (0..4)
+            .map(|i| graph.add_edge(sources[i], targets[i], ()))
+            .collect();
+        let hub = graph.add_node(());
+        let fan: Vec<_> = (0..3)
+            .map(|_| {
+                let leaf = graph.add_node(());
+                graph.add_edge(hub, leaf, ())
+            })
+            .collect();
+
+        let layout = |edge_bundling| {
+            DagreLayout::with_options(LayoutOptions {
+                edge_bundling,
+                ..Default::default()
+            })
+            .with_coordinate_assigner(CenteredAssigner)
+            .compute(&graph)
+            .unwrap()
+        };
+        let plain = layout(None);
+        let bundled = layout(Some(1.0));
+        assert_eq!(bundled.node_positions, plain.node_positions);
+
+        // Edges running alongside the parallel ones join their bundle, the
+        // rest of the fan spreads away and stays straight
+        let slope = |edge: &EdgeIndex| {
+            let points = &plain.edge_points[edge];
+            (points[1].0 - points[0].0) / (points[1].1 - points[0].1)
+        };
+        let (bundle, apart): (Vec<_>, Vec<_>) = parallel
+            .iter()
+            .chain(&fan)
+            .partition(|edge| slope(edge) == slope(&parallel[0]));
+        assert!(parallel.iter().all(|edge| bundle.contains(&edge)));
+        assert!(!apart.is_empty());
+        for edge in apart {
+            assert_eq!(bundled.edge_points[edge], plain.edge_points[edge]);
+        }
+
+        // Full strength meets in one waypoint halfway between the ranks
+        let middles: Vec<f32> = bundle
+            .iter()
+            .map(|edge| (plain.edge_points[edge][0].0 + plain.edge_points[edge][1].0) * 0.5)
+            .collect();
+        let mean = middles.iter().sum::<f32>() / middles.len() as f32;
+        for edge in &bundle {
+            let (points, straight) = (&bundled.edge_points[edge], &plain.edge_points[edge]);
+            assert_eq!(points.len(), 3);
+            assert_eq!((points[0], points[2]), (straight[0], straight[1]));
+            assert!((points[1].0 - mean).abs() < 1e-3);
+            assert!((points[1].1 - (points[0].1 + points[2].1) * 0.5).abs() < 1e-3);
+        }
+
+        // Half strength stops halfway between the straight line and the waypoint
+        let half = layout(Some(0.5));
+        let expected = (middles[0] + mean) * 0.5;
+        assert!((half.edge_points[bundle[0]][1].0 - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_crossing_improvement_threshold() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..9).map(|_| graph.add_node(())).collect();
+        for (source, target) in [(3, 7), (3, 8), (4, 7), (4, 6), (5, 8), (2, 5)] {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+        let ranks = nodes.iter().map(|&node| (node, node.index() / 3)).collect();
+        let layout = |options| {
+            DagreLayout::with_options(options)
+                .layout_with_ranks(&graph, &ranks)
+                .unwrap()
+        };
+
+        // A second iteration removes the last crossing
+        let single = layout(LayoutOptions {
+            max_iterations: 1,
+            ..Default::default()
+        });
+        assert_eq!(single.crossing_count, 1);
+        assert_eq!(layout(LayoutOptions::default()).crossing_count, 0);
+        let strict = layout(LayoutOptions {
+            crossing_improvement_threshold: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(strict.crossing_count, 0);
+
+        // Iterations removing few crossings end the search
+        let lenient = layout(LayoutOptions {
+            crossing_improvement_threshold: Some(usize::MAX),
+            ..Default::default()
+        });
+        assert_eq!(lenient.layers, single.layers);
+    }
+
+    #[test]
+    fn test_reduce_crossings_keeps_best_order() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..12).map(|_| graph.add_node(())).collect();
+        for (source, target) in [(3, 4), (4, 8), (5, 9), (0, 6), (0, 7), (1, 4)] {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+        let ranks = nodes.iter().map(|&node| (node, node.index() / 4)).collect();
+        let layout = |max_iterations| {
+            DagreLayout::with_options(LayoutOptions {
+                max_iterations,
+                ..Default::default()
+            })
+            .layout_with_ranks(&graph, &ranks)
+            .unwrap()
+        };
+
+        // Later sweeps bring crossings back that an earlier one removed
+        let result = layout(24);
+        assert_eq!(result.crossing_count, 0);
+        for iterations in 1..24 {
+            assert!(result.crossing_count <= layout(iterations).crossing_count);
+        }
+    }
+
+    #[test]
+    fn test_node_order_breaks_ties() {
+        let names = ["root", "pear", "apple", "fig", "kiwi", "banana"];
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = names.iter().map(|&name| graph.add_node(name)).collect();
+        for &child in &nodes[1..4] {
+            graph.add_edge(nodes[0], child, ());
+        }
+
+        let labels = graph.clone();
+        let result = DagreLayout::new()
+            .with_node_order(move |a, b| labels[a].cmp(labels[b]))
+            .compute(&graph)
+            .unwrap();
+        let layers: Vec<Vec<&str>> = result
+            .layers
+            .iter()
+            .map(|layer| layer.iter().map(|&node| graph[node]).collect())
+            .collect();
+        assert_eq!(layers[0], ["banana", "kiwi", "root"]);
+        assert_eq!(layers[1], ["apple", "fig", "pear"]);
+    }
+
+    #[test]
+    fn test_barycenter_order_of_unconnected_nodes() {
+        let mut graph = Graph::new();
+        let parents: Vec<_> = (0..3).map(|_| graph.add_node(())).collect();
+        let children: Vec<_> = (0..60).map(|_| graph.add_node(())).collect();
+        for (i, &child) in children.iter().enumerate().step_by(7) {
+            graph.add_edge(parents[(i / 7) % 3], child, ());
+        }
+        let mut layers = vec![parents.clone(), children.iter().rev().copied().collect()];
+        let normalized = normalize(&graph, &mut layers, &HashSet::new());
+        let layout = DagreLayout::new();
+        let pins = RoutingPins::new(&[], &normalized, &layers, layout.options.node_sep);
+
+        // Unconnected nodes keep their slot as barycenter, found by searching the layer
+        let layer = &layers[1];
+        let mut expected: Vec<(f32, NodeIndex)> = layer
+            .iter()
+            .map(|&node| {
+                let parents: Vec<f32> = graph
+                    .neighbors_directed(node, Incoming)
+                    .map(|parent| parent.index() as f32)
+                    .collect();
+                let barycenter = match parents.is_empty() {
+                    true => layer.iter().position(|&n| n == node).unwrap() as f32,
+                    false => parents.iter().sum::<f32>() / parents.len() as f32,
+                };
+                (barycenter, node)
+            })
+            .collect();
+        expected.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        let expected: Vec<NodeIndex> = expected.into_iter().map(|(_, node)| node).collect();
+
+        let graph = &normalized.graph;
+        let adjacent = LayerPositions::of(&layers[0], graph.node_count());
+        let order = layout.order_by_barycenter(graph, layer, &adjacent, true, &pins);
+        assert_eq!(order, expected);
+    }
+
+    #[test]
+    fn test_longest_path_on_deep_chain() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..50_000).map(|_| graph.add_node(())).collect();
+        for pair in nodes.windows(2) {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+        // A shortcut and an edge back to the start must not shorten the chain
+        graph.add_edge(nodes[0], nodes[10], ());
+        graph.add_edge(nodes[49_999], nodes[0], ());
+
+        let layers = DagreLayout::new().assign_layers_longest_path(&graph);
+        assert_eq!(layers.len(), nodes.len());
+        for (layer, &node) in layers.iter().zip(&nodes) {
+            assert_eq!(layer, &[node]);
+        }
+    }
+
+    #[test]
+    fn test_longest_path_through_shared_subpaths() {
+        let mut graph = Graph::new();
+        let short = graph.add_node("short");
+        let long = graph.add_node("long");
+        let left = graph.add_node("left");
+        let right = graph.add_node("right");
+        let join = graph.add_node("join");
+        let tail = graph.add_node("tail");
+        graph.add_edge(short, join, ());
+        graph.add_edge(long, left, ());
+        graph.add_edge(long, right, ());
+        graph.add_edge(left, right, ());
+        graph.add_edge(left, join, ());
+        graph.add_edge(right, join, ());
+        graph.add_edge(join, tail, ());
+
+        let layers = DagreLayout::new().assign_layers_longest_path(&graph);
+        let rank = |node| layers.iter().position(|layer| layer.contains(&node));
+        assert_eq!(rank(right), Some(2));
+        assert_eq!(rank(join), Some(3));
+        assert_eq!(rank(tail), Some(4));
+    }
+
+    #[test]
+    fn test_monotone_long_edge_bend_points() {
+        let mut graph = Graph::new();
+        let s = graph.add_node("S");
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let t = graph.add_node("T");
+        graph.add_edge(s, a, ());
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, t, ());
+        let long = graph.add_edge(s, t, ());
+
+        let mut layers = vec![vec![s], vec![a], vec![b], vec![t]];
+        let normalized = normalize(&graph, &mut layers, &HashSet::new());
+        layers[1].reverse();
+        layers[2].reverse();
+        let (d1, d2) = (layers[1][0], layers[2][0]);
+
+        // The long edge swings out to the right and back
+        let mut positions = HashMap::from([
+            (s, (0.0, 0.0)),
+            (d1, (50.0, 100.0)),
+            (a, (150.0, 100.0)),
+            (d2, (100.0, 200.0)),
+            (b, (150.0, 200.0)),
+            (t, (0.0, 300.0)),
+        ]);
+
+        let options = LayoutOptions {
+            monotone: true,
+            ..Default::default()
+        };
+        let layout = DagreLayout::with_options(options);
+        layout.make_routes_monotone(&normalized, &layers, &mut positions);
+        let points = &layout.route_edges(&graph, &normalized, &positions)[&long];
+
+        assert_eq!(points.len(), 4);
+        let xs: Vec<f32> = points.iter().map(|p| p.0).collect();
+        assert!(
+            xs.windows(2).all(|w| w[0] >= w[1]) || xs.windows(2).all(|w| w[0] <= w[1]),
+            "bend points are not monotone: {:?}",
+            xs
+        );
+
+        // Layer neighbors keep their separation
+        assert!(positions[&a].0 - positions[&d1].0 >= layout.options.node_sep);
+        assert!(positions[&b].0 - positions[&d2].0 >= layout.options.node_sep);
+    }
+
+    #[test]
+    fn test_monotone_bend_points_clear_sized_neighbors() {
+        let mut graph = Graph::new();
+        let s = graph.add_node("S");
+        let a = graph.add_node("A");
+        let t = graph.add_node("T");
+        graph.add_edge(s, a, ());
+        graph.add_edge(a, t, ());
+        let long = graph.add_edge(s, t, ());
+
+        let mut layers = vec![vec![s], vec![a], vec![t]];
+        let normalized = normalize(&graph, &mut layers, &HashSet::new());
+        layers[1].reverse();
+        let dummy = layers[1][0];
+
+        // The bend point lies within reach of the edge but inside the wide node
+        let mut positions = HashMap::from([
+            (s, (0.0, 0.0)),
+            (dummy, (60.0, 100.0)),
+            (a, (100.0, 100.0)),
+            (t, (200.0, 200.0)),
+        ]);
+        let layout = DagreLayout::with_options(LayoutOptions {
+            monotone: true,
+            node_sizes: HashMap::from([(a, (100.0, 20.0))]),
+            ..Default::default()
+        });
+        layout.make_routes_monotone(&normalized, &layers, &mut positions);
+
+        let gap = layout.options.node_sep + 100.0 * 0.5;
+        assert!(positions[&a].0 - positions[&dummy].0 >= gap - 1e-3);
+        let points = &layout.route_edges(&graph, &normalized, &positions)[&long];
+        assert!(points.iter().all(|&(x, y)| y != 100.0 || x <= 100.0 - gap + 1e-3));
+    }
+
+    #[test]
+    fn test_diamond_shape_clips_edge_on_slanted_side() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let decision = graph.add_node("Decision");
+        let edge = graph.add_edge(a, decision, ());
+        graph.add_edge(b, decision, ());
+
+        let options = LayoutOptions {
+            node_sizes: HashMap::from([(decision, (40.0, 40.0))]),
+            node_shapes: HashMap::from([(decision, NodeShape::Diamond)]),
+            ..Default::default()
+        };
+        let layout = DagreLayout::with_options(options);
+        let result = layout.compute(&graph).unwrap();
+
+        let center = result.node_positions[&decision];
+        let end = *result.edge_points[&edge].last().unwrap();
+        let (dx, dy) = ((end.0 - center.0).abs(), (end.1 - center.1).abs());
+
+        // On the diamond outline, strictly inside the bounding box
+        assert!((dx / 20.0 + dy / 20.0 - 1.0).abs() < 1e-4);
+        assert!(dx < 20.0 && dy < 20.0);
+        assert!(dx > 0.0, "edge should arrive diagonally");
+        // Nodes without a size keep the edge at their center
+        assert_eq!(result.edge_points[&edge][0], result.node_positions[&a]);
+    }
+
+    #[test]
+    fn test_fixed_cross_axis_width_centers_layers() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        graph.add_edge(a, b, ());
+        graph.add_edge(a, c, ());
+
+        let options = LayoutOptions {
+            fixed_cross_axis_width: Some(500.0),
+            ..Default::default()
+        };
+        let layout = DagreLayout::with_options(options);
+        let result = layout.compute(&graph).unwrap();
+
+        let (xb, xc) = (result.node_positions[&b].0, result.node_positions[&c].0);
+        assert_eq!((xb - xc).abs(), 50.0);
+        assert_eq!((xb + xc) * 0.5, 250.0);
+        assert_eq!(result.node_positions[&a].0, 250.0);
+        assert_eq!(result.width, 500.0);
+    }
+
+    #[test]
+    fn test_allowed_rank_coordinates() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+
+        let options = LayoutOptions {
+            allowed_rank_coordinates: Some(vec![0.0, 100.0, 250.0]),
+            ..Default::default()
+        };
+        let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+
+        assert_eq!(result.node_positions[&a].1, 0.0);
+        assert_eq!(result.node_positions[&b].1, 100.0);
+        assert_eq!(result.node_positions[&c].1, 250.0);
+
+        let options = LayoutOptions {
+            allowed_rank_coordinates: Some(vec![0.0, 100.0]),
+            ..Default::default()
+        };
+        assert_eq!(
+            DagreLayout::with_options(options)
+                .compute(&graph)
+                .unwrap_err(),
+            LayoutError::TooManyRanks {
+                ranks: 3,
+                allowed: 2
+            }
+        );
+
+        // Rank coordinates of such layers continue below the allowed ones
+        let layers = vec![vec![a], vec![b], vec![c]];
+        let options = LayoutOptions {
+            allowed_rank_coordinates: Some(vec![0.0, 100.0]),
+            ..Default::default()
+        };
+        let coordinates = options.rank_coordinates(&layers);
+        assert_eq!(coordinates, [0.0, 100.0, 100.0 + options.rank_sep]);
+    }
+
+    #[test]
+    fn test_defer_ties_reduces_rank_span_variance() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..9).map(|i| graph.add_node(i)).collect();
+        let [a, b, c, d, e, x, y, z, w] = nodes[..] else {
+            unreachable!()
+        };
+
+        // Side branches hanging off the spine with successors further down
+        graph.add_edge(a, x, ());
+        graph.add_edge(x, d, ());
+        graph.add_edge(x, e, ());
+        graph.add_edge(b, y, ());
+        graph.add_edge(y, e, ());
+        graph.add_edge(y, w, ());
+        graph.add_edge(a, z, ());
+        graph.add_edge(z, c, ());
+        graph.add_edge(z, d, ());
+        graph.add_edge(z, w, ());
+        graph.add_edge(c, w, ());
+
+        // Spine a -> b -> c -> d -> e, added last so it is searched first
+        graph.add_edge(d, e, ());
+        graph.add_edge(c, d, ());
+        graph.add_edge(b, c, ());
+        graph.add_edge(a, b, ());
+
+        let span_variance = |defer_ties| {
+            let options = LayoutOptions {
+                defer_ties,
+                ..Default::default()
+            };
+            let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+            let rank: HashMap<_, _> = result
+                .layers
+                .iter()
+                .enumerate()
+                .flat_map(|(rank, layer)| layer.iter().map(move |&node| (node, rank as f32)))
+                .collect();
+            let spans: Vec<f32> = graph
+                .edge_indices()
+                .map(|edge| {
+                    let (source, target) = graph.edge_endpoints(edge).unwrap();
+                    rank[&target] - rank[&source]
+                })
+                .collect();
+            assert!(spans.iter().all(|&span| span >= 1.0));
+            let mean = spans.iter().sum::<f32>() / spans.len() as f32;
+            spans.iter().map(|span| (span - mean).powi(2)).sum::<f32>() / spans.len() as f32
+        };
+
+        assert!(span_variance(true) < span_variance(false));
+    }
+
+    #[test]
+    fn test_shorten_edges_pulls_nodes_toward_neighbors() {
+        let mut graph = Graph::new();
+        let chain: Vec<_> = (0..4).map(|i| graph.add_node(i)).collect();
+        for pair in chain.windows(2) {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+        let source = graph.add_node(4);
+        graph.add_edge(source, chain[3], ());
+        let fork = graph.add_node(5);
+        graph.add_edge(chain[0], fork, ());
+        graph.add_edge(fork, chain[2], ());
+        graph.add_edge(fork, chain[3], ());
+
+        let ranks = |shorten_edges| {
+            let layout = DagreLayout::with_options(LayoutOptions {
+                shorten_edges,
+                ..Default::default()
+            });
+            let layers = layout.assign_layers_longest_path(&graph);
+            let rank = |node| {
+                layers
+                    .iter()
+                    .position(|layer| layer.contains(&node))
+                    .unwrap()
+            };
+            let span: usize = graph
+                .edge_references()
+                .map(|edge| rank(edge.target()) - rank(edge.source()))
+                .sum();
+            (rank(source), rank(fork), span)
+        };
+        assert_eq!(ranks(false), (0, 1, 10));
+        assert_eq!(ranks(true), (2, 1, 8));
+    }
+
+    #[test]
+    fn test_width_penalty_trades_width_for_ranks() {
+        let mut graph = Graph::new();
+        let root = graph.add_node(0);
+        for i in 1..=12 {
+            let leaf = graph.add_node(i);
+            graph.add_edge(root, leaf, ());
+        }
+
+        let shape = |width_penalty| {
+            let options = LayoutOptions {
+                width_penalty,
+                ..Default::default()
+            };
+            let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+            let widest = result.layers.iter().map(|layer| layer.len()).max().unwrap();
+            (result.layers.len(), widest)
+        };
+
+        let (low_ranks, low_width) = shape(0.01);
+        let (high_ranks, high_width) = shape(1.0);
+        assert_eq!((low_ranks, low_width), (2, 12));
+        assert!(high_ranks > low_ranks);
+        assert!(high_width < low_width);
+    }
+
+    #[test]
+    fn test_routing_constraint_pulls_long_edge() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let c = graph.add_node("C");
+        let long = graph.add_edge(a, c, ());
+        for i in 0..4 {
+            let x = graph.add_node("X");
+            graph.add_edge(x, c, ());
+            graph.add_edge(a, x, ());
+            assert_eq!(x.index(), i + 2);
+        }
+
+        let bend_slot = |cross| {
+            let options = LayoutOptions {
+                routing_constraints: vec![RoutingConstraint {
+                    edge: long,
+                    rank: 1,
+                    cross,
+                }],
+                ..Default::default()
+            };
+            let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+            let bend = result.edge_points[&long][1].0;
+            let mut slots: Vec<f32> = result.layers[1]
+                .iter()
+                .map(|node| result.node_positions[node].0)
+                .collect();
+            slots.push(bend);
+            slots.sort_by(f32::total_cmp);
+            slots.iter().position(|&x| x == bend).unwrap()
+        };
+
+        // The layer has five slots from 0 to 200, the bend takes the closest one
+        assert_eq!(bend_slot(0.0), 0);
+        assert_eq!(bend_slot(200.0), 4);
+    }
+
+    #[test]
+    fn test_edges_only_routes_hand_placed_nodes() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let short = graph.add_edge(a, b, ());
+        let long = graph.add_edge(a, c, ());
+        graph.add_edge(b, c, ());
+
+        let positions = HashMap::from([(a, (0.0, 0.0)), (b, (150.0, 80.0)), (c, (40.0, 200.0))]);
+        let options = LayoutOptions {
+            node_sizes: HashMap::from([(b, (60.0, 40.0))]),
+            ..Default::default()
+        };
+        let edges = DagreLayout::with_options(options).edges_only(&graph, &positions);
+
+        assert_eq!(edges.len(), 3);
+        // The short edge ends on the left border of B, facing A
+        assert_eq!(edges[&short], vec![(0.0, 0.0), (120.0, 64.0)]);
+
+        // The long edge bends on B's rank, on the line between its endpoints
+        assert_eq!(edges[&long], vec![(0.0, 0.0), (16.0, 80.0), (40.0, 200.0)]);
+    }
+
+    #[test]
+    fn test_compact_ranks_removes_dummy_only_rank() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let ab = graph.add_edge(a, b, ());
+        let bc = graph.add_edge(b, c, ());
+        let layers = || vec![vec![a], vec![], vec![b], vec![c]];
+
+        // Without compaction the edge into the empty rank bends there
+        let layout = DagreLayout::new();
+        let result = layout.layout_layers(&graph, layers()).unwrap();
+        assert_eq!(result.layers.len(), 4);
+        assert_eq!(result.edge_points[&ab].len(), 3);
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            compact_ranks: true,
+            ..Default::default()
+        });
+        let result = layout.layout_layers(&graph, layers()).unwrap();
+        assert_eq!(result.layers, vec![vec![a], vec![b], vec![c]]);
+        assert_eq!(result.height, 300.0);
+        for edge in [ab, bc] {
+            let (source, target) = graph.edge_endpoints(edge).unwrap();
+            let points = &result.edge_points[&edge];
+            assert_eq!(
+                *points,
+                vec![
+                    result.node_positions[&source],
+                    result.node_positions[&target]
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_pack_cross_axis_shrinks_width() {
+        /// Leaves four times `node_sep` between the nodes of a layer
+        struct Spread;
+
+        impl CoordinateAssigner for Spread {
+            fn assign(
+                &self,
+                _graph: &DiGraph<NodeKind, EdgeIndex>,
+                layers: &[Vec<NodeIndex>],
+                options: &LayoutOptions,
+            ) -> (HashMap<NodeIndex, (f32, f32)>, f32, f32) {
+                let gap = options.node_sep * 4.0;
+                let ranks = options.rank_coordinates(layers);
+                let mut positions = HashMap::new();
+                for (layer, rank) in layers.iter().zip(ranks.clone()) {
+                    for (order, &node) in layer.iter().enumerate() {
+                        positions.insert(node, (order as f32 * gap, rank));
+                    }
+                }
+                let widest = layers.iter().map(Vec::len).max().unwrap_or(1);
+                let width = (widest - 1) as f32 * gap + options.node_sep;
+                (positions, width, options.rank_extent(&ranks))
             }
         }
+
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let left = graph.add_node("left");
+        let right = graph.add_node("right");
+        graph.add_edge(root, left, ());
+        graph.add_edge(root, right, ());
+
+        let layout = |pack_cross_axis| {
+            DagreLayout::with_options(LayoutOptions {
+                pack_cross_axis,
+                ..Default::default()
+            })
+            .with_coordinate_assigner(Spread)
+        };
+        let plain = layout(false).compute(&graph).unwrap();
+        let packed = layout(true).compute(&graph).unwrap();
+        assert_eq!(layout(true).validate_layout(&graph, &packed, 0.01), Ok(()));
+        assert_eq!(plain.width, 250.0);
+        assert_eq!(packed.width, 100.0);
+        assert_eq!(packed.height, plain.height);
+
+        // The siblings close up under their parent
+        let x = |node| packed.node_positions[&node].0;
+        assert_eq!((x(left) - x(right)).abs(), 50.0);
+        assert!((x(root) - (x(left) + x(right)) * 0.5).abs() < 1.0);
     }
 
-    /// Order nodes in a layer based on barycenter of connected nodes in adjacent layer
-    fn order_by_barycenter<N, E>(
-        &self,
-        graph: &DiGraph<N, E>,
-        layer: &[NodeIndex],
-        adjacent_layer: &[NodeIndex],
-        use_predecessors: bool,
-    ) -> Vec<NodeIndex> {
-        // Create position map for adjacent layer
-        let positions: HashMap<NodeIndex, usize> = adjacent_layer
-            .iter()
-            .enumerate()
-            .map(|(pos, &node)| (node, pos))
+    #[test]
+    fn test_straighten_long_edges() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let leaf = graph.add_node("leaf");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let end = graph.add_node("end");
+        graph.add_edge(root, leaf, ());
+        graph.add_edge(root, b, ());
+        graph.add_edge(b, c, ());
+        graph.add_edge(c, end, ());
+        let long = graph.add_edge(root, end, ());
+
+        let bends = |straighten_long_edges| {
+            let layout = DagreLayout::with_options(LayoutOptions {
+                straighten_long_edges,
+                ..Default::default()
+            })
+            .with_coordinate_assigner(CenteredAssigner);
+            let result = layout.compute(&graph).unwrap();
+            assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+            let points = &result.edge_points[&long];
+            assert_eq!(points.len(), 4);
+            (points[1].0, points[2].0)
+        };
+
+        // Layers of three and two nodes put the bends on different grids
+        let (first, second) = bends(false);
+        assert_ne!(first, second);
+        let (first, second) = bends(true);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_pull_weak_nodes_toward_hub() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let children: Vec<_> = (0..5)
+            .map(|_| {
+                let child = graph.add_node("child");
+                graph.add_edge(root, child, ());
+                child
+            })
             .collect();
+        let weak = graph.add_node("weak");
+        let hub = children[4];
+        graph.add_edge(weak, hub, ());
 
-        // Calculate barycenter for each node in current layer
-        let mut node_barycenters: Vec<(NodeIndex, f32)> = layer
-            .iter()
-            .map(|&node| {
-                let connected_positions: Vec<usize> = if use_predecessors {
-                    graph
-                        .neighbors_directed(node, Incoming)
-                        .filter_map(|pred| positions.get(&pred))
-                        .copied()
-                        .collect()
-                } else {
-                    graph
-                        .neighbors_directed(node, Outgoing)
-                        .filter_map(|succ| positions.get(&succ))
-                        .copied()
-                        .collect()
-                };
+        let distance = |pull_weak_nodes| {
+            let options = LayoutOptions {
+                pull_weak_nodes,
+                ..Default::default()
+            };
+            let layout = DagreLayout::with_options(options);
+            let result = layout
+                .with_coordinate_assigner(CenteredAssigner)
+                .compute(&graph)
+                .unwrap();
+            let x = |node| result.node_positions[&node].0;
+            assert!((x(weak) - x(root)).abs() >= 50.0);
+            (x(weak) - x(hub)).abs()
+        };
 
-                let barycenter = if connected_positions.is_empty() {
-                    // No connections, maintain relative position
-                    layer.iter().position(|&n| n == node).unwrap() as f32
-                } else {
-                    connected_positions.iter().sum::<usize>() as f32
-                        / connected_positions.len() as f32
-                };
+        assert!(distance(true) < distance(false));
+        assert_eq!(distance(true), 0.0);
+    }
 
-                (node, barycenter)
+    #[test]
+    fn test_pull_weak_nodes_keep_sized_neighbors_apart() {
+        // The weak node wants to sit above the hub, right next to the wide root
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let children: Vec<_> = (0..3)
+            .map(|_| {
+                let child = graph.add_node("child");
+                graph.add_edge(root, child, ());
+                child
             })
             .collect();
+        let weak = graph.add_node("weak");
+        graph.add_edge(weak, children[2], ());
 
-        // Sort by barycenter, maintaining stable order for ties
-        node_barycenters.sort_by(|a, b| {
-            a.1.partial_cmp(&b.1)
-                .unwrap_or(std::cmp::Ordering::Equal)
-                .then_with(|| a.0.cmp(&b.0))
+        let layout = DagreLayout::with_options(LayoutOptions {
+            pull_weak_nodes: true,
+            node_sizes: HashMap::from([(root, (300.0, 20.0)), (weak, (100.0, 20.0))]),
+            ..Default::default()
         });
-
-        node_barycenters.into_iter().map(|(node, _)| node).collect()
+        let result = layout.compute(&graph).unwrap();
+        let x = |node| result.node_positions[&node].0;
+        assert!((x(weak) - x(root)).abs() >= 50.0 + (300.0 + 100.0) * 0.5 - 1e-3);
     }
 
-    /// Assign final coordinates to nodes with proper spacing
-    fn assign_coordinates(
-        &self,
-        layers: &[Vec<NodeIndex>],
-    ) -> (HashMap<NodeIndex, (f32, f32)>, f32, f32) {
-        let mut positions = HashMap::new();
-        let LayoutOptions {
-            rank_dir,
-            node_sep,
-            rank_sep,
-            ..
-        } = &self.options;
-
-        let max_layer_width = layers.iter().map(|layer| layer.len()).max().unwrap_or(0) as f32;
-
-        for (layer_idx, layer) in layers.iter().enumerate() {
-            let layer_width = layer.len() as f32;
-
-            // Center the layer
-            let start_offset = (max_layer_width - layer_width) * node_sep * 0.5;
-
-            for (node_idx, &node) in layer.iter().enumerate() {
-                let (x, y) = match rank_dir {
-                    RankDir::TopToBottom => (
-                        start_offset + node_idx as f32 * node_sep,
-                        layer_idx as f32 * rank_sep,
-                    ),
-                    RankDir::LeftToRight => (
-                        layer_idx as f32 * rank_sep,
-                        start_offset + node_idx as f32 * node_sep,
-                    ),
-                };
-                positions.insert(node, (x, y));
-            }
+    #[test]
+    fn test_fast_options_produce_valid_layout() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..60).map(|i| graph.add_node(i)).collect();
+        for i in 0..50 {
+            graph.add_edge(nodes[i], nodes[i + 10], ());
+            graph.add_edge(nodes[i], nodes[(i * 7 + 13) % 50 + 10], ());
         }
 
-        // Calculate total dimensions
-        let (width, height) = match rank_dir {
-            RankDir::TopToBottom => (max_layer_width * node_sep, layers.len() as f32 * rank_sep),
-            RankDir::LeftToRight => (layers.len() as f32 * rank_sep, max_layer_width * node_sep),
-        };
+        let result = DagreLayout::with_options(LayoutOptions::fast())
+            .compute(&graph)
+            .unwrap();
 
-        (positions, width, height)
+        assert_eq!(result.node_positions.len(), graph.node_count());
+        assert_eq!(result.edge_points.len(), graph.edge_count());
+        let rank: HashMap<_, _> = result
+            .layers
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, layer)| layer.iter().map(move |&node| (node, rank)))
+            .collect();
+        assert_eq!(rank.len(), graph.node_count());
+        for layer in &result.layers {
+            let mut xs: Vec<f32> = layer.iter().map(|n| result.node_positions[n].0).collect();
+            xs.sort_by(f32::total_cmp);
+            assert!(xs.windows(2).all(|w| w[1] - w[0] >= 50.0));
+        }
+        assert!(result.width > 0.0 && result.height > 0.0);
     }
-}
 
-impl Default for DagreLayout {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_fast_options_skip_transpose_for_weighted_crossings() {
+        // Small graph whose order the neighbor swaps after each sweep change
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..6).map(|i| graph.add_node(i)).collect();
+        for (source, target) in [(4, 5), (4, 5), (0, 3), (0, 2), (2, 5)] {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+
+        let fast = LayoutOptions {
+            weight_crossings_by_span: true,
+            ..LayoutOptions::fast()
+        };
+        assert!(fast.skip_transpose);
+        let transposed = LayoutOptions {
+            skip_transpose: false,
+            ..fast.clone()
+        };
+        let layers = |options| DagreLayout::with_options(options).compute(&graph).unwrap().layers;
+        assert_ne!(layers(fast), layers(transposed));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use petgraph::Graph;
+    #[test]
+    fn test_highway_edge_routed_along_margin() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..6).map(|i| graph.add_node(i)).collect();
+        let skip = graph.add_edge(nodes[0], nodes[5], ());
+        for pair in nodes.windows(2).rev() {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+
+        let options = LayoutOptions {
+            highway_span: Some(3),
+            ..Default::default()
+        };
+        let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+
+        assert_eq!(result.layers.len(), 6);
+        assert_eq!(result.highway_edges, HashSet::from([skip]));
+        let rightmost = result
+            .node_positions
+            .values()
+            .map(|p| p.0)
+            .fold(f32::MIN, f32::max);
+        let points = &result.edge_points[&skip];
+        assert_eq!(points.len(), 6);
+        assert_eq!(points[0], result.node_positions[&nodes[0]]);
+        assert_eq!(points[5], result.node_positions[&nodes[5]]);
+        // The lane runs beyond every node, inside the layout bounds
+        assert_eq!(points[2].0, points[3].0);
+        assert!(points[2].0 >= rightmost + 50.0);
+        assert!(points[2].0 <= result.width);
+        // Short edges are not affected
+        for edge in graph.edge_indices().filter(|&edge| edge != skip) {
+            assert_eq!(result.edge_points[&edge].len(), 2);
+        }
+    }
 
     #[test]
-    fn test_simple_chain() {
+    fn test_cycle_start_is_deterministic() {
         let mut graph = Graph::new();
         let a = graph.add_node("A");
         let b = graph.add_node("B");
         let c = graph.add_node("C");
-
         graph.add_edge(a, b, ());
         graph.add_edge(b, c, ());
+        graph.add_edge(c, a, ());
 
-        let layout = DagreLayout::new();
-        let result = layout.compute(&graph);
+        for _ in 0..10 {
+            let result = DagreLayout::new().compute(&graph).unwrap();
+            assert_eq!(result.layers, vec![vec![a], vec![b], vec![c]]);
+        }
+
+        // A chord from C makes it the node with the most outgoing edges
+        graph.add_edge(c, b, ());
+        let options = LayoutOptions {
+            cycle_start: CycleStart::HighestOutDegree,
+            ..Default::default()
+        };
+        let layout = DagreLayout::with_options(options);
+        let first = layout.compute(&graph).unwrap().layers;
+        assert_eq!(first[0], vec![c]);
+        for _ in 0..10 {
+            assert_eq!(layout.compute(&graph).unwrap().layers, first);
+        }
+    }
+
+    #[test]
+    fn test_layer_widths_of_diamond() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        graph.add_edge(a, b, ());
+        graph.add_edge(a, c, ());
+        graph.add_edge(b, d, ());
+        graph.add_edge(c, d, ());
+
+        let widths = DagreLayout::new().layer_widths(&graph);
+        assert_eq!(widths, vec![50.0, 100.0, 50.0]);
+
+        let options = LayoutOptions {
+            node_sizes: HashMap::from([(b, (120.0, 30.0))]),
+            ..Default::default()
+        };
+        let widths = DagreLayout::with_options(options).layer_widths(&graph);
+        assert_eq!(widths, vec![50.0, 170.0, 50.0]);
+    }
+
+    #[test]
+    fn test_independent_chains_share_ranks() {
+        let mut graph = Graph::new();
+        let long: Vec<_> = (0..3).map(|i| graph.add_node(i)).collect();
+        let short: Vec<_> = (3..5).map(|i| graph.add_node(i)).collect();
+        graph.add_edge(long[0], long[1], ());
+        graph.add_edge(long[1], long[2], ());
+        graph.add_edge(short[0], short[1], ());
+
+        let result = DagreLayout::new().compute(&graph).unwrap();
 
         assert_eq!(result.layers.len(), 3);
-        assert_eq!(result.layers[0], vec![a]);
-        assert_eq!(result.layers[1], vec![b]);
-        assert_eq!(result.layers[2], vec![c]);
+        assert_eq!(result.height, 300.0);
+        // Each chain keeps its own order from rank to rank
+        let y = |node| result.node_positions[&node].1;
+        assert!(long.windows(2).all(|pair| y(pair[0]) < y(pair[1])));
+        assert!(y(short[0]) < y(short[1]));
+        assert_eq!(y(short[0]), y(long[0]));
     }
 
     #[test]
-    fn test_diamond_pattern() {
+    fn test_align_spine_straightens_diamond() {
         let mut graph = Graph::new();
         let start = graph.add_node("start");
         let left = graph.add_node("left");
         let right = graph.add_node("right");
         let end = graph.add_node("end");
-
         graph.add_edge(start, left, ());
         graph.add_edge(start, right, ());
         graph.add_edge(left, end, ());
         graph.add_edge(right, end, ());
+        // Shares the first layer with the start node, pushing it off center
+        graph.add_node("isolated");
 
-        let layout = DagreLayout::new();
-        let result = layout.compute(&graph);
+        let x = |options: LayoutOptions| {
+            let layout = DagreLayout::with_options(options);
+            let result = layout
+                .with_coordinate_assigner(CenteredAssigner)
+                .compute(&graph)
+                .unwrap();
+            (
+                result.node_positions[&start].0,
+                result.node_positions[&end].0,
+            )
+        };
+        let (start_x, end_x) = x(LayoutOptions::default());
+        assert_ne!(start_x, end_x);
 
-        assert_eq!(result.layers.len(), 3);
-        assert_eq!(result.layers[0], vec![start]);
-        assert_eq!(result.layers[1].len(), 2);
-        assert!(result.layers[1].contains(&left));
-        assert!(result.layers[1].contains(&right));
-        assert_eq!(result.layers[2], vec![end]);
+        let (start_x, end_x) = x(LayoutOptions {
+            align_spine: true,
+            ..Default::default()
+        });
+        assert_eq!(start_x, end_x);
     }
 
     #[test]
-    fn test_left_to_right_layout() {
+    fn test_align_spine_keeps_sized_neighbors_apart() {
+        // The wide neighbor of the start node leaves it less room than node_sep
         let mut graph = Graph::new();
-        let a = graph.add_node("A");
-        let b = graph.add_node("B");
-        graph.add_edge(a, b, ());
+        let start = graph.add_node("start");
+        let left = graph.add_node("left");
+        let right = graph.add_node("right");
+        let end = graph.add_node("end");
+        graph.add_edge(start, left, ());
+        graph.add_edge(start, right, ());
+        graph.add_edge(left, end, ());
+        graph.add_edge(right, end, ());
+        let wide = graph.add_node("wide");
 
-        let options = LayoutOptions {
-            rank_dir: RankDir::LeftToRight,
+        let layout = DagreLayout::with_options(LayoutOptions {
+            align_spine: true,
+            node_sizes: HashMap::from([(wide, (400.0, 20.0)), (start, (60.0, 20.0))]),
             ..Default::default()
-        };
-        let layout = DagreLayout::with_options(options);
-        let result = layout.compute(&graph);
-
-        let pos_a = result.node_positions[&a];
-        let pos_b = result.node_positions[&b];
-
-        // In left-to-right layout, B should be to the right of A
-        assert!(pos_b.0 > pos_a.0);
+        });
+        let result = layout.compute(&graph).unwrap();
+        let x = |node| result.node_positions[&node].0;
+        assert!((x(start) - x(wide)).abs() >= 50.0 + (400.0 + 60.0) * 0.5 - 1e-3);
     }
 
     #[test]
-    fn test_empty_graph() {
-        let graph: Graph<&str, (), petgraph::Directed> = Graph::new();
-        let layout = DagreLayout::new();
-        let result = layout.compute(&graph);
+    fn test_barycenter_follows_heavy_edges() {
+        let mut graph = Graph::new();
+        let left = graph.add_node("left");
+        let middle = graph.add_node("middle");
+        let right = graph.add_node("right");
+        let below_middle = graph.add_node("below middle");
+        let split = graph.add_node("split");
+        graph.add_edge(middle, below_middle, ());
+        let heavy = graph.add_edge(left, split, ());
+        graph.add_edge(right, split, ());
 
-        assert!(result.layers.is_empty());
-        assert!(result.node_positions.is_empty());
+        let mut layers = vec![vec![left, middle, right], vec![below_middle, split]];
+        let normalized = normalize(&graph, &mut layers, &HashSet::new());
+        let pins = RoutingPins::new(&[], &normalized, &layers, 50.0);
+        let adjacent = LayerPositions::of(&layers[0], normalized.graph.node_count());
+        let order = |options: LayoutOptions| {
+            DagreLayout::with_options(options).order_by_barycenter(
+                &normalized.graph,
+                &layers[1],
+                &adjacent,
+                true,
+                &pins,
+            )
+        };
+
+        // Unweighted, both nodes sit below the middle and keep index order
+        assert_eq!(order(LayoutOptions::default()), vec![below_middle, split]);
+        let options = LayoutOptions {
+            edge_weights: HashMap::from([(heavy, 5.0)]),
+            ..Default::default()
+        };
+        assert_eq!(order(options), vec![split, below_middle]);
     }
 
     #[test]
-    fn test_single_node() {
-        let mut graph: Graph<&str, (), petgraph::Directed> = Graph::new();
-        let node = graph.add_node("single");
+    fn test_median_heuristic_on_wide_crossing_graph() {
+        // Each node links a third and two thirds of the way along the next layer
+        let (width, depth) = (15, 6);
+        let mut graph = Graph::new();
+        let layers: Vec<Vec<NodeIndex>> = (0..depth)
+            .map(|_| (0..width).map(|_| graph.add_node(())).collect())
+            .collect();
+        for pair in layers.windows(2) {
+            for (i, &source) in pair[0].iter().enumerate() {
+                graph.add_edge(source, pair[1][(i + width / 3) % width], ());
+                graph.add_edge(source, pair[1][(i + 2 * width / 3) % width], ());
+            }
+        }
+        let ranks: HashMap<NodeIndex, usize> = layers
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, layer)| layer.iter().map(move |&node| (node, rank)))
+            .collect();
+        let crossings = |crossing_heuristic| {
+            DagreLayout::with_options(LayoutOptions {
+                crossing_heuristic,
+                ..Default::default()
+            })
+            .layout_with_ranks(&graph, &ranks)
+            .unwrap()
+            .crossing_count
+        };
+        assert!(crossings(CrossingHeuristic::Median) <= crossings(CrossingHeuristic::Barycenter));
 
+        // A single far neighbor drags the barycenter but not the median
+        let mut graph = Graph::new();
+        let upper: Vec<_> = (0..6).map(|_| graph.add_node("upper")).collect();
+        let spread = graph.add_node("spread");
+        let near = graph.add_node("near");
+        for &source in &upper[..2] {
+            graph.add_edge(source, spread, ());
+        }
+        graph.add_edge(upper[5], spread, ());
+        graph.add_edge(upper[1], near, ());
+        graph.add_edge(upper[2], near, ());
+        let mut layers = vec![upper, vec![spread, near]];
+        let normalized = normalize(&graph, &mut layers, &HashSet::new());
+        let pins = RoutingPins::new(&[], &normalized, &layers, 50.0);
         let layout = DagreLayout::new();
-        let result = layout.compute(&graph);
+        let graph = &normalized.graph;
+        let adjacent = LayerPositions::of(&layers[0], graph.node_count());
+        let barycenter = layout.order_by_barycenter(graph, &layers[1], &adjacent, true, &pins);
+        let median = layout.order_by_median(graph, &layers[1], &adjacent, true, &pins);
+        assert_eq!(barycenter, vec![near, spread]);
+        assert_eq!(median, vec![spread, near]);
+    }
 
-        assert_eq!(result.layers.len(), 1);
-        assert_eq!(result.layers[0], vec![node]);
-        assert_eq!(result.node_positions[&node], (0.0, 0.0));
+    #[test]
+    fn test_seed_varies_tie_order_reproducibly() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let leaves: Vec<_> = (0..6)
+            .map(|_| {
+                let leaf = graph.add_node("leaf");
+                graph.add_edge(root, leaf, ());
+                leaf
+            })
+            .collect();
+        let layout = |seed| {
+            DagreLayout::with_options(LayoutOptions {
+                seed,
+                ..Default::default()
+            })
+            .compute(&graph)
+            .unwrap()
+        };
+
+        assert_eq!(layout(None).layers[1], leaves);
+        let seeded = layout(Some(7));
+        assert!(seeded.approx_eq(&layout(Some(7))));
+        let orders: HashSet<Vec<NodeIndex>> = (0..4)
+            .map(|seed| layout(Some(seed)).layers[1].clone())
+            .collect();
+        assert!(orders.len() > 1);
     }
 
     #[test]
-    fn test_layer_ordering_properties() {
-        // Test that edges only go from lower layer indices to higher layer indices
+    fn test_reversed_rank_directions() {
         let mut graph = Graph::new();
         let a = graph.add_node("A");
         let b = graph.add_node("B");
         let c = graph.add_node("C");
-        let d = graph.add_node("D");
-
-        graph.add_edge(a, b, ());
+        let ab = graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
         graph.add_edge(a, c, ());
-        graph.add_edge(b, d, ());
-        graph.add_edge(c, d, ());
-
-        let layout = DagreLayout::new();
-        let result = layout.compute(&graph);
 
-        // Create a mapping from node to layer index
-        let mut node_to_layer = std::collections::HashMap::new();
-        for (layer_idx, layer) in result.layers.iter().enumerate() {
-            for &node in layer {
-                node_to_layer.insert(node, layer_idx);
-            }
-        }
+        let layout = |rank_dir| {
+            let layout = DagreLayout::with_options(LayoutOptions {
+                rank_dir,
+                ..Default::default()
+            });
+            let result = layout.compute(&graph).unwrap();
+            assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+            result
+        };
+        let down = layout(RankDir::TopToBottom);
+        let up = layout(RankDir::BottomToTop);
+        assert!(up.node_positions[&a].1 > up.node_positions[&b].1);
+        assert!(up.node_positions[&b].1 > up.node_positions[&c].1);
+        assert_eq!(up.node_positions[&a], (down.node_positions[&a].0, 200.0));
+        assert_eq!(up.node_positions[&c].1, 0.0);
+        assert_eq!((up.width, up.height), (down.width, down.height));
+        assert_eq!(up.edge_points[&ab][0], up.node_positions[&a]);
 
-        // Verify all edges respect layer ordering
-        for edge in graph.edge_indices() {
-            if let Some((source, target)) = graph.edge_endpoints(edge) {
-                let source_layer = node_to_layer[&source];
-                let target_layer = node_to_layer[&target];
-                assert!(
-                    source_layer < target_layer,
-                    "Edge from layer {} to layer {} violates hierarchy",
-                    source_layer,
-                    target_layer
-                );
-            }
-        }
+        let left = layout(RankDir::RightToLeft);
+        assert!(left.node_positions[&a].0 > left.node_positions[&b].0);
+        assert_eq!(left.node_positions[&c].0, 0.0);
+        assert!(left.width > left.height);
     }
 
     #[test]
-    fn test_coordinate_consistency() {
+    fn test_node_sizes_space_layers_and_ranks() {
         let mut graph = Graph::new();
-        let a = graph.add_node("A");
-        let b = graph.add_node("B");
-        let c = graph.add_node("C");
-
-        graph.add_edge(a, b, ());
-        graph.add_edge(b, c, ());
-
-        let layout = DagreLayout::new();
-        let result = layout.compute(&graph);
+        let top = graph.add_node("top");
+        let wide = graph.add_node("wide");
+        let narrow = graph.add_node("narrow");
+        graph.add_edge(top, wide, ());
+        graph.add_edge(top, narrow, ());
 
-        // In top-to-bottom layout, Y coordinates should increase with layer depth
-        let pos_a = result.node_positions[&a];
-        let pos_b = result.node_positions[&b];
-        let pos_c = result.node_positions[&c];
+        let options = LayoutOptions {
+            node_sizes: HashMap::from([
+                (top, (40.0, 40.0)),
+                (wide, (100.0, 80.0)),
+                (narrow, (60.0, 20.0)),
+            ]),
+            ..Default::default()
+        };
+        let layout = DagreLayout::with_options(options);
+        let result = layout.compute(&graph).unwrap();
+        let position = |node| result.node_positions[&node];
 
-        assert!(pos_a.1 < pos_b.1, "A should be above B");
-        assert!(pos_b.1 < pos_c.1, "B should be above C");
+        // node_sep plus half of each neighbor's width
+        assert_eq!((position(wide).0 - position(narrow).0).abs(), 130.0);
+        // rank_sep plus half of the tallest node on each rank
+        assert_eq!(position(top).1, 0.0);
+        assert_eq!(position(wide).1, 20.0 + 100.0 + 40.0);
+        assert_eq!(position(narrow).1, position(wide).1);
+        assert_eq!(result.width, 260.0);
+        assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
     }
 
     #[test]
@@ -498,7 +4081,7 @@ mod tests {
             ..Default::default()
         };
         let layout = DagreLayout::with_options(options);
-        let result = layout.compute(&graph);
+        let result = layout.compute(&graph).unwrap();
 
         let pos_a = result.node_positions[&a];
         let pos_b = result.node_positions[&b];
@@ -528,7 +4111,7 @@ mod tests {
         graph.add_edge(c, d, ());
 
         let layout = DagreLayout::new();
-        let result = layout.compute(&graph);
+        let result = layout.compute(&graph).unwrap();
 
         // Should handle disconnected components gracefully
         assert!(result.layers.len() >= 2);
@@ -554,8 +4137,8 @@ mod tests {
         let layout = DagreLayout::new();
         
         // Run layout multiple times and verify results are identical
-        let result1 = layout.compute(&graph);
-        let result2 = layout.compute(&graph);
+        let result1 = layout.compute(&graph).unwrap();
+        let result2 = layout.compute(&graph).unwrap();
 
         assert_eq!(result1.layers, result2.layers);
         assert_eq!(result1.node_positions, result2.node_positions);
@@ -563,6 +4146,34 @@ mod tests {
         assert_eq!(result1.height, result2.height);
     }
 
+    #[test]
+    fn test_layers_start_in_index_order() {
+        // Many ties on the middle ranks, where hash order would show first
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..30).map(|_| graph.add_node(())).collect();
+        for i in 1..20 {
+            graph.add_edge(nodes[0], nodes[i], ());
+            graph.add_edge(nodes[i], nodes[20 + i % 10], ());
+        }
+        graph.add_edge(nodes[29], nodes[0], ());
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            max_iterations: 0,
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        for layer in &result.layers {
+            assert!(layer.is_sorted());
+        }
+
+        // Fresh runs, each with its own hash maps, agree with and without sweeps
+        let swept = DagreLayout::new().compute(&graph).unwrap();
+        for _ in 0..8 {
+            assert_eq!(layout.compute(&graph).unwrap().layers, result.layers);
+            assert_eq!(DagreLayout::new().compute(&graph).unwrap().layers, swept.layers);
+        }
+    }
+
     #[test]
     fn test_crossing_reduction_effectiveness() {
         // Create a graph that should have crossings if not optimized
@@ -580,7 +4191,7 @@ mod tests {
         graph.add_edge(left2, right1, ());
 
         let layout = DagreLayout::new();
-        let result = layout.compute(&graph);
+        let result = layout.compute(&graph).unwrap();
 
         // After crossing reduction, nodes should be reordered to minimize crossings
         assert_eq!(result.layers.len(), 3);
@@ -608,7 +4219,7 @@ mod tests {
         graph.add_edge(c, d, ());
 
         let layout = DagreLayout::new();
-        let result = layout.compute(&graph);
+        let result = layout.compute(&graph).unwrap();
 
         // Should have 3 layers: {A}, {B, C}, {D}
         assert_eq!(result.layers.len(), 3);
@@ -1,5 +1,54 @@
+pub mod acyclic;
+pub mod anchors;
+pub mod animation;
+mod bidirectional;
+mod brandes_kopf;
+pub mod chains;
+pub mod components;
+pub mod constraints;
+pub mod coordinates;
+mod crossing;
+mod derived;
+pub mod double;
+pub mod error;
+mod geometry;
+mod hybrid;
+pub mod incremental;
+mod labels;
 pub mod layout;
+pub mod metrics;
+mod normalize;
+#[cfg(feature = "dot")]
+pub mod parse;
+mod ports;
+pub mod ranking;
+#[cfg(any(feature = "svg", feature = "dot"))]
+pub mod render;
+pub mod routing;
+pub mod shape;
+mod stability;
+mod undirected;
+pub mod validate;
+
+pub use acyclic::{CycleRemoval, greedy_fas};
+pub use anchors::AnchorSlots;
+pub use animation::{AnimationEvent, AnimationItem};
+pub use chains::{CollapsedGraph, collapse_chains};
+pub use components::{ComponentPacking, PackedComponent};
+pub use constraints::{FlatEdge, RankConflict};
+pub use coordinates::{BrandesKopfAssigner, CenteredAssigner, CoordinateAssigner};
+pub use double::LayoutResultF64;
+pub use error::LayoutError;
+pub use incremental::IncrementalLayout;
 pub use layout::*;
+pub use metrics::LayoutMetrics;
+pub use normalize::NodeKind;
+#[cfg(feature = "dot")]
+pub use parse::{ParseError, from_dot};
+pub use ranking::RankingAlgorithm;
+pub use routing::RoutingConstraint;
+pub use shape::*;
+pub use validate::LayoutViolation;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,100 @@
+use crate::error::LayoutError;
+use crate::layout::{DagreLayout, LayoutResult};
+use petgraph::prelude::*;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Time spent in the phases of a layout, see [`DagreLayout::compute_with_metrics`]
+///
+/// Layouts that are computed on a derived graph or per component add up
+/// the durations and iterations of every run.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutMetrics {
+    /// Layer assignment, including `same_rank`, `min_rank_nodes` and `max_rank_nodes`
+    pub ranking: Duration,
+    /// Ordering of the layers, from the initial order to the end of the sweeps
+    pub crossing_reduction: Duration,
+    /// Placement of the ordered layers and the passes refining it, up to edge routing
+    pub coordinate_assignment: Duration,
+    /// The whole computation
+    pub total: Duration,
+    /// Crossing reduction sweeps run, each one down and up the layers
+    pub iterations: usize,
+    /// Crossings left in the layout, equal to its `crossing_count`
+    pub crossing_count: usize,
+}
+
+/// Metrics shared by a layout engine and the engines derived from it
+pub(crate) type MetricsRecorder = Arc<Mutex<LayoutMetrics>>;
+
+impl DagreLayout {
+    /// Like [`Self::compute`], also reporting how long each phase took
+    ///
+    /// # Example
+    /// ```
+    /// use dagrers::DagreLayout;
+    /// use petgraph::Graph;
+    ///
+    /// let mut graph = Graph::new();
+    /// let a = graph.add_node("A");
+    /// let b = graph.add_node("B");
+    /// graph.add_edge(a, b, ());
+    ///
+    /// let (result, metrics) = DagreLayout::new().compute_with_metrics(&graph).unwrap();
+    /// assert_eq!(metrics.crossing_count, result.crossing_count);
+    /// assert!(metrics.ranking <= metrics.total);
+    /// ```
+    pub fn compute_with_metrics<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Result<(LayoutResult, LayoutMetrics), LayoutError> {
+        let recorder = MetricsRecorder::default();
+        let mut layout = self.clone();
+        layout.metrics = Some(recorder.clone());
+
+        let start = Instant::now();
+        let result = layout.compute(graph)?;
+        let mut metrics = *recorder.lock().unwrap_or_else(PoisonError::into_inner);
+        metrics.total = start.elapsed();
+        metrics.crossing_count = result.crossing_count;
+        Ok((result, metrics))
+    }
+
+    /// Update the metrics if this engine records any
+    pub(crate) fn record(&self, update: impl FnOnce(&mut LayoutMetrics)) {
+        if let Some(recorder) = &self.metrics {
+            update(&mut recorder.lock().unwrap_or_else(PoisonError::into_inner));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutOptions;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_compute_with_metrics_matches_compute() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..6).map(|_| graph.add_node(())).collect();
+        for (source, target) in [(0, 3), (0, 4), (1, 3), (2, 5), (1, 5), (2, 4), (5, 0)] {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            max_iterations: 3,
+            ..Default::default()
+        });
+        let (result, metrics) = layout.compute_with_metrics(&graph).unwrap();
+        assert!(result.approx_eq(&layout.compute(&graph).unwrap()));
+        assert_eq!(metrics.crossing_count, result.crossing_count);
+        assert!((1..=3).contains(&metrics.iterations));
+        let phases = metrics.ranking + metrics.crossing_reduction + metrics.coordinate_assignment;
+        assert!(phases <= metrics.total);
+
+        // The engine itself keeps no metrics
+        assert!(layout.metrics.is_none());
+    }
+}
@@ -0,0 +1,294 @@
+use crate::constraints::FlatEdge;
+use crate::error::LayoutError;
+use crate::layout::DagreLayout;
+use petgraph::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// A node of the normalized layout graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// A node of the input graph
+    Real(NodeIndex),
+    /// A virtual node placed on an intermediate layer of a long edge
+    Dummy(EdgeIndex),
+}
+
+/// Layout graph in which every edge connects adjacent layers
+///
+/// Real nodes keep the `NodeIndex` they have in the input graph, dummy
+/// nodes are appended after them.
+pub(crate) struct NormalizedGraph {
+    /// Graph containing real and dummy nodes, edges carry the input edge they belong to
+    pub(crate) graph: DiGraph<NodeKind, EdgeIndex>,
+    /// Dummy nodes of each long edge, ordered from source to target
+    pub(crate) chains: HashMap<EdgeIndex, Vec<NodeIndex>>,
+    /// Number of real nodes, i.e. the first dummy node index
+    pub(crate) real_count: usize,
+}
+
+impl NormalizedGraph {
+    /// Check whether a node of the normalized graph is a dummy node
+    pub(crate) fn is_dummy(&self, node: NodeIndex) -> bool {
+        node.index() >= self.real_count
+    }
+}
+
+/// Split every edge spanning more than one layer into a chain of dummy nodes
+///
+/// Dummy nodes are appended to the layers they pass through, so `layers`
+/// afterwards describes the normalized graph. Edges that do not point to a
+/// later layer are kept as direct edges. Edges in `skip` are routed
+/// separately and left out of the normalized graph.
+pub(crate) fn normalize<N, E>(
+    graph: &DiGraph<N, E>,
+    layers: &mut [Vec<NodeIndex>],
+    skip: &HashSet<EdgeIndex>,
+) -> NormalizedGraph {
+    let mut ranks = HashMap::new();
+    for (layer_idx, layer) in layers.iter().enumerate() {
+        for &node in layer {
+            ranks.insert(node, layer_idx);
+        }
+    }
+
+    let mut normalized = DiGraph::with_capacity(graph.node_count(), graph.edge_count());
+    for node in graph.node_indices() {
+        normalized.add_node(NodeKind::Real(node));
+    }
+
+    let mut chains = HashMap::new();
+    for edge in graph.edge_references() {
+        if skip.contains(&edge.id()) {
+            continue;
+        }
+        let (source, target) = (edge.source(), edge.target());
+        let (source_rank, target_rank) = (ranks[&source], ranks[&target]);
+
+        if target_rank <= source_rank + 1 {
+            normalized.add_edge(source, target, edge.id());
+            continue;
+        }
+
+        let mut chain = Vec::with_capacity(target_rank - source_rank - 1);
+        let mut previous = source;
+        for layer in &mut layers[source_rank + 1..target_rank] {
+            let dummy = normalized.add_node(NodeKind::Dummy(edge.id()));
+            layer.push(dummy);
+            normalized.add_edge(previous, dummy, edge.id());
+            chain.push(dummy);
+            previous = dummy;
+        }
+        normalized.add_edge(previous, target, edge.id());
+        chains.insert(edge.id(), chain);
+    }
+
+    NormalizedGraph {
+        graph: normalized,
+        chains,
+        real_count: graph.node_count(),
+    }
+}
+
+/// Normalized graph with the rank of every node, see [`DagreLayout::normalized_graph`]
+pub(crate) type NormalizedView = (DiGraph<NodeKind, ()>, HashMap<NodeIndex, usize>);
+
+impl DagreLayout {
+    /// The graph that coordinate assignment works on, with the rank of every node
+    ///
+    /// Runs the stages of [`DagreLayout::compute`] up to crossing reduction
+    /// and fails where it would. Edges closing a cycle are reversed and long
+    /// edges are split into chains of [`NodeKind::Dummy`] nodes, one per
+    /// rank they cross; the label node of an edge in `edge_label_sizes` is
+    /// a dummy node of that edge as well. Edges routed as highways, merged
+    /// by `merge_bidirectional`, drawn as arcs by `on_flat_edge` or dropped
+    /// by `on_rank_conflict` are left out. Real nodes keep their index from
+    /// `graph`, dummy nodes follow them, component by component with
+    /// `pack_components`. Ranks are those of the layers after `max_width`
+    /// wrapped them, merged by index across packed components like
+    /// `layers`.
+    pub fn normalized_graph<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+    ) -> Result<NormalizedView, LayoutError> {
+        if let Some(view) = self.normalized_components(graph) {
+            return view;
+        }
+
+        if let Some(layout) = self.without_ports() {
+            return layout.normalized_graph(graph);
+        }
+
+        if let Some((layout, labeled)) = self.splitting_labeled_edges(graph) {
+            let (mut normalized, ranks) = layout.normalized_graph(&labeled.graph)?;
+            labeled.restore_normalized(&mut normalized);
+            return Ok((normalized, ranks));
+        }
+
+        let derived = match self.merging_bidirectional(graph) {
+            Some((layout, derived, _)) => Some((layout, derived)),
+            None => self
+                .removing_cycles(graph)
+                .map(|(layout, derived, _)| (layout, derived)),
+        };
+        if let Some((layout, derived)) = derived {
+            let (mut normalized, ranks) = layout.normalized_graph(&derived.graph)?;
+            derived.restore_normalized(&mut normalized);
+            return Ok((normalized, ranks));
+        }
+
+        let mut layers = if self.options.fixed_ranks.is_empty() {
+            let mut layers = self.assign_constrained_ranks(graph)?;
+            self.moving_isolated_nodes(graph, &mut layers);
+            if self.options.on_flat_edge == FlatEdge::Conflict {
+                return self.normalized_layers(graph, layers);
+            }
+            layers
+        } else {
+            self.assign_fixed_ranks(graph)
+        };
+        match self.resolving_conflicts(graph, &mut layers)? {
+            None => self.normalized_layers(graph, layers),
+            Some(resolved) => {
+                let derived = &resolved.derived;
+                let layout = self.for_derived(derived);
+                let (mut normalized, ranks) = layout.normalized_layers(&derived.graph, layers)?;
+                derived.restore_normalized(&mut normalized);
+                Ok((normalized, ranks))
+            }
+        }
+    }
+
+    /// Normalized graph of ranked layers, as [`DagreLayout::layout_layers`] orders them
+    fn normalized_layers<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        mut layers: Vec<Vec<NodeIndex>>,
+    ) -> Result<NormalizedView, LayoutError> {
+        self.compacting_ranks(&mut layers)?;
+        let (normalized, _) = self.order_layers(graph, &mut layers);
+        self.wrap_layers(&normalized, &mut layers);
+
+        let ranks = layers
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, layer)| layer.iter().map(move |&node| (node, rank)))
+            .collect();
+        Ok((normalized.graph.map(|_, &kind| kind, |_, _| ()), ranks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_long_edge_gets_one_dummy_per_intermediate_layer() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        graph.add_edge(c, d, ());
+        let long = graph.add_edge(a, d, ());
+
+        let mut layers = vec![vec![a], vec![b], vec![c], vec![d]];
+        let normalized = normalize(&graph, &mut layers, &HashSet::new());
+
+        let chain = &normalized.chains[&long];
+        assert_eq!(chain.len(), 2);
+        assert_eq!(layers[1], vec![b, chain[0]]);
+        assert_eq!(layers[2], vec![c, chain[1]]);
+        assert!(chain.iter().all(|&dummy| normalized.is_dummy(dummy)));
+        assert_eq!(normalized.graph[chain[0]], NodeKind::Dummy(long));
+        assert_eq!(normalized.graph.edge_count(), 6);
+    }
+
+    #[test]
+    fn test_normalized_graph_exposes_dummy_nodes() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let long = graph.add_edge(a, c, ());
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+
+        let (normalized, ranks) = DagreLayout::new().normalized_graph(&graph).unwrap();
+
+        let dummies: Vec<NodeIndex> = normalized
+            .node_indices()
+            .filter(|&node| matches!(normalized[node], NodeKind::Dummy(_)))
+            .collect();
+        assert_eq!(dummies.len(), 1);
+        assert_eq!(normalized[dummies[0]], NodeKind::Dummy(long));
+        assert_eq!(normalized[b], NodeKind::Real(b));
+        assert_eq!(normalized.node_count(), 4);
+        assert_eq!(normalized.edge_count(), 4);
+        assert_eq!(ranks[&dummies[0]], 1);
+        assert_eq!((ranks[&a], ranks[&b], ranks[&c]), (0, 1, 2));
+    }
+
+    #[test]
+    fn test_normalized_graph_follows_every_compute_stage() {
+        use crate::components::ComponentPacking;
+        use crate::layout::LayoutOptions;
+
+        // Two components, one with a labeled long edge, a flat edge within
+        // a same_rank group and a cycle, and an isolated node
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..9).map(|i| graph.add_node(i)).collect();
+        let mut edges = Vec::new();
+        for (source, target) in [(0, 1), (1, 2), (0, 2), (1, 3), (3, 0), (5, 6), (6, 7)] {
+            edges.push(graph.add_edge(nodes[source], nodes[target], ()));
+        }
+        let labeled = edges[2];
+        let base = LayoutOptions {
+            edge_label_sizes: HashMap::from([(labeled, (30.0, 10.0))]),
+            same_rank: vec![vec![nodes[2], nodes[3]]],
+            ..Default::default()
+        };
+        let variants = [
+            LayoutOptions {
+                on_flat_edge: FlatEdge::Arc,
+                ..base.clone()
+            },
+            LayoutOptions {
+                on_flat_edge: FlatEdge::NewRank,
+                isolated_lane: true,
+                ..base.clone()
+            },
+            LayoutOptions {
+                on_flat_edge: FlatEdge::Arc,
+                pack_components: Some(ComponentPacking::Row { gap: 20.0 }),
+                max_width: Some(60.0),
+                ..base.clone()
+            },
+        ];
+        for options in variants {
+            let layout = DagreLayout::with_options(options);
+            let result = layout.compute(&graph).unwrap();
+            let (normalized, ranks) = layout.normalized_graph(&graph).unwrap();
+            for node in graph.node_indices() {
+                assert_eq!(normalized[node], NodeKind::Real(node));
+                assert_eq!(ranks[&node], result.node_ranks[&node]);
+            }
+            // The label node stands in the middle of its edge
+            let label_nodes = normalized
+                .node_weights()
+                .filter(|&&kind| kind == NodeKind::Dummy(labeled))
+                .count();
+            assert!(label_nodes >= 1);
+        }
+
+        // Failures match those of compute
+        let layout = DagreLayout::with_options(LayoutOptions {
+            allowed_rank_coordinates: Some(vec![0.0, 50.0]),
+            ..base
+        });
+        let error = layout.normalized_graph(&graph).unwrap_err();
+        assert_eq!(error, layout.compute(&graph).unwrap_err());
+    }
+}
@@ -0,0 +1,304 @@
+use petgraph::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Reason [`from_dot`] could not read its input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input does not start with `digraph`
+    MissingHeader,
+    /// A token that does not fit the supported DOT subset
+    Unexpected {
+        /// Line of the token, starting at 1
+        line: usize,
+        /// The token as written
+        token: String,
+    },
+    /// The input ends inside the graph body, a quoted name or a comment
+    UnexpectedEnd,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingHeader => write!(f, "input does not start with digraph"),
+            ParseError::Unexpected { line, token } => {
+                write!(f, "unexpected {} on line {}", token, line)
+            }
+            ParseError::UnexpectedEnd => write!(f, "input ends inside the graph"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Id(String),
+    Arrow,
+    Open,
+    Close,
+    Attributes,
+    Equals,
+    Separator,
+    Other(String),
+}
+
+/// Read a directed graph from a small subset of DOT
+///
+/// The input is a `digraph` with an optional name whose body holds node
+/// declarations `a;` and edge chains `a -> b -> c;`. Names are bare
+/// identifiers or numbers, or quoted strings. Nodes are added in the order
+/// they first appear and weighted with their name. Attribute lists,
+/// `graph`, `node` and `edge` defaults, `name = value` assignments and
+/// comments are skipped; subgraphs and undirected edges are not supported.
+///
+/// # Example
+/// ```
+/// use dagrers::{DagreLayout, from_dot};
+///
+/// let graph = from_dot("digraph { a -> b -> c; a -> c; d }").unwrap();
+/// assert_eq!(graph.node_count(), 4);
+/// let result = DagreLayout::new().compute(&graph).unwrap();
+/// assert_eq!(result.layers.len(), 3);
+/// ```
+pub fn from_dot(input: &str) -> Result<DiGraph<String, ()>, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut tokens = tokens.into_iter().peekable();
+    let keyword = |token: &Option<(Token, usize)>, word: &str| match token {
+        Some((Token::Id(id), _)) => id.eq_ignore_ascii_case(word),
+        _ => false,
+    };
+    let unexpected = |token: Option<(Token, usize)>| match token {
+        None => ParseError::UnexpectedEnd,
+        Some((token, line)) => ParseError::Unexpected {
+            line,
+            token: token.to_string(),
+        },
+    };
+
+    let mut header = tokens.next();
+    if keyword(&header, "strict") {
+        header = tokens.next();
+    }
+    if !keyword(&header, "digraph") {
+        return Err(ParseError::MissingHeader);
+    }
+    if let Some((Token::Id(_), _)) = tokens.peek() {
+        tokens.next();
+    }
+    match tokens.next() {
+        Some((Token::Open, _)) => {}
+        token => return Err(unexpected(token)),
+    }
+
+    let mut graph = DiGraph::new();
+    let mut nodes: HashMap<String, NodeIndex> = HashMap::new();
+    let mut node = |graph: &mut DiGraph<String, ()>, name: String| {
+        *nodes
+            .entry(name)
+            .or_insert_with_key(|name| graph.add_node(name.clone()))
+    };
+    loop {
+        let statement = tokens.next();
+        let name = match statement {
+            Some((Token::Close, _)) => break,
+            Some((Token::Separator, _)) => continue,
+            Some((Token::Id(ref id), _))
+                if ["graph", "node", "edge"]
+                    .iter()
+                    .any(|word| id.eq_ignore_ascii_case(word)) =>
+            {
+                match tokens.next() {
+                    Some((Token::Attributes, _)) => continue,
+                    token => return Err(unexpected(token)),
+                }
+            }
+            Some((Token::Id(id), _)) => id,
+            token => return Err(unexpected(token)),
+        };
+        if let Some((Token::Equals, _)) = tokens.peek() {
+            tokens.next();
+            match tokens.next() {
+                Some((Token::Id(_), _)) => continue,
+                token => return Err(unexpected(token)),
+            }
+        }
+
+        let mut source = node(&mut graph, name);
+        while let Some((Token::Arrow, _)) = tokens.peek() {
+            tokens.next();
+            let target = match tokens.next() {
+                Some((Token::Id(id), _)) => node(&mut graph, id),
+                token => return Err(unexpected(token)),
+            };
+            graph.add_edge(source, target, ());
+            source = target;
+        }
+        if let Some((Token::Attributes, _)) = tokens.peek() {
+            tokens.next();
+        }
+    }
+
+    match tokens.next() {
+        None => Ok(graph),
+        token => Err(unexpected(token)),
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Id(id) => write!(f, "`{}`", id),
+            Token::Arrow => write!(f, "`->`"),
+            Token::Open => write!(f, "`{{`"),
+            Token::Close => write!(f, "`}}`"),
+            Token::Attributes => write!(f, "attribute list"),
+            Token::Equals => write!(f, "`=`"),
+            Token::Separator => write!(f, "separator"),
+            Token::Other(text) => write!(f, "`{}`", text),
+        }
+    }
+}
+
+/// Split DOT input into tokens with their line, dropping comments
+///
+/// Attribute lists are reduced to a single token, their content is never
+/// interpreted.
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '#' => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+                continue;
+            }
+            '/' if chars.next_if_eq(&'/').is_some() => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+                continue;
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                let mut previous = ' ';
+                loop {
+                    match chars.next() {
+                        None => return Err(ParseError::UnexpectedEnd),
+                        Some('/') if previous == '*' => break,
+                        Some(c) => {
+                            line += usize::from(c == '\n');
+                            previous = c;
+                        }
+                    }
+                }
+                continue;
+            }
+            '-' if chars.next_if_eq(&'>').is_some() => Token::Arrow,
+            '-' if chars.next_if_eq(&'-').is_some() => Token::Other("--".to_string()),
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '=' => Token::Equals,
+            ';' | ',' => Token::Separator,
+            '[' => {
+                let start = line;
+                let mut quoted = false;
+                loop {
+                    match chars.next() {
+                        None => return Err(ParseError::UnexpectedEnd),
+                        Some(']') if !quoted => break,
+                        Some('"') => quoted = !quoted,
+                        Some('\\') if quoted => {
+                            chars.next();
+                        }
+                        Some('\n') => line += 1,
+                        Some(_) => {}
+                    }
+                }
+                tokens.push((Token::Attributes, start));
+                continue;
+            }
+            '"' => {
+                let start = line;
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(ParseError::UnexpectedEnd),
+                        Some('"') => break,
+                        Some('\\') if chars.peek() == Some(&'"') => id.extend(chars.next()),
+                        Some(c) => {
+                            line += usize::from(c == '\n');
+                            id.push(c);
+                        }
+                    }
+                }
+                tokens.push((Token::Id(id), start));
+                continue;
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' => {
+                let mut id = String::from(c);
+                while let Some(c) = chars.next_if(|&c| c.is_alphanumeric() || c == '_' || c == '.')
+                {
+                    id.push(c);
+                }
+                Token::Id(id)
+            }
+            c => Token::Other(c.to_string()),
+        };
+        tokens.push((token, line));
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::DagreLayout;
+
+    #[test]
+    fn test_from_dot_subset() {
+        let input = r#"
+            // Build pipeline
+            strict digraph build {
+                rankdir = LR;
+                node [shape=box, label="ignored ]"];
+                fetch -> "unit tests" -> report [color=red]
+                fetch -> lint; lint -> report
+                /* declared only */ docs
+                # shell-style comment
+            }
+        "#;
+        let graph = from_dot(input).unwrap();
+        let names: Vec<&str> = graph.node_weights().map(String::as_str).collect();
+        assert_eq!(names, ["fetch", "unit tests", "report", "lint", "docs"]);
+        let edges: Vec<(usize, usize)> = graph
+            .edge_references()
+            .map(|edge| (edge.source().index(), edge.target().index()))
+            .collect();
+        assert_eq!(edges, [(0, 1), (1, 2), (0, 3), (3, 2)]);
+
+        let result = DagreLayout::new().compute(&graph).unwrap();
+        assert_eq!(result.layers.len(), 3);
+
+        assert_eq!(
+            from_dot("graph { a -- b }").unwrap_err(),
+            ParseError::MissingHeader
+        );
+        assert_eq!(
+            from_dot("digraph {\n a -- b }").unwrap_err(),
+            ParseError::Unexpected {
+                line: 2,
+                token: "`--`".to_string()
+            }
+        );
+        assert_eq!(
+            from_dot("digraph { a -> b").unwrap_err(),
+            ParseError::UnexpectedEnd
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use crate::layout::{DagreLayout, LayoutResult};
+use petgraph::prelude::*;
+
+impl DagreLayout {
+    /// Layout engine with the port hints left out, if `source_ports` or `target_ports` has any
+    pub(crate) fn without_ports(&self) -> Option<DagreLayout> {
+        let options = &self.options;
+        if options.source_ports.is_empty() && options.target_ports.is_empty() {
+            return None;
+        }
+        let mut layout = self.clone();
+        layout.options.source_ports.clear();
+        layout.options.target_ports.clear();
+        Some(layout)
+    }
+
+    /// Move the ends of edges with a port hint to the middle of that side of their node
+    ///
+    /// Edges without `edge_points`, such as dropped ones, are skipped.
+    /// Unsized nodes have no sides, their edges end at the node center.
+    pub(crate) fn attach_ports<N, E>(&self, graph: &DiGraph<N, E>, result: &mut LayoutResult) {
+        let ends = [
+            (&self.options.source_ports, Outgoing),
+            (&self.options.target_ports, Incoming),
+        ];
+        for (ports, direction) in ends {
+            for (&edge, side) in ports {
+                let Some((source, target)) = graph.edge_endpoints(edge) else {
+                    continue;
+                };
+                let node = if direction == Outgoing {
+                    source
+                } else {
+                    target
+                };
+                let (Some(points), Some(&center)) = (
+                    result.edge_points.get_mut(&edge),
+                    result.node_positions.get(&node),
+                ) else {
+                    continue;
+                };
+                let size = result.node_sizes.get(&node).copied().unwrap_or_default();
+                let point = side.point(center, size);
+                match direction {
+                    Outgoing => points[0] = point,
+                    Incoming => *points.last_mut().unwrap() = point,
+                }
+
+                if let Some(slots) = result.anchor_slots.get_mut(&node) {
+                    let slots = match direction {
+                        Outgoing => &mut slots.outgoing,
+                        Incoming => &mut slots.incoming,
+                    };
+                    for slot in slots.iter_mut().filter(|(slot, _)| *slot == edge) {
+                        slot.1 = point;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::{DagreLayout, LayoutOptions, RankDir};
+    use crate::shape::PortSide;
+    use petgraph::Graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_ports_attach_edges_to_sides() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let down = graph.add_edge(a, b, ());
+        let across = graph.add_edge(a, c, ());
+        let sizes = HashMap::from([(a, (80.0, 40.0)), (b, (60.0, 30.0)), (c, (60.0, 30.0))]);
+
+        let layout = DagreLayout::with_options(LayoutOptions {
+            rank_dir: RankDir::LeftToRight,
+            node_sizes: sizes,
+            anchor_slots: true,
+            source_ports: HashMap::from([(down, PortSide::Bottom), (across, PortSide::Right)]),
+            target_ports: HashMap::from([(down, PortSide::Top)]),
+            ..Default::default()
+        });
+        let result = layout.compute(&graph).unwrap();
+        let (ax, ay) = result.node_positions[&a];
+        let (bx, by) = result.node_positions[&b];
+
+        let points = &result.edge_points[&down];
+        assert_eq!(points[0], (ax, ay + 20.0));
+        assert_eq!(points[points.len() - 1], (bx, by - 15.0));
+        assert_eq!(result.edge_points[&across][0], (ax + 40.0, ay));
+        let outgoing = &result.anchor_slots[&a].outgoing;
+        assert!(outgoing.contains(&(down, (ax, ay + 20.0))));
+
+        // Ends without a hint are clipped to the border as usual
+        let (cx, _) = result.node_positions[&c];
+        let last = result.edge_points[&across].last().copied().unwrap();
+        assert_eq!(last.0, cx - 30.0);
+    }
+}
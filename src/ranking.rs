@@ -0,0 +1,496 @@
+use petgraph::prelude::*;
+use std::collections::HashMap;
+
+/// Algorithm that assigns the ranks of nodes in an acyclic graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RankingAlgorithm {
+    /// Every node one rank below its lowest predecessor
+    #[default]
+    LongestPath,
+    /// Longest-path ranks with the nodes pulled together along a spanning
+    /// tree of edges spanning one rank, usually shorter edges at little cost
+    TightTree,
+    /// Ranks with the smallest total weighted edge span, computed with the
+    /// network simplex method starting from the tight tree
+    NetworkSimplex,
+}
+
+/// Cut values below this are treated as negative, guarding against float noise
+const EPSILON: f64 = 1e-6;
+
+/// Edge of the simplified graph, parallel edges merged into one
+struct Edge {
+    source: usize,
+    target: usize,
+    weight: f64,
+    min_length: i64,
+}
+
+/// Ranking problem of one connected component with its spanning tree
+///
+/// Nodes are numbered within the component. The tree is rooted at node 0,
+/// `low` and `lim` number the subtree of every node in postorder so that
+/// `x` lies below `y` iff `low[y] <= lim[x] <= lim[y]`. The cut value of the
+/// tree edge between a node and its parent is stored with the node.
+struct Simplex {
+    edges: Vec<Edge>,
+    incident: Vec<Vec<usize>>,
+    between: HashMap<(usize, usize), usize>,
+    ranks: Vec<i64>,
+    tree: Vec<Vec<usize>>,
+    parent: Vec<Option<usize>>,
+    low: Vec<usize>,
+    lim: Vec<usize>,
+    cut: Vec<f64>,
+    postorder: Vec<usize>,
+}
+
+impl Simplex {
+    fn new(edges: Vec<Edge>, ranks: Vec<i64>) -> Self {
+        let count = ranks.len();
+        let mut incident = vec![Vec::new(); count];
+        let mut between = HashMap::new();
+        for (id, edge) in edges.iter().enumerate() {
+            incident[edge.source].push(id);
+            incident[edge.target].push(id);
+            between.insert((edge.source, edge.target), id);
+        }
+        Self {
+            edges,
+            incident,
+            between,
+            ranks,
+            tree: vec![Vec::new(); count],
+            parent: vec![None; count],
+            low: vec![0; count],
+            lim: vec![0; count],
+            cut: vec![0.0; count],
+            postorder: Vec::with_capacity(count),
+        }
+    }
+
+    /// Rank span of an edge beyond its minimum length
+    fn slack(&self, edge: &Edge) -> i64 {
+        self.ranks[edge.target] - self.ranks[edge.source] - edge.min_length
+    }
+
+    /// Grow a spanning tree of edges without slack, shifting ranks as needed
+    ///
+    /// Whenever no tight edge leaves the tree, the whole tree is shifted
+    /// toward the non-tree end of the edge with the least slack, which keeps
+    /// every edge feasible and makes that edge tight.
+    fn feasible_tree(&mut self) {
+        let count = self.ranks.len();
+        let mut in_tree = vec![false; count];
+        in_tree[0] = true;
+        let mut members = vec![0];
+
+        loop {
+            let mut stack = members.clone();
+            while let Some(node) = stack.pop() {
+                for &id in &self.incident[node] {
+                    let edge = &self.edges[id];
+                    let other = if edge.source == node {
+                        edge.target
+                    } else {
+                        edge.source
+                    };
+                    if !in_tree[other] && self.slack(edge) == 0 {
+                        in_tree[other] = true;
+                        members.push(other);
+                        stack.push(other);
+                        self.tree[node].push(other);
+                        self.tree[other].push(node);
+                    }
+                }
+            }
+            if members.len() == count {
+                return;
+            }
+
+            let entering = self
+                .edges
+                .iter()
+                .filter(|edge| in_tree[edge.source] != in_tree[edge.target])
+                .min_by_key(|edge| self.slack(edge))
+                .expect("component is connected");
+            let delta = match in_tree[entering.source] {
+                true => self.slack(entering),
+                false => -self.slack(entering),
+            };
+            for &node in &members {
+                self.ranks[node] += delta;
+            }
+        }
+    }
+
+    /// Number the tree in postorder and record every node's parent
+    fn assign_low_lim(&mut self) {
+        self.postorder.clear();
+        self.parent[0] = None;
+        self.low[0] = 1;
+        let mut next = 1;
+        let mut stack = vec![(0, 0)];
+        while let Some((node, child)) = stack.last_mut() {
+            let node = *node;
+            match self.tree[node].get(*child) {
+                Some(&neighbor) => {
+                    *child += 1;
+                    if Some(neighbor) != self.parent[node] {
+                        self.parent[neighbor] = Some(node);
+                        self.low[neighbor] = next;
+                        stack.push((neighbor, 0));
+                    }
+                }
+                None => {
+                    self.lim[node] = next;
+                    next += 1;
+                    self.postorder.push(node);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    /// Cut values of all tree edges, children before their parents
+    ///
+    /// The cut value of a tree edge is the weight of all edges from the tail
+    /// side to the head side of the tree split at that edge, minus the weight
+    /// of edges going the other way. It follows from the cut values of the
+    /// edges to the node's children and the node's own edges.
+    fn assign_cut_values(&mut self) {
+        for i in 0..self.postorder.len() {
+            let child = self.postorder[i];
+            let Some(parent) = self.parent[child] else {
+                continue;
+            };
+            let (tree_edge, child_is_tail) = match self.between.get(&(child, parent)) {
+                Some(&id) => (id, true),
+                None => (self.between[&(parent, child)], false),
+            };
+
+            let mut cut = self.edges[tree_edge].weight;
+            for &id in &self.incident[child] {
+                let edge = &self.edges[id];
+                let outgoing = edge.source == child;
+                let other = if outgoing { edge.target } else { edge.source };
+                if other == parent {
+                    continue;
+                }
+                let points_to_head = outgoing == child_is_tail;
+                cut += if points_to_head {
+                    edge.weight
+                } else {
+                    -edge.weight
+                };
+                if self.parent[other] == Some(child) {
+                    let other_cut = self.cut[other];
+                    cut += if points_to_head {
+                        -other_cut
+                    } else {
+                        other_cut
+                    };
+                }
+            }
+            self.cut[child] = cut;
+        }
+    }
+
+    /// Recompute ranks along the tree so every tree edge is tight
+    fn update_ranks(&mut self) {
+        for i in (0..self.postorder.len()).rev() {
+            let node = self.postorder[i];
+            let Some(parent) = self.parent[node] else {
+                continue;
+            };
+            self.ranks[node] = match self.between.get(&(node, parent)) {
+                Some(&id) => self.ranks[parent] - self.edges[id].min_length,
+                None => self.ranks[parent] + self.edges[self.between[&(parent, node)]].min_length,
+            };
+        }
+    }
+
+    /// Swap tree edges with negative cut values for the tightest replacement
+    fn optimize(&mut self) {
+        self.assign_low_lim();
+        self.assign_cut_values();
+
+        while let Some(child) = (0..self.ranks.len())
+            .find(|&node| self.parent[node].is_some() && self.cut[node] < -EPSILON)
+        {
+            let parent = self.parent[child].unwrap();
+            let (tail, head) = match self.between.contains_key(&(child, parent)) {
+                true => (child, parent),
+                false => (parent, child),
+            };
+
+            // The replacement runs from the head side back to the tail side
+            let (root, flip) = match self.lim[tail] > self.lim[head] {
+                true => (head, true),
+                false => (tail, false),
+            };
+            let below =
+                |node: usize| self.low[root] <= self.lim[node] && self.lim[node] <= self.lim[root];
+            let Some(entering) = (0..self.edges.len())
+                .filter(|&id| {
+                    let edge = &self.edges[id];
+                    flip == below(edge.source) && flip != below(edge.target)
+                })
+                .min_by_key(|&id| self.slack(&self.edges[id]))
+            else {
+                return;
+            };
+
+            self.tree[child].retain(|&node| node != parent);
+            self.tree[parent].retain(|&node| node != child);
+            let (source, target) = (self.edges[entering].source, self.edges[entering].target);
+            self.tree[source].push(target);
+            self.tree[target].push(source);
+            self.assign_low_lim();
+            self.assign_cut_values();
+            self.update_ranks();
+        }
+    }
+}
+
+/// Shorten the edges of feasible ranks of an acyclic graph with `algorithm`
+///
+/// Every connected component is refined on its own and shifted so that
+/// its first rank is zero. Edges weigh their `edge_weights` entry, or 1,
+/// and every edge spans at least `min_length` ranks. Self-loops are
+/// ignored. Ranks in which some edge spans fewer ranks are left unchanged.
+/// `ranks` is indexed by node index.
+pub(crate) fn refine_ranks<N, E>(
+    algorithm: RankingAlgorithm,
+    graph: &DiGraph<N, E>,
+    ranks: &mut [usize],
+    edge_weights: &HashMap<EdgeIndex, f32>,
+    min_length: impl Fn(EdgeIndex) -> usize,
+) {
+    let feasible = graph.edge_references().all(|edge| {
+        edge.source() == edge.target()
+            || ranks[edge.source().index()] + min_length(edge.id()) <= ranks[edge.target().index()]
+    });
+    if algorithm == RankingAlgorithm::LongestPath || !feasible {
+        return;
+    }
+
+    let mut components = petgraph::unionfind::UnionFind::new(graph.node_count());
+    for edge in graph.edge_references() {
+        components.union(edge.source().index(), edge.target().index());
+    }
+    let mut members: HashMap<usize, Vec<NodeIndex>> = HashMap::new();
+    for node in graph.node_indices() {
+        members
+            .entry(components.find(node.index()))
+            .or_default()
+            .push(node);
+    }
+
+    for nodes in members.values() {
+        let local: HashMap<NodeIndex, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, &node)| (node, i))
+            .collect();
+        let mut merged: HashMap<(usize, usize), (f64, i64)> = HashMap::new();
+        for &node in nodes {
+            for edge in graph.edges_directed(node, Outgoing) {
+                if edge.target() != node {
+                    let weight = edge_weights.get(&edge.id()).copied().unwrap_or(1.0);
+                    let (total, length) = merged
+                        .entry((local[&node], local[&edge.target()]))
+                        .or_default();
+                    *total += f64::from(weight.max(0.0));
+                    *length = (*length).max(min_length(edge.id()) as i64);
+                }
+            }
+        }
+        let mut edges: Vec<Edge> = merged
+            .into_iter()
+            .map(|((source, target), (weight, min_length))| Edge {
+                source,
+                target,
+                weight,
+                min_length,
+            })
+            .collect();
+        edges.sort_by_key(|edge| (edge.source, edge.target));
+
+        let initial = nodes
+            .iter()
+            .map(|node| ranks[node.index()] as i64)
+            .collect();
+        let mut simplex = Simplex::new(edges, initial);
+        simplex.feasible_tree();
+        if algorithm == RankingAlgorithm::NetworkSimplex {
+            simplex.optimize();
+        }
+
+        let min = simplex.ranks.iter().copied().min().unwrap_or(0);
+        for (&node, &rank) in nodes.iter().zip(&simplex.ranks) {
+            ranks[node.index()] = (rank - min) as usize;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{DagreLayout, LayoutOptions};
+    use petgraph::Graph;
+
+    /// Total weighted rank span of all edges
+    fn span(graph: &Graph<&str, ()>, ranks: &[usize], heavy: EdgeIndex) -> usize {
+        graph
+            .edge_references()
+            .map(|edge| {
+                let length = ranks[edge.target().index()] - ranks[edge.source().index()];
+                if edge.id() == heavy {
+                    5 * length
+                } else {
+                    length
+                }
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_network_simplex_shortens_weighted_edges() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|name| graph.add_node(name))
+            .collect();
+        for pair in nodes.windows(2) {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+        // Longest path puts the middle node right below the first one
+        let middle = graph.add_node("middle");
+        graph.add_edge(nodes[0], middle, ());
+        let heavy = graph.add_edge(middle, nodes[3], ());
+        let weights = HashMap::from([(heavy, 5.0)]);
+
+        let layout = DagreLayout::new();
+        let mut ranks = vec![0; graph.node_count()];
+        for (rank, layer) in layout.assign_layers_longest_path(&graph).iter().enumerate() {
+            for &node in layer {
+                ranks[node.index()] = rank;
+            }
+        }
+        let before = span(&graph, &ranks, heavy);
+        refine_ranks(
+            RankingAlgorithm::NetworkSimplex,
+            &graph,
+            &mut ranks,
+            &weights,
+            |_| 1,
+        );
+        assert_eq!(ranks[middle.index()], 2);
+        assert!(span(&graph, &ranks, heavy) < before);
+        assert_eq!(span(&graph, &ranks, heavy), 3 + 2 + 5);
+
+        // A second source moves down next to its successor
+        let late = graph.add_node("late");
+        graph.add_edge(late, nodes[3], ());
+        let result = DagreLayout::with_options(LayoutOptions {
+            ranking: RankingAlgorithm::NetworkSimplex,
+            edge_weights: weights,
+            ..Default::default()
+        })
+        .compute(&graph)
+        .unwrap();
+        assert!(result.layers[2].contains(&middle));
+        assert!(result.layers[2].contains(&late));
+        assert_eq!(result.layers.len(), 4);
+    }
+
+    #[test]
+    fn test_tight_tree_pulls_sources_to_their_successors() {
+        let mut graph = Graph::new();
+        let chain: Vec<_> = (0..4).map(|_| graph.add_node("chain")).collect();
+        for pair in chain.windows(2) {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+        let late = graph.add_node("late");
+        let later = graph.add_node("later");
+        graph.add_edge(late, chain[2], ());
+        graph.add_edge(later, chain[3], ());
+
+        let layers = |ranking| {
+            DagreLayout::with_options(LayoutOptions {
+                ranking,
+                ..Default::default()
+            })
+            .compute(&graph)
+            .unwrap()
+            .layers
+        };
+        let total_span = |layers: &[Vec<NodeIndex>]| -> usize {
+            let rank = |node| {
+                layers
+                    .iter()
+                    .position(|layer| layer.contains(&node))
+                    .unwrap()
+            };
+            graph
+                .edge_references()
+                .map(|edge| rank(edge.target()) - rank(edge.source()))
+                .sum()
+        };
+
+        let longest = layers(RankingAlgorithm::LongestPath);
+        let tight = layers(RankingAlgorithm::TightTree);
+        assert_eq!(total_span(&longest), 3 + 2 + 3);
+        assert_eq!(total_span(&tight), 3 + 1 + 1);
+        assert!(tight[1].contains(&late) && tight[2].contains(&later));
+    }
+
+    #[test]
+    fn test_min_lengths_stretch_edges_for_every_algorithm() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = (0..4).map(|_| graph.add_node("node")).collect();
+        graph.add_edge(nodes[0], nodes[1], ());
+        let long = graph.add_edge(nodes[1], nodes[2], ());
+        graph.add_edge(nodes[0], nodes[3], ());
+        graph.add_edge(nodes[3], nodes[2], ());
+
+        for ranking in [
+            RankingAlgorithm::LongestPath,
+            RankingAlgorithm::TightTree,
+            RankingAlgorithm::NetworkSimplex,
+        ] {
+            let layout = |min_lengths| {
+                DagreLayout::with_options(LayoutOptions {
+                    ranking,
+                    min_lengths,
+                    ..Default::default()
+                })
+            };
+            let plain = layout(HashMap::new()).compute(&graph).unwrap();
+            let ones = layout(HashMap::from([(long, 1)])).compute(&graph).unwrap();
+            assert!(ones.approx_eq(&plain));
+
+            let layout = layout(HashMap::from([(long, 3)]));
+            let result = layout.compute(&graph).unwrap();
+            let rank = |node: NodeIndex| result.node_ranks[&node];
+            assert_eq!(rank(nodes[2]) - rank(nodes[1]), 3);
+            assert_eq!(result.layers.len(), 5);
+            assert_eq!(result.edge_points[&long].len(), 4);
+            assert_eq!(layout.validate_layout(&graph, &result, 0.01), Ok(()));
+        }
+
+        // Ranks holding nothing but the stretched edge are kept
+        let mut pair = Graph::new();
+        let (a, b) = (pair.add_node("a"), pair.add_node("b"));
+        let edge = pair.add_edge(a, b, ());
+        let layout = DagreLayout::with_options(LayoutOptions {
+            min_lengths: HashMap::from([(edge, 3)]),
+            ..Default::default()
+        });
+        let result = layout.compute(&pair).unwrap();
+        assert_eq!(result.layers, vec![vec![a], vec![], vec![], vec![b]]);
+        assert_eq!(layout.validate_layout(&pair, &result, 0.01), Ok(()));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::layout::LayoutResult;
+use petgraph::prelude::*;
+use std::fmt::{Display, Write};
+
+/// Points per inch, the unit Graphviz expects node sizes in
+const POINTS_PER_INCH: f32 = 72.0;
+
+/// Write a computed layout as a Graphviz DOT digraph with fixed positions
+///
+/// Nodes are named by their index and labeled with the node weight's
+/// `Display` implementation. Every node gets a `pos` in points, and nodes
+/// with a size also get `width` and `height` in inches. Edges with
+/// `edge_points` get a `pos` spline running straight through them. Since
+/// Graphviz puts the origin at the bottom left, y coordinates are flipped
+/// within the layout's height and the graph's `bb` is set to match, so
+/// `neato -n` draws the layout as computed.
+///
+/// # Example
+/// ```
+/// use dagrers::DagreLayout;
+/// use dagrers::render::to_dot;
+/// use petgraph::Graph;
+///
+/// let mut graph = Graph::new();
+/// let a = graph.add_node("A");
+/// let b = graph.add_node("B");
+/// graph.add_edge(a, b, ());
+///
+/// let result = DagreLayout::new().compute(&graph).unwrap();
+/// let dot = to_dot(&graph, &result);
+/// assert!(dot.starts_with("digraph {"));
+/// ```
+pub fn to_dot<N: Display, E>(graph: &DiGraph<N, E>, result: &LayoutResult) -> String {
+    let point = |(x, y): (f32, f32)| format!("{},{}", x, result.height - y);
+
+    let mut dot = String::from("digraph {\n");
+    let _ = writeln!(
+        dot,
+        "  graph [bb=\"0,0,{},{}\"];",
+        result.width, result.height
+    );
+    for node in graph.node_indices() {
+        let _ = write!(
+            dot,
+            "  {} [label=\"{}\"",
+            node.index(),
+            escape(&graph[node].to_string())
+        );
+        if let Some(&position) = result.node_positions.get(&node) {
+            let _ = write!(dot, ", pos=\"{}\"", point(position));
+        }
+        if let Some(&(width, height)) = result.node_sizes.get(&node) {
+            let _ = write!(
+                dot,
+                ", width={}, height={}",
+                width / POINTS_PER_INCH,
+                height / POINTS_PER_INCH
+            );
+        }
+        dot.push_str("];\n");
+    }
+    for edge in graph.edge_references() {
+        let _ = write!(
+            dot,
+            "  {} -> {}",
+            edge.source().index(),
+            edge.target().index()
+        );
+        match result.edge_points.get(&edge.id()) {
+            Some(points) if !points.is_empty() => {
+                let _ = writeln!(dot, " [pos=\"{}\"];", spline(points, point));
+            }
+            _ => dot.push_str(";\n"),
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Cubic B-spline control points drawing the polyline through `points`
+///
+/// Graphviz expects one start point and three points per segment, so
+/// each segment uses its own end points as control points and runs straight.
+fn spline(points: &[(f32, f32)], point: impl Fn((f32, f32)) -> String) -> String {
+    let mut controls = vec![point(points[0])];
+    for segment in points.windows(2) {
+        controls.push(point(segment[0]));
+        controls.push(point(segment[1]));
+        controls.push(point(segment[1]));
+    }
+    controls.join(" ")
+}
+
+/// Escape the characters that end or break a quoted DOT string
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{DagreLayout, LayoutOptions};
+    use petgraph::Graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_to_dot_positions() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A \"quoted\"");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        let long = graph.add_edge(a, c, ());
+
+        let options = LayoutOptions {
+            node_sizes: HashMap::from([(b, (72.0, 36.0))]),
+            ..Default::default()
+        };
+        let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+        let dot = to_dot(&graph, &result);
+
+        let (x, y) = result.node_positions[&a];
+        assert!(dot.contains(&format!(
+            "  0 [label=\"A \\\"quoted\\\"\", pos=\"{},{}\"];",
+            x,
+            result.height - y
+        )));
+        assert!(dot.contains(", width=1, height=0.5];"));
+        assert_eq!(dot.matches(" -> ").count(), 3);
+
+        // The long edge bends once, giving one start and two segments of three points
+        let line = dot
+            .lines()
+            .find(|line| line.starts_with("  0 -> 2"))
+            .unwrap();
+        let pos = line.split('"').nth(1).unwrap();
+        assert_eq!(result.edge_points[&long].len(), 3);
+        assert_eq!(pos.split(' ').count(), 7);
+        assert!(dot.ends_with("}\n"));
+    }
+}
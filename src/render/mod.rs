@@ -0,0 +1,13 @@
+//! Rendering of computed layouts into drawable formats
+
+pub mod dot;
+#[cfg(feature = "svg")]
+pub mod primitives;
+#[cfg(feature = "svg")]
+pub mod svg;
+
+pub use dot::*;
+#[cfg(feature = "svg")]
+pub use primitives::*;
+#[cfg(feature = "svg")]
+pub use svg::*;
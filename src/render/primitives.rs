@@ -0,0 +1,289 @@
+use crate::layout::LayoutResult;
+use crate::render::SvgStyle;
+use petgraph::prelude::*;
+use std::fmt::Display;
+
+/// Renderer-agnostic drawing instruction produced by [`to_primitives`]
+///
+/// Coordinates are in document space, i.e. layout coordinates shifted by
+/// the style's `padding`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawPrimitive {
+    /// Axis-aligned rectangle given by its top-left corner
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        fill: String,
+        stroke: String,
+        stroke_width: f32,
+    },
+    /// Circle given by its center
+    Circle {
+        cx: f32,
+        cy: f32,
+        r: f32,
+        fill: String,
+        stroke: String,
+        stroke_width: f32,
+    },
+    /// Text centered on a point
+    Text {
+        x: f32,
+        y: f32,
+        text: String,
+        font_family: String,
+        font_size: f32,
+        color: String,
+    },
+    /// Straight edge ending in an arrowhead at `to`
+    Line {
+        from: (f32, f32),
+        to: (f32, f32),
+        stroke: String,
+        stroke_width: f32,
+    },
+    /// Bent edge ending in an arrowhead at its last point
+    Polyline {
+        points: Vec<(f32, f32)>,
+        stroke: String,
+        stroke_width: f32,
+    },
+    /// Box around a packed component, given by its top-left corner
+    Cluster {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        fill: String,
+        stroke: String,
+        stroke_width: f32,
+    },
+}
+
+/// Flatten a computed layout into an ordered list of drawing primitives
+///
+/// Every component packed by `pack_components` is a `Cluster` around its
+/// nodes and edges, `cluster_padding` away from them. Clusters come first,
+/// then edges, so that nodes are drawn on top of them. Every edge is a
+/// `Line`, or a `Polyline` if it has bend points. Every node is a `Rect` if
+/// it has a size in the layout and a `Circle` otherwise, followed by a
+/// `Text` holding the node weight's `Display` output.
+pub fn to_primitives<N: Display, E>(
+    graph: &DiGraph<N, E>,
+    result: &LayoutResult,
+    style: &SvgStyle,
+) -> Vec<DrawPrimitive> {
+    let padding = style.padding;
+    let offset = |(x, y): (f32, f32)| (x + padding, y + padding);
+    let mut primitives = Vec::new();
+
+    for component in &result.components {
+        let mut min = (f32::INFINITY, f32::INFINITY);
+        let mut max = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        let mut extend = |(x, y): (f32, f32), (half_width, half_height): (f32, f32)| {
+            min = (min.0.min(x - half_width), min.1.min(y - half_height));
+            max = (max.0.max(x + half_width), max.1.max(y + half_height));
+        };
+        for node in &component.nodes {
+            let Some(&position) = result.node_positions.get(node) else {
+                continue;
+            };
+            let half = match result.node_sizes.get(node) {
+                Some(&(width, height)) => (width * 0.5, height * 0.5),
+                None => (style.node_radius, style.node_radius),
+            };
+            extend(position, half);
+            for edge in graph.edges(*node) {
+                for &point in result.edge_points.get(&edge.id()).into_iter().flatten() {
+                    extend(point, (0.0, 0.0));
+                }
+            }
+        }
+        if min.0 > max.0 {
+            continue;
+        }
+        let margin = style.cluster_padding;
+        let (x, y) = offset((min.0 - margin, min.1 - margin));
+        primitives.push(DrawPrimitive::Cluster {
+            x,
+            y,
+            width: max.0 - min.0 + 2.0 * margin,
+            height: max.1 - min.1 + 2.0 * margin,
+            fill: style.cluster_fill.clone(),
+            stroke: style.cluster_stroke.clone(),
+            stroke_width: style.cluster_stroke_width,
+        });
+    }
+
+    for edge in graph.edge_indices() {
+        let Some(points) = result.edge_points.get(&edge) else {
+            continue;
+        };
+        let stroke = style.edge_stroke.clone();
+        let stroke_width = style.edge_stroke_width;
+        primitives.push(match points[..] {
+            [from, to] => DrawPrimitive::Line {
+                from: offset(from),
+                to: offset(to),
+                stroke,
+                stroke_width,
+            },
+            _ => DrawPrimitive::Polyline {
+                points: points.iter().map(|&p| offset(p)).collect(),
+                stroke,
+                stroke_width,
+            },
+        });
+    }
+
+    for node in graph.node_indices() {
+        let Some(&position) = result.node_positions.get(&node) else {
+            continue;
+        };
+        let (x, y) = offset(position);
+
+        primitives.push(match result.node_sizes.get(&node) {
+            Some(&(width, height)) => DrawPrimitive::Rect {
+                x: x - width * 0.5,
+                y: y - height * 0.5,
+                width,
+                height,
+                fill: style.node_fill.clone(),
+                stroke: style.node_stroke.clone(),
+                stroke_width: style.node_stroke_width,
+            },
+            None => DrawPrimitive::Circle {
+                cx: x,
+                cy: y,
+                r: style.node_radius,
+                fill: style.node_fill.clone(),
+                stroke: style.node_stroke.clone(),
+                stroke_width: style.node_stroke_width,
+            },
+        });
+        primitives.push(DrawPrimitive::Text {
+            x,
+            y,
+            text: graph[node].to_string(),
+            font_family: style.font_family.clone(),
+            font_size: style.font_size,
+            color: style.text_color.clone(),
+        });
+    }
+
+    primitives
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::ComponentPacking;
+    use crate::layout::{DagreLayout, LayoutOptions};
+    use petgraph::Graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_one_shape_and_text_per_node_and_one_line_per_edge() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        graph.add_edge(a, c, ());
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+
+        let options = LayoutOptions {
+            node_sizes: HashMap::from([(b, (40.0, 20.0))]),
+            ..Default::default()
+        };
+        let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+        let primitives = to_primitives(&graph, &result, &SvgStyle::default());
+
+        let count =
+            |matches: fn(&DrawPrimitive) -> bool| primitives.iter().filter(|p| matches(p)).count();
+        assert_eq!(count(|p| matches!(p, DrawPrimitive::Text { .. })), 3);
+        assert_eq!(count(|p| matches!(p, DrawPrimitive::Rect { .. })), 1);
+        assert_eq!(count(|p| matches!(p, DrawPrimitive::Circle { .. })), 2);
+        assert_eq!(count(|p| matches!(p, DrawPrimitive::Line { .. })), 2);
+        assert_eq!(count(|p| matches!(p, DrawPrimitive::Polyline { .. })), 1);
+
+        // Edges are drawn before any node
+        let first_node = primitives
+            .iter()
+            .position(|p| {
+                !matches!(
+                    p,
+                    DrawPrimitive::Line { .. } | DrawPrimitive::Polyline { .. }
+                )
+            })
+            .unwrap();
+        assert_eq!(first_node, 3);
+    }
+
+    #[test]
+    fn test_one_cluster_around_every_packed_component() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        graph.add_edge(a, b, ());
+        graph.add_edge(c, d, ());
+
+        let options = LayoutOptions {
+            node_sizes: HashMap::from([(d, (80.0, 30.0))]),
+            pack_components: Some(ComponentPacking::Row { gap: 40.0 }),
+            ..Default::default()
+        };
+        let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+        let style = SvgStyle {
+            padding: 0.0,
+            ..Default::default()
+        };
+        let primitives = to_primitives(&graph, &result, &style);
+
+        let clusters: Vec<_> = primitives
+            .iter()
+            .filter_map(|p| match *p {
+                DrawPrimitive::Cluster {
+                    x,
+                    y,
+                    width,
+                    height,
+                    ..
+                } => Some((x, y, x + width, y + height)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(clusters.len(), 2);
+        assert!(matches!(primitives[0], DrawPrimitive::Cluster { .. }));
+        assert!(matches!(primitives[1], DrawPrimitive::Cluster { .. }));
+
+        // Every cluster holds the whole node box of exactly its own nodes
+        let inside = |(left, top, right, bottom): (f32, f32, f32, f32), node| {
+            let (x, y) = result.node_positions[&node];
+            let (w, h) = result.node_sizes.get(&node).map_or(
+                (2.0 * style.node_radius, 2.0 * style.node_radius),
+                |&(w, h)| (w, h),
+            );
+            left <= x - w * 0.5
+                && x + w * 0.5 <= right
+                && top <= y - h * 0.5
+                && y + h * 0.5 <= bottom
+        };
+        for (cluster, component) in clusters.iter().zip(&result.components) {
+            for node in graph.node_indices() {
+                assert_eq!(inside(*cluster, node), component.nodes.contains(&node));
+            }
+        }
+
+        let unpacked = DagreLayout::new().compute(&graph).unwrap();
+        assert!(
+            to_primitives(&graph, &unpacked, &style)
+                .iter()
+                .all(|p| !matches!(p, DrawPrimitive::Cluster { .. }))
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use crate::layout::LayoutResult;
+use crate::render::{DrawPrimitive, to_primitives};
+use petgraph::prelude::*;
+use std::fmt::{Display, Write};
+
+/// Visual style used by [`render_svg`]
+#[derive(Debug, Clone)]
+pub struct SvgStyle {
+    /// Fill color of nodes
+    pub node_fill: String,
+    /// Outline color of nodes
+    pub node_stroke: String,
+    /// Outline width of nodes
+    pub node_stroke_width: f32,
+    /// Radius of nodes drawn as circles, used for nodes without a size
+    pub node_radius: f32,
+    /// Line color of edges and arrowheads
+    pub edge_stroke: String,
+    /// Line width of edges
+    pub edge_stroke_width: f32,
+    /// Font family of node labels
+    pub font_family: String,
+    /// Font size of node labels (pixels)
+    pub font_size: f32,
+    /// Color of node labels
+    pub text_color: String,
+    /// Empty space around the layout (pixels)
+    pub padding: f32,
+    /// Fill color of the boxes around packed components
+    pub cluster_fill: String,
+    /// Outline color of the boxes around packed components
+    pub cluster_stroke: String,
+    /// Outline width of the boxes around packed components
+    pub cluster_stroke_width: f32,
+    /// Space between a packed component and its box (pixels)
+    pub cluster_padding: f32,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        Self {
+            node_fill: "#e1f5fe".to_string(),
+            node_stroke: "#0277bd".to_string(),
+            node_stroke_width: 2.0,
+            node_radius: 20.0,
+            edge_stroke: "#424242".to_string(),
+            edge_stroke_width: 1.5,
+            font_family: "Arial, sans-serif".to_string(),
+            font_size: 14.0,
+            text_color: "#000000".to_string(),
+            padding: 50.0,
+            cluster_fill: "#fafafa".to_string(),
+            cluster_stroke: "#9e9e9e".to_string(),
+            cluster_stroke_width: 1.0,
+            cluster_padding: 10.0,
+        }
+    }
+}
+
+/// Render a computed layout as a standalone SVG document
+///
+/// Draws the primitives of [`to_primitives`]: nodes with a size in the
+/// layout are rectangles, all other nodes circles with the style's
+/// `node_radius`. Edges follow their `edge_points`, including the bend
+/// points of long edges, and end in an arrowhead. Node labels use the node
+/// weight's `Display` implementation. Components packed by
+/// `pack_components` are drawn as clusters, a box behind their nodes and
+/// edges.
+///
+/// # Example
+/// ```
+/// use dagrers::DagreLayout;
+/// use dagrers::render::{SvgStyle, render_svg};
+/// use petgraph::Graph;
+///
+/// let mut graph = Graph::new();
+/// let a = graph.add_node("A");
+/// let b = graph.add_node("B");
+/// graph.add_edge(a, b, ());
+///
+/// let result = DagreLayout::new().compute(&graph).unwrap();
+/// let svg = render_svg(&graph, &result, &SvgStyle::default());
+/// assert!(svg.starts_with("<?xml"));
+/// ```
+pub fn render_svg<N: Display, E>(
+    graph: &DiGraph<N, E>,
+    result: &LayoutResult,
+    style: &SvgStyle,
+) -> String {
+    let padding = style.padding;
+    let width = result.width + 2.0 * padding;
+    let height = result.height + 2.0 * padding;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<svg width=\"{width}\" height=\"{height}\" xmlns=\"http://www.w3.org/2000/svg\">
+<style>
+.node {{ fill: {}; stroke: {}; stroke-width: {}; }}
+.node-text {{ font-family: {}; font-size: {}px; fill: {}; text-anchor: middle; dominant-baseline: middle; }}
+.edge {{ fill: none; stroke: {}; stroke-width: {}; marker-end: url(#arrowhead); }}
+.cluster {{ fill: {}; stroke: {}; stroke-width: {}; }}
+</style>
+<defs>
+<marker id=\"arrowhead\" markerWidth=\"10\" markerHeight=\"7\" refX=\"9\" refY=\"3.5\" orient=\"auto\">
+<polygon points=\"0 0, 10 3.5, 0 7\" fill=\"{}\" />
+</marker>
+</defs>
+",
+        style.node_fill,
+        style.node_stroke,
+        style.node_stroke_width,
+        style.font_family,
+        style.font_size,
+        style.text_color,
+        style.edge_stroke,
+        style.edge_stroke_width,
+        style.cluster_fill,
+        style.cluster_stroke,
+        style.cluster_stroke_width,
+        style.edge_stroke,
+    );
+
+    let points = |points: &[(f32, f32)]| {
+        let points: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+        points.join(" ")
+    };
+    for primitive in to_primitives(graph, result, style) {
+        let _ = match primitive {
+            DrawPrimitive::Rect {
+                x,
+                y,
+                width,
+                height,
+                ..
+            } => writeln!(
+                svg,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" class=\"node\" />"
+            ),
+            DrawPrimitive::Circle { cx, cy, r, .. } => writeln!(
+                svg,
+                "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{r}\" class=\"node\" />"
+            ),
+            DrawPrimitive::Text { x, y, text, .. } => writeln!(
+                svg,
+                "<text x=\"{x}\" y=\"{y}\" class=\"node-text\">{}</text>",
+                escape(&text)
+            ),
+            DrawPrimitive::Line { from, to, .. } => writeln!(
+                svg,
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" class=\"edge\" />",
+                from.0, from.1, to.0, to.1
+            ),
+            DrawPrimitive::Polyline { points: line, .. } => writeln!(
+                svg,
+                "<polyline points=\"{}\" class=\"edge\" />",
+                points(&line)
+            ),
+            DrawPrimitive::Cluster {
+                x,
+                y,
+                width,
+                height,
+                ..
+            } => writeln!(
+                svg,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" class=\"cluster\" />"
+            ),
+        };
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Escape the characters that are not allowed verbatim in XML text
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::ComponentPacking;
+    use crate::layout::{DagreLayout, LayoutOptions};
+    use petgraph::Graph;
+    use std::collections::HashMap;
+
+    /// Check that every opened tag is closed in the right order
+    fn is_well_formed(xml: &str) -> bool {
+        let mut open = Vec::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find('<') {
+            let Some(end) = rest[start..].find('>') else {
+                return false;
+            };
+            let tag = &rest[start + 1..start + end];
+            rest = &rest[start + end + 1..];
+
+            if tag.starts_with('?') || tag.ends_with('/') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                if open.pop() != Some(name.trim()) {
+                    return false;
+                }
+            } else {
+                open.push(tag.split_whitespace().next().unwrap_or(""));
+            }
+        }
+        open.is_empty()
+    }
+
+    #[test]
+    fn test_render_svg_elements() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B & C");
+        let c = graph.add_node("D");
+        let d = graph.add_node("E");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        graph.add_edge(a, c, ());
+        graph.add_edge(c, d, ());
+
+        let options = LayoutOptions {
+            node_sizes: HashMap::from([(d, (80.0, 30.0))]),
+            ..Default::default()
+        };
+        let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+        let svg = render_svg(&graph, &result, &SvgStyle::default());
+
+        assert!(is_well_formed(&svg));
+        assert_eq!(svg.matches("<circle").count(), 3);
+        assert_eq!(svg.matches("<rect").count(), 1);
+        assert_eq!(svg.matches("class=\"node-text\"").count(), 4);
+        assert_eq!(svg.matches("class=\"edge\"").count(), 4);
+        assert!(svg.contains("B &amp; C"));
+        assert_eq!(svg.matches("class=\"cluster\"").count(), 0);
+
+        let options = LayoutOptions {
+            pack_components: Some(ComponentPacking::Row { gap: 40.0 }),
+            ..Default::default()
+        };
+        graph.add_node("F");
+        let result = DagreLayout::with_options(options).compute(&graph).unwrap();
+        let svg = render_svg(&graph, &result, &SvgStyle::default());
+
+        assert!(is_well_formed(&svg));
+        assert_eq!(svg.matches("class=\"cluster\"").count(), 2);
+        assert_eq!(svg.matches("class=\"node-text\"").count(), 5);
+    }
+
+    #[test]
+    fn test_render_svg_draws_bend_points() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let long = graph.add_edge(a, c, ());
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+
+        let result = DagreLayout::new().compute(&graph).unwrap();
+        let style = SvgStyle {
+            padding: 0.0,
+            ..Default::default()
+        };
+        let svg = render_svg(&graph, &result, &style);
+
+        let points: Vec<String> = result.edge_points[&long]
+            .iter()
+            .map(|(x, y)| format!("{},{}", x, y))
+            .collect();
+        assert_eq!(points.len(), 3);
+        assert!(svg.contains(&format!("points=\"{}\"", points.join(" "))));
+    }
+
+    #[test]
+    fn test_render_svg_reversed_edge_ends_at_its_target() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        let closing = graph.add_edge(c, a, ());
+
+        let result = DagreLayout::new().compute(&graph).unwrap();
+        assert!(result.reversed_edges.contains(&closing));
+        let style = SvgStyle {
+            padding: 0.0,
+            ..Default::default()
+        };
+        let svg = render_svg(&graph, &result, &style);
+
+        // The arrowhead sits on the last point, which is the original target
+        let points = &result.edge_points[&closing];
+        assert_eq!(*points.last().unwrap(), result.node_positions[&a]);
+        let points: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+        assert!(svg.contains(&format!("points=\"{}\"", points.join(" "))));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::layout::DagreLayout;
+use crate::normalize::NormalizedGraph;
+use petgraph::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Virtual point a long edge should pass near on one of the ranks it spans
+///
+/// The dummy node of the edge on that rank is pulled toward the point during
+/// crossing reduction, but it is still ordered together with the other nodes
+/// of the rank, so the edge passes through the free slot closest to it
+/// rather than through the exact coordinate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoutingConstraint {
+    /// Edge the constraint applies to
+    pub edge: EdgeIndex,
+    /// Rank the edge should pass near the point on, strictly between its endpoints
+    pub rank: usize,
+    /// Cross-axis coordinate of the point
+    pub cross: f32,
+}
+
+/// Dummy nodes pinned by routing constraints, in slot units of the widest layer
+pub(crate) struct RoutingPins {
+    slots: HashMap<NodeIndex, f32>,
+    widest: usize,
+}
+
+impl RoutingPins {
+    /// Resolve constraints to the dummy nodes of their edges
+    ///
+    /// Constraints on edges that do not span their rank are ignored.
+    pub(crate) fn new(
+        constraints: &[RoutingConstraint],
+        normalized: &NormalizedGraph,
+        layers: &[Vec<NodeIndex>],
+        node_sep: f32,
+    ) -> Self {
+        let mut slots = HashMap::new();
+        for constraint in constraints {
+            let Some(layer) = layers.get(constraint.rank) else {
+                continue;
+            };
+            let Some(chain) = normalized.chains.get(&constraint.edge) else {
+                continue;
+            };
+            if let Some(&dummy) = chain.iter().find(|dummy| layer.contains(dummy)) {
+                slots.insert(dummy, constraint.cross / node_sep);
+            }
+        }
+
+        Self {
+            slots,
+            widest: layers.iter().map(|layer| layer.len()).max().unwrap_or(0),
+        }
+    }
+
+    /// Position of a pinned node in the index space of a layer with `len` nodes
+    ///
+    /// Layers are centered against the widest one, so a layer's first slot
+    /// sits half the difference in length away from the widest layer's.
+    pub(crate) fn slot(&self, node: NodeIndex, len: usize) -> Option<f32> {
+        let slot = self.slots.get(&node)?;
+        Some(slot - (self.widest - len) as f32 * 0.5)
+    }
+}
+
+/// Edges spanning more ranks than the `highway_span` option allows
+pub(crate) fn find_highways<N, E>(
+    layout: &DagreLayout,
+    graph: &DiGraph<N, E>,
+    layers: &[Vec<NodeIndex>],
+) -> HashSet<EdgeIndex> {
+    let Some(max_span) = layout.options.highway_span else {
+        return HashSet::new();
+    };
+    let ranks: HashMap<NodeIndex, usize> = layers
+        .iter()
+        .enumerate()
+        .flat_map(|(rank, layer)| layer.iter().map(move |&node| (node, rank)))
+        .collect();
+    graph
+        .edge_references()
+        .filter(|edge| ranks[&edge.target()].saturating_sub(ranks[&edge.source()]) > max_span)
+        .map(|edge| edge.id())
+        .collect()
+}
+
+/// Route highway edges along lanes beyond the far end of the cross axis
+///
+/// Each edge leaves its source into the gap below the source rank, runs
+/// over to its own lane, follows the lane to the gap above the target rank
+/// and comes back in. Lanes are `node_sep` apart, starting at `cross_extent`,
+/// and are assigned in edge index order. Returns the cross extent including
+/// the lanes.
+pub(crate) fn route_highways<N, E>(
+    layout: &DagreLayout,
+    graph: &DiGraph<N, E>,
+    highways: &HashSet<EdgeIndex>,
+    positions: &HashMap<NodeIndex, (f32, f32)>,
+    cross_extent: f32,
+    edge_points: &mut HashMap<EdgeIndex, Vec<(f32, f32)>>,
+) -> f32 {
+    let rank_dir = layout.options.rank_dir;
+    let half_gap = layout.options.rank_sep * 0.5;
+    let mut edges: Vec<EdgeIndex> = highways.iter().copied().collect();
+    edges.sort();
+
+    for (lane, &edge) in edges.iter().enumerate() {
+        let (source, target) = graph.edge_endpoints(edge).unwrap();
+        let (from, to) = (positions[&source], positions[&target]);
+        let lane = cross_extent + lane as f32 * layout.options.node_sep;
+        let leave = rank_dir.rank(from) + half_gap;
+        let arrive = rank_dir.rank(to) - half_gap;
+
+        let mut points = vec![
+            from,
+            rank_dir.point(rank_dir.cross(from), leave),
+            rank_dir.point(lane, leave),
+            rank_dir.point(lane, arrive),
+            rank_dir.point(rank_dir.cross(to), arrive),
+            to,
+        ];
+        points[0] = layout.clip_to_node(source, from, points[1]);
+        points[5] = layout.clip_to_node(target, to, points[4]);
+        edge_points.insert(edge, points);
+    }
+
+    cross_extent + edges.len() as f32 * layout.options.node_sep
+}
+
+/// Largest difference in cross-axis slope between segments that count as parallel
+const PARALLEL_SLOPE: f32 = 0.25;
+
+/// Cross-axis coordinate and slope where a segment crosses the middle of a
+/// gap, with its edge and the index of the segment in the edge's points
+type Crossing = (f32, f32, EdgeIndex, usize);
+
+/// Pull edges crossing a gap between ranks side by side toward shared waypoints
+///
+/// Every segment running forward across the middle of the gap between two
+/// adjacent ranks is a candidate. Sorted by where they cross the middle,
+/// neighbors at most `node_sep` apart whose cross-axis slopes differ by at
+/// most [`PARALLEL_SLOPE`] form a bundle. Every edge of a bundle of two or
+/// more gets a bend point on the middle, moved from its own crossing toward
+/// the mean crossing of the bundle by `strength`, clamped to 0..=1. Node
+/// positions and the ends of edges stay put; edges in `skip` are left alone.
+pub(crate) fn bundle_edges(
+    layout: &DagreLayout,
+    layers: &[Vec<NodeIndex>],
+    skip: &HashSet<EdgeIndex>,
+    strength: f32,
+    edge_points: &mut HashMap<EdgeIndex, Vec<(f32, f32)>>,
+) {
+    let strength = strength.clamp(0.0, 1.0);
+    if strength == 0.0 {
+        return;
+    }
+    let rank_dir = layout.options.rank_dir;
+    let node_sep = layout.options.node_sep;
+    let coordinates = layout.options.rank_coordinates(layers);
+    let middles: Vec<f32> = coordinates
+        .windows(2)
+        .map(|pair| (pair[0] + pair[1]) * 0.5)
+        .collect();
+
+    // Where every segment crosses the middle of a gap, with its slope
+    let mut edges: Vec<EdgeIndex> = edge_points
+        .keys()
+        .filter(|edge| !skip.contains(edge))
+        .copied()
+        .collect();
+    edges.sort();
+    let mut crossings: Vec<Vec<Crossing>> = vec![Vec::new(); middles.len()];
+    for &edge in &edges {
+        for (segment, pair) in edge_points[&edge].windows(2).enumerate() {
+            let (from, to) = (pair[0], pair[1]);
+            let (from_rank, to_rank) = (rank_dir.rank(from), rank_dir.rank(to));
+            if to_rank <= from_rank {
+                continue;
+            }
+            let slope = (rank_dir.cross(to) - rank_dir.cross(from)) / (to_rank - from_rank);
+            for (gap, &middle) in middles.iter().enumerate() {
+                if from_rank < middle && middle < to_rank {
+                    let cross = rank_dir.cross(from) + slope * (middle - from_rank);
+                    crossings[gap].push((cross, slope, edge, segment));
+                }
+            }
+        }
+    }
+
+    let mut waypoints: HashMap<EdgeIndex, Vec<(usize, f32, f32)>> = HashMap::new();
+    for (segments, &middle) in crossings.iter_mut().zip(&middles) {
+        segments.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut start = 0;
+        for end in 1..=segments.len() {
+            if let Some(next) = segments.get(end) {
+                let previous = &segments[end - 1];
+                let close = next.0 - previous.0 <= node_sep;
+                if close && (next.1 - previous.1).abs() <= PARALLEL_SLOPE {
+                    continue;
+                }
+            }
+            let bundle = &segments[start..end];
+            start = end;
+            if bundle.len() < 2 {
+                continue;
+            }
+            let mean = bundle.iter().map(|segment| segment.0).sum::<f32>() / bundle.len() as f32;
+            for &(cross, _, edge, segment) in bundle {
+                let cross = cross + (mean - cross) * strength;
+                waypoints
+                    .entry(edge)
+                    .or_default()
+                    .push((segment, middle, cross));
+            }
+        }
+    }
+
+    // Later waypoints first, so earlier segment indices stay valid
+    for (edge, mut waypoints) in waypoints {
+        waypoints.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        let points = edge_points.get_mut(&edge).unwrap();
+        for (segment, rank, cross) in waypoints.into_iter().rev() {
+            points.insert(segment + 1, rank_dir.point(cross, rank));
+        }
+    }
+}
@@ -0,0 +1,107 @@
+/// Outline of a node, used to clip edges where they meet the node border
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeShape {
+    /// Axis-aligned rectangle filling the node's bounding box
+    #[default]
+    Rectangle,
+    /// Ellipse inscribed in the node's bounding box
+    Ellipse,
+    /// Rhombus whose corners touch the middle of each bounding box side
+    Diamond,
+}
+
+impl NodeShape {
+    /// Find where the ray from `center` toward `toward` leaves the shape
+    ///
+    /// `size` is the width and height of the node's bounding box. If the
+    /// target point coincides with the center, the center is returned.
+    pub fn clip(&self, center: (f32, f32), size: (f32, f32), toward: (f32, f32)) -> (f32, f32) {
+        let (dx, dy) = (toward.0 - center.0, toward.1 - center.1);
+        let (half_w, half_h) = (size.0 * 0.5, size.1 * 0.5);
+        if (dx == 0.0 && dy == 0.0) || half_w <= 0.0 || half_h <= 0.0 {
+            return center;
+        }
+
+        // Scale factor along the ray at which the boundary is reached
+        let scale = match self {
+            NodeShape::Rectangle => {
+                let sx = if dx != 0.0 {
+                    half_w / dx.abs()
+                } else {
+                    f32::INFINITY
+                };
+                let sy = if dy != 0.0 {
+                    half_h / dy.abs()
+                } else {
+                    f32::INFINITY
+                };
+                sx.min(sy)
+            }
+            NodeShape::Ellipse => 1.0 / ((dx / half_w).powi(2) + (dy / half_h).powi(2)).sqrt(),
+            NodeShape::Diamond => 1.0 / (dx.abs() / half_w + dy.abs() / half_h),
+        };
+
+        (center.0 + dx * scale, center.1 + dy * scale)
+    }
+}
+
+/// Side of a node's bounding box that an edge end can be attached to
+///
+/// Sides refer to the finished layout, where `y` grows downward, whatever
+/// the `rank_dir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PortSide {
+    /// The side with the smallest `y`
+    Top,
+    /// The side with the largest `y`
+    Bottom,
+    /// The side with the smallest `x`
+    Left,
+    /// The side with the largest `x`
+    Right,
+}
+
+impl PortSide {
+    /// Middle of this side of the bounding box of `size` around `center`
+    ///
+    /// Every [`NodeShape`] touches its bounding box there, so the point
+    /// lies on the node border.
+    pub fn point(&self, center: (f32, f32), size: (f32, f32)) -> (f32, f32) {
+        let (half_w, half_h) = (size.0 * 0.5, size.1 * 0.5);
+        match self {
+            PortSide::Top => (center.0, center.1 - half_h),
+            PortSide::Bottom => (center.0, center.1 + half_h),
+            PortSide::Left => (center.0 - half_w, center.1),
+            PortSide::Right => (center.0 + half_w, center.1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clip_rectangle() {
+        let point = NodeShape::Rectangle.clip((0.0, 0.0), (40.0, 20.0), (100.0, 100.0));
+        assert_eq!(point, (10.0, 10.0));
+    }
+
+    #[test]
+    fn test_clip_ellipse() {
+        let point = NodeShape::Ellipse.clip((0.0, 0.0), (40.0, 20.0), (0.0, -100.0));
+        assert_eq!(point, (0.0, -10.0));
+
+        let (x, y) = NodeShape::Ellipse.clip((0.0, 0.0), (40.0, 20.0), (30.0, 30.0));
+        assert!(((x / 20.0).powi(2) + (y / 10.0).powi(2) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_clip_diamond() {
+        let (x, y) = NodeShape::Diamond.clip((0.0, 0.0), (40.0, 40.0), (10.0, -30.0));
+        assert!((x.abs() / 20.0 + y.abs() / 20.0 - 1.0).abs() < 1e-4);
+        assert!(x.abs() < 20.0 && y.abs() < 20.0);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::layout::{DagreLayout, LayoutResult};
+use petgraph::prelude::*;
+
+/// Largest coordinate difference [`LayoutResult::approx_eq`] treats as equal
+const TOLERANCE: f32 = 1e-3;
+
+impl LayoutResult {
+    /// Whether two layouts agree up to float rounding
+    ///
+    /// Layer orders, crossing counts and edge sets must match exactly,
+    /// coordinates of nodes, edge points, edge labels and the overall size
+    /// may differ by a small tolerance.
+    pub fn approx_eq(&self, other: &LayoutResult) -> bool {
+        let close = |a: (f32, f32), b: (f32, f32)| {
+            (a.0 - b.0).abs() <= TOLERANCE && (a.1 - b.1).abs() <= TOLERANCE
+        };
+
+        self.layers == other.layers
+            && self.crossing_count == other.crossing_count
+            && self.node_sizes == other.node_sizes
+            && self.highway_edges == other.highway_edges
+            && self.reversed_edges == other.reversed_edges
+            && self.dropped_edges == other.dropped_edges
+            && self.flat_edges == other.flat_edges
+            && self.isolated_nodes == other.isolated_nodes
+            && self.wrapped_ranks == other.wrapped_ranks
+            && self.unreachable_nodes == other.unreachable_nodes
+            && self.components.len() == other.components.len()
+            && self
+                .components
+                .iter()
+                .zip(&other.components)
+                .all(|(a, b)| a.nodes == b.nodes && close(a.offset, b.offset))
+            && close((self.width, self.height), (other.width, other.height))
+            && self.node_positions.len() == other.node_positions.len()
+            && self.node_positions.iter().all(|(node, &position)| {
+                other
+                    .node_positions
+                    .get(node)
+                    .is_some_and(|&theirs| close(position, theirs))
+            })
+            && self.edge_points.len() == other.edge_points.len()
+            && self.edge_points.iter().all(|(edge, points)| {
+                other.edge_points.get(edge).is_some_and(|theirs| {
+                    points.len() == theirs.len()
+                        && points.iter().zip(theirs).all(|(&a, &b)| close(a, b))
+                })
+            })
+            && self.edge_label_positions.len() == other.edge_label_positions.len()
+            && self.edge_label_positions.iter().all(|(edge, &position)| {
+                other
+                    .edge_label_positions
+                    .get(edge)
+                    .is_some_and(|&theirs| close(position, theirs))
+            })
+    }
+}
+
+impl LayoutResult {
+    /// Compact encoding of the node order of every rank, independent of coordinates
+    ///
+    /// Ranks are separated by `|` and list their nodes by index, separated
+    /// by `,`, from the first rank to the last. Two layouts with equal
+    /// signatures have the same ranks and orderings.
+    pub fn order_signature(&self) -> String {
+        let ranks: Vec<String> = self
+            .layers
+            .iter()
+            .map(|layer| {
+                let nodes: Vec<String> =
+                    layer.iter().map(|node| node.index().to_string()).collect();
+                nodes.join(",")
+            })
+            .collect();
+        ranks.join("|")
+    }
+}
+
+impl DagreLayout {
+    /// Compute the layout `runs` times and check that every run gives the same result
+    ///
+    /// Surfaces configurations whose output depends on hash map iteration
+    /// order. Failed layouts count as deterministic only if every run fails
+    /// with the same error.
+    pub fn is_deterministic<N, E>(&self, graph: &DiGraph<N, E>, runs: usize) -> bool {
+        let first = self.compute(graph);
+        all_agree(&first, (1..runs).map(|_| self.compute(graph)))
+    }
+}
+
+/// Whether every result matches the first one
+fn all_agree<R: PartialEq + std::fmt::Debug>(
+    first: &Result<LayoutResult, R>,
+    rest: impl IntoIterator<Item = Result<LayoutResult, R>>,
+) -> bool {
+    rest.into_iter().all(|result| match (first, &result) {
+        (Ok(first), Ok(result)) => first.approx_eq(result),
+        (Err(first), Err(result)) => first == result,
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LayoutError;
+    use crate::layout::LayoutOptions;
+    use petgraph::Graph;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_is_deterministic() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        let c = graph.add_node("C");
+        let d = graph.add_node("D");
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        graph.add_edge(c, d, ());
+        graph.add_edge(a, c, ());
+
+        let layout = DagreLayout::new();
+        assert!(layout.is_deterministic(&graph, 5));
+
+        // Nudging a node by whatever a fresh hash set yields first is not
+        let base = layout.compute(&graph).unwrap();
+        let hash_ordered = || -> Result<LayoutResult, LayoutError> {
+            let offsets: HashSet<u32> = (0..64).collect();
+            let mut result = base.clone();
+            result.node_positions.get_mut(&d).unwrap().0 += *offsets.iter().next().unwrap() as f32;
+            Ok(result)
+        };
+        assert!(!all_agree(&hash_ordered(), (0..8).map(|_| hash_ordered())));
+    }
+
+    #[test]
+    fn test_order_signature_ignores_coordinates() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let x = graph.add_node("x");
+        let y = graph.add_node("y");
+        let p = graph.add_node("p");
+        let q = graph.add_node("q");
+        graph.add_edge(root, x, ());
+        graph.add_edge(root, y, ());
+        graph.add_edge(x, q, ());
+        graph.add_edge(y, p, ());
+
+        let signature = |node_sep| {
+            let options = LayoutOptions {
+                node_sep,
+                ..Default::default()
+            };
+            DagreLayout::with_options(options)
+                .compute(&graph)
+                .unwrap()
+                .order_signature()
+        };
+        assert_eq!(signature(50.0), signature(120.0));
+        assert_eq!(signature(50.0).split('|').count(), 3);
+
+        let result = LayoutResult {
+            layers: vec![vec![root], vec![y, x], vec![]],
+            ..Default::default()
+        };
+        assert_eq!(result.order_signature(), "0|2,1|");
+    }
+}
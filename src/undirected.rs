@@ -0,0 +1,124 @@
+use crate::acyclic::restore_reversed;
+use crate::error::LayoutError;
+use crate::layout::{DagreLayout, LayoutResult};
+use petgraph::prelude::*;
+use std::collections::{HashSet, VecDeque};
+
+impl DagreLayout {
+    /// Compute the layout of an undirected graph, rooted at its node with the most edges
+    ///
+    /// See [`Self::compute_undirected_from`].
+    pub fn compute_undirected<N, E>(
+        &self,
+        graph: &UnGraph<N, E>,
+    ) -> Result<LayoutResult, LayoutError> {
+        self.compute_undirected_from(graph, None)
+    }
+
+    /// Compute the layout of an undirected graph by orienting its edges away from `root`
+    ///
+    /// A breadth-first search from `root` gives every node its distance, and
+    /// each edge points from the nearer to the farther end, or from the lower
+    /// to the higher index if both are equally far, so the oriented graph is
+    /// acyclic. Without a root, and for every component the root does not
+    /// reach, the search starts from the node with the most edges, ties
+    /// going to the lowest index. Edges oriented from their second endpoint
+    /// to their first are reported in `reversed_edges`; their `edge_points`
+    /// still run from the first endpoint to the second.
+    pub fn compute_undirected_from<N, E>(
+        &self,
+        graph: &UnGraph<N, E>,
+        root: Option<NodeIndex>,
+    ) -> Result<LayoutResult, LayoutError> {
+        let distances = search_distances(graph, root);
+        let mut oriented = DiGraph::with_capacity(graph.node_count(), graph.edge_count());
+        for _ in graph.node_indices() {
+            oriented.add_node(());
+        }
+        let mut reversed = HashSet::new();
+        for edge in graph.edge_references() {
+            let (a, b) = (edge.source(), edge.target());
+            let key = |node: NodeIndex| (distances[node.index()], node);
+            if key(b) < key(a) {
+                reversed.insert(edge.id());
+                oriented.add_edge(b, a, ());
+            } else {
+                oriented.add_edge(a, b, ());
+            }
+        }
+
+        let mut result = self.compute(&oriented)?;
+        restore_reversed(&mut result, reversed);
+        Ok(result)
+    }
+}
+
+/// Breadth-first distance of every node from the root of its component
+///
+/// `root` is searched from first if it is a node of the graph, every
+/// component left unreached starts from its node with the most edges.
+fn search_distances<N, E>(graph: &UnGraph<N, E>, root: Option<NodeIndex>) -> Vec<usize> {
+    let mut distances = vec![usize::MAX; graph.node_count()];
+    let mut by_degree: Vec<NodeIndex> = graph.node_indices().collect();
+    by_degree.sort_by_key(|&node| (std::cmp::Reverse(graph.edges(node).count()), node));
+    let roots = root
+        .filter(|root| root.index() < graph.node_count())
+        .into_iter()
+        .chain(by_degree);
+
+    let mut queue = VecDeque::new();
+    for root in roots {
+        if distances[root.index()] != usize::MAX {
+            continue;
+        }
+        distances[root.index()] = 0;
+        queue.push_back(root);
+        while let Some(node) = queue.pop_front() {
+            for neighbor in graph.neighbors(node) {
+                if distances[neighbor.index()] == usize::MAX {
+                    distances[neighbor.index()] = distances[node.index()] + 1;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_undirected_graph_is_oriented_from_root() {
+        let mut graph = Graph::new_undirected();
+        let nodes: Vec<_> = (0..7).map(|i| graph.add_node(i)).collect();
+        let edges: Vec<_> = [(0, 1), (1, 2), (2, 3), (1, 4), (4, 2), (5, 6)]
+            .into_iter()
+            .map(|(a, b)| graph.add_edge(nodes[a], nodes[b], ()))
+            .collect();
+
+        // The node with the most edges roots its component
+        let layout = DagreLayout::new();
+        let result = layout.compute_undirected(&graph).unwrap();
+        let first = |result: &LayoutResult| {
+            let mut layer = result.layers[0].clone();
+            layer.sort();
+            layer
+        };
+        assert_eq!(first(&result), [nodes[1], nodes[5]]);
+        assert_eq!(result.node_ranks[&nodes[4]], 2);
+        assert_eq!(result.reversed_edges, HashSet::from([edges[0], edges[4]]));
+        let points = &result.edge_points[&edges[0]];
+        assert_eq!(points[0], result.node_positions[&nodes[0]]);
+
+        // An explicit root turns the graph around
+        let result = layout
+            .compute_undirected_from(&graph, Some(nodes[3]))
+            .unwrap();
+        assert_eq!(first(&result), [nodes[3], nodes[5]]);
+        assert_eq!(result.node_ranks[&nodes[0]], 3);
+        assert_eq!(result.layers.len(), 4);
+    }
+}
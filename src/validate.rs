@@ -0,0 +1,236 @@
+use crate::layout::{DagreLayout, LayoutResult};
+use petgraph::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Broken invariant found by [`DagreLayout::validate_layout`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutViolation {
+    /// A node of the graph has no position
+    MissingPosition {
+        /// The unplaced node
+        node: NodeIndex,
+    },
+    /// A node of the graph is in no layer or in more than one
+    LayerMembership {
+        /// The misplaced node
+        node: NodeIndex,
+        /// Number of layers containing the node
+        count: usize,
+    },
+    /// An edge does not point to a later layer, a reversed edge not to an
+    /// earlier one or a flat edge not within its layer
+    EdgeDirection {
+        /// The offending edge
+        edge: EdgeIndex,
+    },
+    /// A node is off the rank-axis coordinate of its layer
+    RankCoordinate {
+        /// The displaced node
+        node: NodeIndex,
+        /// Rank-axis coordinate of the node's layer
+        expected: f32,
+        /// Rank-axis coordinate of the node
+        actual: f32,
+    },
+    /// Two nodes of a layer are closer than `node_sep` along the cross axis
+    Overlap {
+        /// The node with the lower cross-axis coordinate
+        first: NodeIndex,
+        /// The node with the higher cross-axis coordinate
+        second: NodeIndex,
+    },
+    /// A node lies outside the layout's width and height
+    OutOfBounds {
+        /// The outlying node
+        node: NodeIndex,
+    },
+}
+
+impl fmt::Display for LayoutViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutViolation::MissingPosition { node } => {
+                write!(f, "node {} has no position", node.index())
+            }
+            LayoutViolation::LayerMembership { node, count } => {
+                write!(f, "node {} is in {} layers", node.index(), count)
+            }
+            LayoutViolation::EdgeDirection { edge } => {
+                write!(f, "edge {} points against the rank order", edge.index())
+            }
+            LayoutViolation::RankCoordinate {
+                node,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "node {} is at rank coordinate {} instead of {}",
+                node.index(),
+                actual,
+                expected
+            ),
+            LayoutViolation::Overlap { first, second } => write!(
+                f,
+                "nodes {} and {} are closer than the node separation",
+                first.index(),
+                second.index()
+            ),
+            LayoutViolation::OutOfBounds { node } => {
+                write!(f, "node {} lies outside the layout", node.index())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayoutViolation {}
+
+impl DagreLayout {
+    /// Check that a layout computed with these options satisfies the layout invariants
+    ///
+    /// Every node must be placed on exactly one layer, every edge must point
+    /// to a later layer, except for reversed and flat edges, and every node
+    /// must sit on its layer's rank-axis coordinate. Along the cross axis
+    /// only the separation of nodes within a layer and the layout bounds are
+    /// checked, since placement phases are free to shift nodes sideways.
+    /// Coordinates may be off by up to `tolerance`.
+    pub fn validate_layout<N, E>(
+        &self,
+        graph: &DiGraph<N, E>,
+        result: &LayoutResult,
+        tolerance: f32,
+    ) -> Result<(), LayoutViolation> {
+        let rank_dir = self.options.rank_dir;
+
+        let mut layer_of: HashMap<NodeIndex, usize> = HashMap::new();
+        let mut membership = vec![0; graph.node_count()];
+        for (layer_idx, layer) in result.layers.iter().enumerate() {
+            for &node in layer {
+                layer_of.insert(node, layer_idx);
+                if let Some(count) = membership.get_mut(node.index()) {
+                    *count += 1;
+                }
+            }
+        }
+        for node in graph.node_indices() {
+            if !result.node_positions.contains_key(&node) {
+                return Err(LayoutViolation::MissingPosition { node });
+            }
+            let count = membership[node.index()];
+            if count != 1 {
+                return Err(LayoutViolation::LayerMembership { node, count });
+            }
+        }
+
+        for edge in graph.edge_references() {
+            if edge.source() == edge.target() || result.dropped_edges.contains(&edge.id()) {
+                continue;
+            }
+            let (source, target) = (layer_of[&edge.source()], layer_of[&edge.target()]);
+            let forward = if result.flat_edges.contains(&edge.id()) {
+                source == target
+            } else if result.reversed_edges.contains(&edge.id()) {
+                target < source
+            } else {
+                source < target
+            };
+            if !forward {
+                return Err(LayoutViolation::EdgeDirection { edge: edge.id() });
+            }
+        }
+
+        let margin = rank_dir.rank((self.options.margin_x, self.options.margin_y));
+        let coordinates = self.options.final_rank_coordinates(&result.layers);
+        for (layer, &coordinate) in result.layers.iter().zip(&coordinates) {
+            let expected = coordinate + margin;
+            for &node in layer {
+                let position = result.node_positions[&node];
+                let actual = rank_dir.rank(position);
+                if (actual - expected).abs() > tolerance {
+                    return Err(LayoutViolation::RankCoordinate {
+                        node,
+                        expected,
+                        actual,
+                    });
+                }
+                let (x, y) = position;
+                if x < -tolerance
+                    || y < -tolerance
+                    || x > result.width + tolerance
+                    || y > result.height + tolerance
+                {
+                    return Err(LayoutViolation::OutOfBounds { node });
+                }
+            }
+
+            let mut by_cross: Vec<(f32, NodeIndex)> = layer
+                .iter()
+                .map(|&node| (rank_dir.cross(result.node_positions[&node]), node))
+                .collect();
+            by_cross.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for pair in by_cross.windows(2) {
+                if pair[1].0 - pair[0].0 < self.options.node_sep - tolerance {
+                    return Err(LayoutViolation::Overlap {
+                        first: pair[0].1,
+                        second: pair[1].1,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutOptions;
+    use petgraph::Graph;
+
+    #[test]
+    fn test_validate_layout_tolerance() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("root");
+        let leaves: Vec<_> = (0..3).map(|_| graph.add_node("leaf")).collect();
+        for &leaf in &leaves {
+            graph.add_edge(root, leaf, ());
+        }
+        let tail = graph.add_node("tail");
+        graph.add_edge(leaves[0], tail, ());
+
+        // Sideways refinements keep ranks but move nodes off the centered grid
+        let layout = DagreLayout::with_options(LayoutOptions {
+            pull_weak_nodes: true,
+            align_spine: true,
+            ..Default::default()
+        });
+        let mut result = layout.compute(&graph).unwrap();
+        assert_eq!(layout.validate_layout(&graph, &result, 0.5), Ok(()));
+
+        // Slightly off positions pass only with a matching tolerance
+        result.node_positions.get_mut(&tail).unwrap().1 += 0.25;
+        assert_eq!(layout.validate_layout(&graph, &result, 0.5), Ok(()));
+        assert!(matches!(
+            layout.validate_layout(&graph, &result, 0.01),
+            Err(LayoutViolation::RankCoordinate { node, .. }) if node == tail
+        ));
+
+        // Moving a node onto its neighbor is caught regardless
+        let neighbor = result.node_positions[&leaves[1]];
+        result.node_positions.insert(leaves[2], neighbor);
+        assert!(matches!(
+            layout.validate_layout(&graph, &result, 0.5),
+            Err(LayoutViolation::Overlap { .. })
+        ));
+
+        result.layers[1].retain(|&node| node != leaves[0]);
+        assert_eq!(
+            layout.validate_layout(&graph, &result, 0.5),
+            Err(LayoutViolation::LayerMembership {
+                node: leaves[0],
+                count: 0
+            })
+        );
+    }
+}